@@ -1,24 +1,128 @@
+use crate::domain::ban::Geocoder;
 use crate::domain::models::ISO20022Address;
-use crate::domain::repository::AddressRepository;
+use crate::domain::repository::{AddressRepository, RepositoryError};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Outcome of `AddressCommandService::import_addresses`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportSummary {
+    pub added: usize,     // New addresses
+    pub merged: usize,    // Already stored; source references appended
+    pub unchanged: usize, // Already stored with the same source references
+}
 
 pub struct AddressCommandService {
     repository: Box<dyn AddressRepository>,
+    geocoder: Option<Arc<dyn Geocoder>>,
 }
 
 impl AddressCommandService {
     pub fn new(repository: Box<dyn AddressRepository>) -> Self {
-        Self { repository }
+        Self {
+            repository,
+            geocoder: None,
+        }
+    }
+
+    /// Geocodes every added or updated address with the given source.
+    pub fn with_geocoder(mut self, geocoder: Arc<dyn Geocoder>) -> Self {
+        self.geocoder = Some(geocoder);
+        self
     }
 
-    pub fn add_address(&mut self, address: ISO20022Address) -> Result<(), String> {
+    pub fn add_address(&mut self, mut address: ISO20022Address) -> Result<(), RepositoryError> {
+        self.apply_geocoding(&mut address);
         self.repository.save(address)
     }
 
-    pub fn delete_address(&mut self, address_id: &str) -> Result<(), String> {
+    /// Stores a batch of new addresses in one repository call.
+    pub fn add_addresses(
+        &mut self,
+        mut addresses: Vec<ISO20022Address>,
+    ) -> Result<(), RepositoryError> {
+        for address in addresses.iter_mut() {
+            self.apply_geocoding(address);
+        }
+        self.repository.save_all(addresses)
+    }
+
+    pub fn delete_address(&mut self, address_id: &str) -> Result<(), RepositoryError> {
         self.repository.delete(address_id)
     }
 
-    pub fn update_address(&mut self, address: ISO20022Address) -> Result<(), String> {
+    pub fn update_address(&mut self, mut address: ISO20022Address) -> Result<(), RepositoryError> {
+        self.apply_geocoding(&mut address);
         self.repository.update(address)
     }
+
+    /// Stores imported addresses, skipping those already in `existing` (or
+    /// earlier in the batch) according to `ISO20022Address::dedup_key`. The
+    /// `source_references` of a duplicate are appended to the stored record;
+    /// new addresses without an id get one.
+    pub fn import_addresses(
+        &mut self,
+        addresses: Vec<ISO20022Address>,
+        existing: Vec<ISO20022Address>,
+    ) -> Result<ImportSummary, RepositoryError> {
+        let mut known: HashMap<String, ISO20022Address> = existing
+            .into_iter()
+            .map(|address| (address.dedup_key(), address))
+            .collect();
+        let mut summary = ImportSummary::default();
+
+        for mut address in addresses {
+            if address.id.is_empty() {
+                address.id = Uuid::new_v4().to_string();
+            }
+            let key = address.dedup_key();
+            match known.get_mut(&key) {
+                Some(stored) => {
+                    let new_references: Vec<String> = address
+                        .source_references
+                        .into_iter()
+                        .filter(|r| !stored.source_references.contains(r))
+                        .collect();
+                    if new_references.is_empty() {
+                        summary.unchanged += 1;
+                        continue;
+                    }
+                    stored.source_references.extend(new_references);
+                    self.repository.update(stored.clone())?;
+                    summary.merged += 1;
+                }
+                None => {
+                    self.add_address(address.clone())?;
+                    known.insert(key, address);
+                    summary.added += 1;
+                }
+            }
+        }
+        Ok(summary)
+    }
+
+    pub fn has_geocoder(&self) -> bool {
+        self.geocoder.is_some()
+    }
+
+    /// Geocodes an already stored address and saves the result; without a
+    /// geocoder (see `has_geocoder`) the address is returned as is.
+    pub fn geocode_address(
+        &mut self,
+        mut address: ISO20022Address,
+    ) -> Result<ISO20022Address, RepositoryError> {
+        if self.geocoder.is_none() {
+            return Ok(address);
+        }
+        self.apply_geocoding(&mut address);
+        self.repository.update(address.clone())?;
+        Ok(address)
+    }
+
+    fn apply_geocoding(&self, address: &mut ISO20022Address) {
+        if let Some(geocoder) = &self.geocoder {
+            address.geolocation = geocoder.geocode(address);
+        }
+    }
 }
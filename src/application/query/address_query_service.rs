@@ -1,5 +1,6 @@
 use crate::domain::models::ISO20022Address;
-use crate::domain::repository::ReadAddressRepository;
+use crate::domain::repository::{AddressStream, ReadAddressRepository, RepositoryError};
+use crate::domain::search::{AddressPage, AddressQuery};
 
 pub struct AddressQueryService {
     repository: Box<dyn ReadAddressRepository>,
@@ -10,11 +11,23 @@ impl AddressQueryService {
         Self { repository }
     }
 
-    pub fn get_address(&self, address_id: &str) -> Option<ISO20022Address> {
+    pub fn get_address(
+        &self,
+        address_id: &str,
+    ) -> Result<Option<ISO20022Address>, RepositoryError> {
         self.repository.find_by_id(address_id)
     }
 
-    pub fn get_all_addresses(&self) -> Vec<ISO20022Address> {
+    pub fn get_all_addresses(&self) -> Result<Vec<ISO20022Address>, RepositoryError> {
         self.repository.find_all()
     }
+
+    pub fn stream_all_addresses(&self) -> AddressStream {
+        self.repository.stream_all()
+    }
+
+    /// Searches with a query checked by `AddressQuery::validate`.
+    pub fn search_addresses(&self, query: &AddressQuery) -> Result<AddressPage, RepositoryError> {
+        self.repository.search(query)
+    }
 }
@@ -0,0 +1,17 @@
+use crate::domain::ban::{BanIndex, VerificationResult};
+use crate::domain::models::ISO20022Address;
+use std::sync::Arc;
+
+pub struct AddressVerificationService {
+    index: Arc<BanIndex>,
+}
+
+impl AddressVerificationService {
+    pub fn new(index: Arc<BanIndex>) -> Self {
+        Self { index }
+    }
+
+    pub fn verify_address(&self, address: &ISO20022Address) -> VerificationResult {
+        self.index.verify(address)
+    }
+}
@@ -1 +1,2 @@
-pub mod address_query_service;
\ No newline at end of file
+pub mod address_query_service;
+pub mod address_verification_service;
\ No newline at end of file
@@ -1,9 +1,49 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 
-use crate::application::command::address_command_service::AddressCommandService;
+use crate::application::command::address_command_service::{AddressCommandService, ImportSummary};
 use crate::application::query::address_query_service::AddressQueryService;
-use crate::domain::models::{AddressKind, FrenchAddressBuilder, ISO20022Address};
-use crate::domain::usecases::{convert_to_french, convert_to_iso};
+use crate::application::query::address_verification_service::AddressVerificationService;
+use crate::cli::output::{CliError, OutputFormat, fields_table};
+use crate::cli::wizard::AddressWizard;
+use crate::domain::conversion::to_checked_iso;
+use crate::domain::formats::en16931::InvoiceAddress;
+use crate::domain::formats::swift_mt::{from_mt_free_format, from_mt_structured, to_mt_structured};
+use crate::domain::formats::swiss_qr::QrBillAddress;
+use crate::domain::formats::vcard::{VCardVersion, parse_vcards, to_vcard};
+use crate::domain::formats::{
+    AddressLines, FrenchFormat, NationalAddressFormat, SUPPORTED_COUNTRIES, format_for_country,
+};
+use crate::domain::geojson::to_geojson;
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::domain::postal_reference::PostalReference;
+use crate::domain::repository::{RepositoryError, until_error};
+use crate::domain::search::{AddressQuery, DEFAULT_PAGE_SIZE};
+use crate::domain::validation::{
+    ValidationProfile, validate_iso20022_address, validate_with_profile,
+};
+use crate::infrastructure::bulk_export::address_exporter::{
+    ExportFormat, ExportLayout, ExportOptions, export_addresses,
+};
+use crate::infrastructure::bulk_import::address_reader::{
+    read_french_json, read_iso_csv, read_iso_json,
+};
+use crate::infrastructure::bulk_import::csv_importer::{
+    CsvAddressReader, CsvImportOptions, RejectedRow, convert_lines, import_csv,
+};
+use crate::infrastructure::invoice_xml::postal_address::{
+    InvoiceSyntax, invoice_address_xml, read_invoice_addresses,
+};
+use crate::infrastructure::iso20022_xml::audit::audit_directory;
+use crate::infrastructure::iso20022_xml::payment_rewriter::{
+    RewriteOutcome, rewrite_payment_document,
+};
+use crate::infrastructure::iso20022_xml::postal_address::read_postal_addresses;
+use crate::infrastructure::iso20022_xml::statement_parties::parse_statement_parties;
+use crate::infrastructure::reference::ban_importer::{import_ban_directory, save_ban_index};
+use serde_json::{Value, json};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Parser)]
@@ -11,27 +51,125 @@ use uuid::Uuid;
 pub struct CliCqrs {
     #[command(subcommand)]
     pub command: CommandsCqrs,
+    /// Format of the addresses, results and errors printed by every command;
+    /// files and standard documents (GeoJSON, vCard, MT...) keep their own.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+/// The seven envelope lines of a postal address, laid out as in NF Z10-011.
+#[derive(Args, Clone, Default)]
+pub struct AddressLinesArgs {
+    #[arg(short = 'a', long)]
+    pub line1: Option<String>,
+    #[arg(short = 'b', long)]
+    pub line2: Option<String>,
+    #[arg(short = 'c', long)]
+    pub line3: Option<String>,
+    #[arg(short = 'd', long)]
+    pub line4: Option<String>,
+    #[arg(short = 'e', long)]
+    pub line5: Option<String>,
+    #[arg(short = 'f', long)]
+    pub line6: Option<String>,
+    #[arg(short = 'g', long)]
+    pub line7: Option<String>,
+}
+
+impl AddressLinesArgs {
+    fn is_empty(&self) -> bool {
+        [
+            &self.line1,
+            &self.line2,
+            &self.line3,
+            &self.line4,
+            &self.line5,
+            &self.line6,
+            &self.line7,
+        ]
+        .iter()
+        .all(|line| line.is_none())
+    }
+
+    fn into_lines(self, id: String) -> AddressLines {
+        AddressLines {
+            id,
+            lines: [
+                self.line1, self.line2, self.line3, self.line4, self.line5, self.line6, self.line7,
+            ],
+        }
+    }
+}
+
+/// Criteria of `search`; an address must match all those given.
+#[derive(Args, Clone, Default)]
+pub struct SearchArgs {
+    /// Start of the postal code, as 75 or 75008.
+    #[arg(long)]
+    pub post_code: Option<String>,
+    /// Whole town name, any case.
+    #[arg(long)]
+    pub town: Option<String>,
+    /// ISO 3166 alpha-2 code.
+    #[arg(long)]
+    pub country: Option<String>,
+    #[arg(short, long)]
+    pub kind: Option<String>,
+    /// Part of the recipient name, any case.
+    #[arg(long)]
+    pub recipient_name: Option<String>,
+    /// Part of the street name, any case.
+    #[arg(long)]
+    pub street_name: Option<String>,
+    /// id, recipient_name, post_code, town_name, country or street_name.
+    #[arg(long, default_value = "id")]
+    pub sort: String,
+    #[arg(long)]
+    pub desc: bool,
+    /// Page to print, from 1; all the matching addresses without it or --page-size.
+    #[arg(long)]
+    pub page: Option<usize>,
+    #[arg(long)]
+    pub page_size: Option<usize>,
+}
+
+impl SearchArgs {
+    fn query(self) -> Result<AddressQuery, CliError> {
+        let page_size = match (self.page, self.page_size) {
+            (None, None) => None,
+            (_, size) => Some(size.unwrap_or(DEFAULT_PAGE_SIZE)),
+        };
+        let query = AddressQuery {
+            post_code: self.post_code,
+            town: self.town,
+            country: self.country,
+            kind: self.kind.as_deref().map(parse_kind).transpose()?,
+            recipient_name: self.recipient_name,
+            street_name: self.street_name,
+            sort: self.sort.parse().map_err(CliError::Usage)?,
+            descending: self.desc,
+            page: self.page.unwrap_or(1),
+            page_size,
+        };
+        query.validate().map_err(CliError::Usage)?;
+        Ok(query)
+    }
 }
 
 #[derive(Subcommand)]
 pub enum CommandsCqrs {
     Add {
-        #[arg(short, long)]
-        kind: String,
-        #[arg(short = 'a', long)]
-        line1: Option<String>,
-        #[arg(short = 'b', long)]
-        line2: Option<String>,
-        #[arg(short = 'c', long)]
-        line3: Option<String>,
-        #[arg(short = 'd', long)]
-        line4: Option<String>,
-        #[arg(short = 'e', long)]
-        line5: Option<String>,
-        #[arg(short = 'f', long)]
-        line6: Option<String>,
-        #[arg(short = 'g', long)]
-        line7: Option<String>,
+        #[arg(short, long, required_unless_present = "interactive")]
+        kind: Option<String>,
+        /// Postal layout of the lines: FR, BE or DE.
+        #[arg(long, default_value = "FR")]
+        country: String,
+        #[command(flatten)]
+        lines: AddressLinesArgs,
+        /// Asks the French lines one by one, the given ones as defaults, and
+        /// shows the ISO 20022 address before saving it.
+        #[arg(long)]
+        interactive: bool,
     },
 
     Update {
@@ -39,20 +177,19 @@ pub enum CommandsCqrs {
         id: String,
         #[arg(short, long)]
         kind: String,
-        #[arg(short = 'a', long)]
-        line1: Option<String>,
-        #[arg(short = 'b', long)]
-        line2: Option<String>,
-        #[arg(short = 'c', long)]
-        line3: Option<String>,
-        #[arg(short = 'd', long)]
-        line4: Option<String>,
-        #[arg(short = 'e', long)]
-        line5: Option<String>,
-        #[arg(short = 'f', long)]
-        line6: Option<String>,
-        #[arg(short = 'g', long)]
-        line7: Option<String>,
+        #[arg(long, default_value = "FR")]
+        country: String,
+        #[command(flatten)]
+        lines: AddressLinesArgs,
+    },
+    /// Asks again the French lines of a stored address, the stored ones as
+    /// defaults, and shows the ISO 20022 address before saving it.
+    Edit {
+        #[arg(short, long)]
+        id: String,
+        /// Keeps the stored kind without asking.
+        #[arg(short, long)]
+        kind: Option<String>,
     },
     Delete {
         #[arg(short, long)]
@@ -63,209 +200,1520 @@ pub enum CommandsCqrs {
         id: String,
     },
     List,
+    /// Lists the stored addresses matching criteria, sorted and paged.
+    Search {
+        #[command(flatten)]
+        criteria: SearchArgs,
+    },
     Convert {
         #[arg(short, long)]
         id: String,
+        /// Postal layout to print; defaults to the address's own country when supported, else FR.
+        #[arg(long)]
+        country: Option<String>,
+    },
+    /// Builds the local BAN index from per-département `adresses-XX.csv` exports.
+    BanImport {
+        #[arg(long)]
+        dir: String,
+        #[arg(long = "departement")]
+        departements: Vec<String>,
+        #[arg(long, default_value = "ban_index.ndjson")]
+        index: String,
+    },
+    /// Looks a stored address up in the BAN index (see `BAN_INDEX_PATH`).
+    Verify {
+        #[arg(short, long)]
+        id: String,
+    },
+    /// Fills in the coordinates of stored addresses from the BAN index.
+    Geocode {
+        #[arg(short, long, required_unless_present = "all")]
+        id: Option<String>,
+        #[arg(long, conflicts_with = "id")]
+        all: bool,
+    },
+    /// Prints the geocoded addresses as a GeoJSON FeatureCollection.
+    Geojson,
+    /// Prints a stored address as the numbered lines of MT 50F / 59F.
+    MtExport {
+        #[arg(short, long)]
+        id: String,
+    },
+    /// Adds an address read from the lines of an MT party field
+    /// (50F / 59F, or 50K / 59 with `--free-format`).
+    MtImport {
+        #[arg(short, long)]
+        kind: String,
+        #[arg(long = "line", required = true)]
+        lines: Vec<String>,
+        #[arg(long)]
+        free_format: bool,
+    },
+    /// Prints a stored address as the address block of a Swiss QR-bill (type S).
+    QrExport {
+        #[arg(short, long)]
+        id: String,
+    },
+    /// Adds an address read from the seven lines of a QR-bill address block
+    /// (`S`, name, street, building number, postcode, town, country).
+    QrImport {
+        #[arg(short, long)]
+        kind: String,
+        #[arg(long = "line", required = true, allow_hyphen_values = true)]
+        lines: Vec<String>,
+    },
+    /// Prints stored addresses as vCards (all of them without `--id`).
+    VcardExport {
+        #[arg(short, long)]
+        id: Option<String>,
+        /// vCard version: 3.0 or 4.0.
+        #[arg(long = "vcard-version", default_value = "4.0")]
+        version: String,
+    },
+    /// Stores the postal address of every contact of a .vcf file, merging
+    /// those already stored.
+    VcardImport {
+        #[arg(long)]
+        file: String,
+    },
+    /// Rewrites the AdrLine-only French postal addresses of a pain.001 or
+    /// pacs.008 file as structured addresses.
+    RewritePayments {
+        #[arg(long)]
+        input: String,
+        #[arg(long)]
+        output: String,
+    },
+    /// Reports the structure and validity of the party addresses of every
+    /// pain.001, pacs.008 and camt.053 file of a directory, without modifying them.
+    Audit {
+        #[arg(long)]
+        dir: String,
+        /// Validation profile: basic, cbpr or sepa.
+        #[arg(long, default_value = "basic")]
+        profile: String,
+        /// Writes the JSON report to this file instead of stdout.
+        #[arg(long)]
+        json: Option<String>,
+        /// Writes addresses.csv, files.csv and counterparties.csv to this directory.
+        #[arg(long)]
+        csv_dir: Option<String>,
+    },
+    /// Stores the debtor, creditor and ultimate party addresses of camt.053 and
+    /// camt.054 files, merging those already stored.
+    ImportStatements {
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    /// Prints a stored address as a UBL `cac:PostalAddress` or CII
+    /// `ram:PostalTradeAddress` element.
+    InvoiceExport {
+        #[arg(short, long)]
+        id: String,
+        /// Invoice syntax: ubl or cii.
+        #[arg(long, default_value = "ubl")]
+        syntax: String,
+    },
+    /// Stores the party addresses of a UBL or CII (Factur-X) invoice, merging
+    /// those already stored.
+    InvoiceImport {
+        #[arg(long)]
+        file: String,
+        #[arg(short, long, default_value = "company")]
+        kind: String,
+    },
+    /// Stores the French addresses of a CSV file, one per row, and writes the
+    /// rows failing validation with their error to a rejects file.
+    Import {
+        #[arg(long)]
+        file: String,
+        #[arg(long, default_value = ";")]
+        delimiter: String,
+        /// Encoding of the file: utf-8, windows-1252, iso-8859-15...
+        #[arg(long, default_value = "utf-8")]
+        encoding: String,
+        /// Column feeding a line, as `line4=Adresse`; lines default to the
+        /// `line1` to `line7` columns.
+        #[arg(long = "map")]
+        mappings: Vec<String>,
+        #[arg(long, default_value = "kind")]
+        kind_column: String,
+        /// Kind of the rows with no kind column value: company, particular or auto.
+        #[arg(short, long, default_value = "auto")]
+        kind: String,
+        /// Defaults to the input file name followed by `.rejects.csv`.
+        #[arg(long)]
+        rejects: Option<String>,
+    },
+    /// Writes all stored addresses as CSV or NDJSON, to stdout without `--file`.
+    Export {
+        /// csv or ndjson.
+        #[arg(long, default_value = "csv")]
+        format: String,
+        /// iso (structured fields) or french (seven lines).
+        #[arg(long, default_value = "iso")]
+        layout: String,
+        /// Comma-separated columns, in output order; all by default.
+        #[arg(long)]
+        columns: Option<String>,
+        #[arg(long)]
+        file: Option<String>,
+    },
+    /// Converts French addresses (`line1` to `line7`, optional `id` and
+    /// `kind`) read from stdin or `--file` to ISO 20022 on stdout, without
+    /// storing them.
+    ToIso {
+        #[arg(long)]
+        file: Option<String>,
+        /// ndjson (one JSON object per address) or csv.
+        #[arg(long, default_value = "ndjson")]
+        input_format: String,
+        /// ndjson or csv.
+        #[arg(long, default_value = "ndjson")]
+        format: String,
+        #[arg(long)]
+        columns: Option<String>,
+        /// CSV delimiter, in and out.
+        #[arg(long, default_value = ";")]
+        delimiter: String,
+        /// Column feeding a line of CSV input, as `line4=Adresse`.
+        #[arg(long = "map")]
+        mappings: Vec<String>,
+        /// Kind of the addresses without one: company, particular or auto.
+        #[arg(short, long, default_value = "auto")]
+        kind: String,
+    },
+    /// Converts ISO 20022 addresses (as written by `export --layout iso`) read
+    /// from stdin or `--file` to the seven French lines on stdout, without
+    /// storing them.
+    ToFrench {
+        #[arg(long)]
+        file: Option<String>,
+        #[arg(long, default_value = "ndjson")]
+        input_format: String,
+        #[arg(long, default_value = "ndjson")]
+        format: String,
+        #[arg(long)]
+        columns: Option<String>,
+        #[arg(long, default_value = ";")]
+        delimiter: String,
+    },
+    /// Checks addresses without storing them: the French lines given as
+    /// options, or else the records read from stdin or `--file`. Fails when
+    /// any of them is invalid.
+    Validate {
+        #[command(flatten)]
+        lines: AddressLinesArgs,
+        #[arg(long)]
+        file: Option<String>,
+        /// ndjson, csv or xml (every `PstlAdr` of the document).
+        #[arg(long, default_value = "ndjson")]
+        input_format: String,
+        /// Records of French lines (`line1` to `line7`) or of ISO 20022
+        /// fields (`iso`); ignored for xml.
+        #[arg(long, default_value = "french")]
+        layout: String,
+        /// Validation profile: basic, cbpr or sepa.
+        #[arg(long, default_value = "basic")]
+        profile: String,
+        #[arg(long, default_value = ";")]
+        delimiter: String,
+        /// Kind of the French addresses without one: company, particular or auto.
+        #[arg(short, long, default_value = "auto")]
+        kind: String,
     },
 }
 
+/// Runs the commands that need no repository (`to-iso`, `to-french`,
+/// `validate`) and hands the others back. French lines are checked against
+/// `postal_reference` when loaded.
+pub fn run_stateless(
+    cli: CliCqrs,
+    postal_reference: Option<&Arc<PostalReference>>,
+) -> Result<Option<CliCqrs>, CliError> {
+    let out = cli.output;
+    match cli.command {
+        CommandsCqrs::ToIso {
+            file,
+            input_format,
+            format,
+            columns,
+            delimiter,
+            mappings,
+            kind,
+        } => {
+            let options = mappings
+                .iter()
+                .try_fold(
+                    CsvImportOptions {
+                        postal_reference: postal_reference.cloned(),
+                        ..CsvImportOptions::default()
+                    },
+                    |options, mapping| options.with_mapping(mapping),
+                )
+                .and_then(|o| o.with_delimiter(&delimiter))
+                .and_then(|o| o.with_default_kind(&kind))
+                .map_err(CliError::Usage)?;
+            convert_to_iso_stream(
+                out,
+                file.as_deref(),
+                &input_format,
+                &format,
+                columns.as_deref(),
+                &options,
+            )?;
+            Ok(None)
+        }
+        CommandsCqrs::ToFrench {
+            file,
+            input_format,
+            format,
+            columns,
+            delimiter,
+        } => {
+            convert_to_french_stream(
+                out,
+                file.as_deref(),
+                &input_format,
+                &format,
+                columns.as_deref(),
+                &delimiter,
+            )?;
+            Ok(None)
+        }
+        CommandsCqrs::Validate {
+            lines,
+            file,
+            input_format,
+            layout,
+            profile,
+            delimiter,
+            kind,
+        } => {
+            let options = CsvImportOptions {
+                postal_reference: postal_reference.cloned(),
+                ..CsvImportOptions::default()
+            }
+            .with_delimiter(&delimiter)
+            .and_then(|o| o.with_default_kind(&kind))
+            .map_err(CliError::Usage)?;
+            let profile = profile
+                .parse::<ValidationProfile>()
+                .map_err(CliError::Usage)?;
+            let checks = if lines.is_empty() {
+                check_records(file.as_deref(), &input_format, &layout, profile, &options)?
+            } else {
+                let lines = lines.into_lines(String::new());
+                let name = lines.lines[0].clone();
+                let error = convert_lines(
+                    None,
+                    lines.lines,
+                    None,
+                    options.default_kind,
+                    postal_reference.map(Arc::as_ref),
+                )
+                .map_or_else(Some, |iso| check_profile(&iso, &[], profile));
+                vec![RecordCheck {
+                    record: 1,
+                    name,
+                    error,
+                }]
+            };
+            report_checks(out, profile, &checks)?;
+            Ok(None)
+        }
+        command => Ok(Some(CliCqrs { command, ..cli })),
+    }
+}
+
 pub fn run_cqrs(
     cli: CliCqrs,
     command_service: &mut AddressCommandService,
     query_service: &AddressQueryService,
-) {
+    verification_service: Option<&AddressVerificationService>,
+    postal_reference: Option<&Arc<PostalReference>>,
+) -> Result<(), CliError> {
+    let Some(cli) = run_stateless(cli, postal_reference)? else {
+        return Ok(());
+    };
+    let out = cli.output;
     match cli.command {
         CommandsCqrs::Add {
             kind,
-            line1,
-            line2,
-            line3,
-            line4,
-            line5,
-            line6,
-            line7,
-        } => add_address(
+            country,
+            lines,
+            interactive: true,
+        } => add_address_interactive(
             command_service,
+            out,
             kind,
-            line1,
-            line2,
-            line3,
-            line4,
-            line5,
-            line6,
-            line7,
+            country,
+            lines,
+            postal_reference.cloned(),
+        ),
+        CommandsCqrs::Add {
+            kind,
+            country,
+            lines,
+            interactive: false,
+        } => add_address(
+            command_service,
+            out,
+            kind.unwrap_or_default(),
+            country,
+            lines,
+            postal_reference.map(Arc::as_ref),
         ),
 
         CommandsCqrs::Update {
             id,
             kind,
-            line1,
-            line2,
-            line3,
-            line4,
-            line5,
-            line6,
-            line7,
+            country,
+            lines,
         } => update_address(
             command_service,
             query_service,
+            out,
+            kind,
+            country,
+            lines.into_lines(id),
+            postal_reference.map(Arc::as_ref),
+        ),
+
+        CommandsCqrs::Edit { id, kind } => edit_address(
+            command_service,
+            query_service,
+            out,
             id,
             kind,
-            line1,
-            line2,
-            line3,
-            line4,
-            line5,
-            line6,
-            line7,
+            postal_reference.cloned(),
         ),
 
-        CommandsCqrs::Delete { id } => delete_address(command_service, id),
+        CommandsCqrs::Delete { id } => delete_address(command_service, query_service, out, id),
 
-        CommandsCqrs::Get { id } => get_address(query_service, id),
-        CommandsCqrs::List => list_addresses(query_service),
-        CommandsCqrs::Convert { id } => convert_address(query_service, id),
+        CommandsCqrs::Get { id } => get_address(query_service, out, id),
+        CommandsCqrs::List => {
+            out.print_addresses(&all_addresses(query_service)?);
+            Ok(())
+        }
+        CommandsCqrs::Search { criteria } => search_addresses(query_service, out, criteria),
+        CommandsCqrs::Convert { id, country } => convert_address(query_service, out, id, country),
+        CommandsCqrs::BanImport {
+            dir,
+            departements,
+            index,
+        } => import_ban(out, &dir, &departements, &index),
+        CommandsCqrs::Verify { id } => verify_address(query_service, verification_service, out, id),
+        CommandsCqrs::Geocode { id, all: _ } => {
+            geocode_addresses(command_service, query_service, out, id)
+        }
+        CommandsCqrs::Geojson => {
+            let collection = to_geojson(&all_addresses(query_service)?);
+            println!("{}", serde_json::to_string_pretty(&collection).unwrap());
+            Ok(())
+        }
+        CommandsCqrs::MtExport { id } => export_mt(query_service, id),
+        CommandsCqrs::MtImport {
+            kind,
+            lines,
+            free_format,
+        } => import_mt(command_service, out, kind, lines, free_format),
+        CommandsCqrs::QrExport { id } => export_qr(query_service, id),
+        CommandsCqrs::QrImport { kind, lines } => import_qr(command_service, out, kind, lines),
+        CommandsCqrs::VcardExport { id, version } => export_vcards(query_service, id, &version),
+        CommandsCqrs::VcardImport { file } => {
+            import_vcards(command_service, query_service, out, &file)
+        }
+        CommandsCqrs::RewritePayments { input, output } => rewrite_payments(out, &input, &output),
+        CommandsCqrs::Audit {
+            dir,
+            profile,
+            json,
+            csv_dir,
+        } => audit_payments(out, &dir, &profile, json.as_deref(), csv_dir.as_deref()),
+        CommandsCqrs::ImportStatements { files } => {
+            import_statements(command_service, query_service, out, &files)
+        }
+        CommandsCqrs::InvoiceExport { id, syntax } => export_invoice(query_service, id, &syntax),
+        CommandsCqrs::InvoiceImport { file, kind } => {
+            import_invoice(command_service, query_service, out, &file, &kind)
+        }
+        CommandsCqrs::Import {
+            file,
+            delimiter,
+            encoding,
+            mappings,
+            kind_column,
+            kind,
+            rejects,
+        } => {
+            let options = mappings
+                .iter()
+                .try_fold(
+                    CsvImportOptions {
+                        kind_column: Some(kind_column),
+                        postal_reference: postal_reference.cloned(),
+                        ..CsvImportOptions::default()
+                    },
+                    |options, mapping| options.with_mapping(mapping),
+                )
+                .and_then(|o| o.with_delimiter(&delimiter))
+                .and_then(|o| o.with_encoding(&encoding))
+                .and_then(|o| o.with_default_kind(&kind))
+                .map_err(CliError::Usage)?;
+            let rejects = rejects.unwrap_or_else(|| format!("{file}.rejects.csv"));
+            import_csv_file(command_service, out, &file, &rejects, &options)
+        }
+        CommandsCqrs::Export {
+            format,
+            layout,
+            columns,
+            file,
+        } => export_addresses_to(
+            query_service,
+            out,
+            &format,
+            &layout,
+            columns,
+            file.as_deref(),
+        ),
+        // Run by `run_stateless`.
+        CommandsCqrs::ToIso { .. }
+        | CommandsCqrs::ToFrench { .. }
+        | CommandsCqrs::Validate { .. } => Ok(()),
     }
 }
 
+/// Prints `message` in table mode and the stored address in the other modes.
+fn print_stored(out: OutputFormat, message: &str, iso: &ISO20022Address) {
+    match out {
+        OutputFormat::Table => println!("{message}"),
+        _ => out.print_address(iso),
+    }
+}
+
+fn find_address(query_svc: &AddressQueryService, id: &str) -> Result<ISO20022Address, CliError> {
+    query_svc
+        .get_address(id)
+        .map_err(read_error)?
+        .ok_or_else(|| CliError::address_not_found(id))
+}
+
+fn all_addresses(query_svc: &AddressQueryService) -> Result<Vec<ISO20022Address>, CliError> {
+    query_svc.get_all_addresses().map_err(read_error)
+}
+
+fn read_error(error: RepositoryError) -> CliError {
+    CliError::repository("Failed to read addresses", error)
+}
+
 fn add_address(
     cmd_svc: &mut AddressCommandService,
+    out: OutputFormat,
     kind: String,
-    line1: Option<String>,
-    line2: Option<String>,
-    line3: Option<String>,
-    line4: Option<String>,
-    line5: Option<String>,
-    line6: Option<String>,
-    line7: Option<String>,
-) {
+    country: String,
+    lines: AddressLinesArgs,
+    postal_reference: Option<&PostalReference>,
+) -> Result<(), CliError> {
     let id = Uuid::new_v4().to_string();
-    let kind_enum = match parse_kind(&kind) {
-        Ok(k) => k,
-        Err(_) => return,
+    let kind_enum = parse_kind(&kind)?;
+    let format = parse_country(&country)?;
+    let iso = to_checked_iso(
+        lines.into_lines(id.clone()),
+        kind_enum,
+        format,
+        postal_reference,
+    )
+    .map_err(|e| CliError::Validation(format!("Error building address: {e}")))?;
+    cmd_svc
+        .add_address(iso.clone())
+        .map_err(|e| CliError::repository("Failed to add address", e))?;
+    print_stored(
+        out,
+        &format!("Address added successfully with ID: {}", id),
+        &iso,
+    );
+    Ok(())
+}
+
+fn add_address_interactive(
+    cmd_svc: &mut AddressCommandService,
+    out: OutputFormat,
+    kind: Option<String>,
+    country: String,
+    lines: AddressLinesArgs,
+    postal_reference: Option<Arc<PostalReference>>,
+) -> Result<(), CliError> {
+    if parse_country(&country)?.country_code() != "FR" {
+        return Err(CliError::Usage(
+            "The interactive mode only knows the French (NF Z10-011) layout.".to_string(),
+        ));
+    }
+    let mut wizard = AddressWizard::new(io::stdin().lock(), io::stderr())
+        .with_postal_reference(postal_reference);
+    let kind = match kind {
+        Some(kind) => parse_kind(&kind)?,
+        None => wizard.ask_kind(None)?,
     };
-    let french = match FrenchAddressBuilder::new()
-        .id(id.clone())
-        .line1(line1)
-        .line2(line2)
-        .line3(line3)
-        .line4(line4)
-        .line5(line5)
-        .line6(line6)
-        .line7(line7)
-        .build()
-    {
-        Ok(addr) => addr,
-        Err(e) => {
-            eprintln!("Error building FrenchAddress: {e}");
-            return;
-        }
+    let id = Uuid::new_v4().to_string();
+    let Some(iso) = wizard.run(kind, lines.into_lines(id.clone()))? else {
+        out.print_result("Address not saved.", json!({ "saved": false }));
+        return Ok(());
     };
-    let iso: ISO20022Address = convert_to_iso(&french, kind_enum);
-    match cmd_svc.add_address(iso) {
-        Ok(_) => println!("Address added successfully with ID: {}", id),
-        Err(e) => eprintln!("Failed to add address: {e}"),
-    }
+    cmd_svc
+        .add_address(iso.clone())
+        .map_err(|e| CliError::repository("Failed to add address", e))?;
+    print_stored(
+        out,
+        &format!("Address added successfully with ID: {}", id),
+        &iso,
+    );
+    Ok(())
 }
 
-fn update_address(
+fn edit_address(
     cmd_svc: &mut AddressCommandService,
     query_svc: &AddressQueryService,
+    out: OutputFormat,
     id: String,
+    kind: Option<String>,
+    postal_reference: Option<Arc<PostalReference>>,
+) -> Result<(), CliError> {
+    let existing_iso = find_address(query_svc, &id)?;
+    let mut wizard = AddressWizard::new(io::stdin().lock(), io::stderr())
+        .with_postal_reference(postal_reference);
+    let kind = match kind {
+        Some(kind) => parse_kind(&kind)?,
+        None => wizard.ask_kind(Some(existing_iso.kind))?,
+    };
+    let Some(mut iso) = wizard.run(kind, FrenchFormat.to_lines(&existing_iso))? else {
+        out.print_result("Address not saved.", json!({ "saved": false }));
+        return Ok(());
+    };
+    iso.keep_stored_data(&existing_iso);
+    cmd_svc
+        .update_address(iso.clone())
+        .map_err(|e| CliError::repository("Failed to update address", e))?;
+    print_stored(
+        out,
+        &format!("Address with ID {} updated successfully.", id),
+        &iso,
+    );
+    Ok(())
+}
+
+/// Replaces the lines given in `updated_lines` (their id naming the address),
+/// keeping the stored ones for the others.
+fn update_address(
+    cmd_svc: &mut AddressCommandService,
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
     kind: String,
-    line1: Option<String>,
-    line2: Option<String>,
-    line3: Option<String>,
-    line4: Option<String>,
-    line5: Option<String>,
-    line6: Option<String>,
-    line7: Option<String>,
-) {
-    let existing_iso = match query_svc.get_address(&id) {
-        Some(iso) => iso,
-        None => {
-            eprintln!("Address with ID {id} not found.");
-            return;
+    country: String,
+    mut updated_lines: AddressLines,
+    postal_reference: Option<&PostalReference>,
+) -> Result<(), CliError> {
+    let id = updated_lines.id.clone();
+    let existing_iso = find_address(query_svc, &id)?;
+    let kind_enum = parse_kind(&kind)?;
+    let format = parse_country(&country)?;
+
+    let existing_lines = format.to_lines(&existing_iso);
+    for (line, existing) in updated_lines.lines.iter_mut().zip(existing_lines.lines) {
+        if line.is_none() {
+            *line = existing;
         }
+    }
+
+    let mut updated_iso = to_checked_iso(updated_lines, kind_enum, format, postal_reference)
+        .map_err(|e| CliError::Validation(format!("Error building updated address: {e}")))?;
+    updated_iso.keep_stored_data(&existing_iso);
+    cmd_svc
+        .update_address(updated_iso.clone())
+        .map_err(|e| CliError::repository("Failed to update address", e))?;
+    print_stored(
+        out,
+        &format!("Address with ID {} updated successfully.", id),
+        &updated_iso,
+    );
+    Ok(())
+}
+
+fn delete_address(
+    cmd_svc: &mut AddressCommandService,
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
+    id: String,
+) -> Result<(), CliError> {
+    find_address(query_svc, &id)?;
+    cmd_svc
+        .delete_address(&id)
+        .map_err(|e| CliError::repository("Failed to delete address", e))?;
+    out.print_result(
+        &format!("Address with ID {} deleted successfully.", id),
+        json!({ "deleted": id }),
+    );
+    Ok(())
+}
+
+/// Prints a page of matching addresses: in JSON with the paging and the
+/// total, in table mode followed by which of the matches it shows.
+fn search_addresses(
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
+    criteria: SearchArgs,
+) -> Result<(), CliError> {
+    let page = query_svc
+        .search_addresses(&criteria.query()?)
+        .map_err(read_error)?;
+    match out {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&page).unwrap()),
+        OutputFormat::Table if page.total > 0 => {
+            out.print_addresses(&page.addresses);
+            let first = page.page_size.unwrap_or(0) * (page.page - 1);
+            match page.page_size {
+                None => println!("Matching addresses: {}.", page.total),
+                Some(_) if page.addresses.is_empty() => {
+                    println!(
+                        "Page {} is past the last one, matching addresses: {}.",
+                        page.page, page.total
+                    )
+                }
+                Some(_) => println!(
+                    "Addresses {} to {} of {} (page {}).",
+                    first + 1,
+                    first + page.addresses.len(),
+                    page.total,
+                    page.page
+                ),
+            }
+        }
+        _ => out.print_addresses(&page.addresses),
+    }
+    Ok(())
+}
+
+fn get_address(
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
+    id: String,
+) -> Result<(), CliError> {
+    out.print_address(&find_address(query_svc, &id)?);
+    Ok(())
+}
+
+fn convert_address(
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
+    id: String,
+    country: Option<String>,
+) -> Result<(), CliError> {
+    let iso = find_address(query_svc, &id)?;
+    let format = match country {
+        Some(country) => parse_country(&country)?,
+        None => iso
+            .country
+            .as_deref()
+            .and_then(format_for_country)
+            .unwrap_or(&FrenchFormat),
     };
+    out.print_lines(&format.to_lines(&iso));
+    Ok(())
+}
+
+fn import_ban(
+    out: OutputFormat,
+    dir: &str,
+    departements: &[String],
+    index_path: &str,
+) -> Result<(), CliError> {
+    let index = import_ban_directory(dir, departements)
+        .map_err(|e| CliError::Io(format!("Failed to import BAN data: {e}")))?;
+    save_ban_index(&index, index_path)
+        .map_err(|e| CliError::Io(format!("Failed to write BAN index: {e}")))?;
+    out.print_result(
+        &format!(
+            "BAN index written to {} ({} addresses).",
+            index_path,
+            index.len()
+        ),
+        json!({ "index": index_path, "addresses": index.len() }),
+    );
+    Ok(())
+}
 
-    let kind_enum = match parse_kind(&kind) {
-        Ok(k) => k,
-        Err(_) => return,
+fn verify_address(
+    query_svc: &AddressQueryService,
+    verification_svc: Option<&AddressVerificationService>,
+    out: OutputFormat,
+    id: String,
+) -> Result<(), CliError> {
+    let Some(verification_svc) = verification_svc else {
+        return Err(CliError::Usage(
+            "No BAN index configured. Set BAN_INDEX_PATH.".to_string(),
+        ));
     };
+    let iso = find_address(query_svc, &id)?;
+    let result = serde_json::to_value(verification_svc.verify_address(&iso)).unwrap();
+    out.print_result(&fields_table(&result), result);
+    Ok(())
+}
 
-    let existing_french = convert_to_french(&existing_iso);
-
-    let updated_french = match FrenchAddressBuilder::new()
-        .id(id.clone())
-        .line1(line1.or(existing_french.line1))
-        .line2(line2.or(existing_french.line2))
-        .line3(line3.or(existing_french.line3))
-        .line4(line4.or(existing_french.line4))
-        .line5(line5.or(existing_french.line5))
-        .line6(line6.or(existing_french.line6))
-        .line7(line7.or(existing_french.line7))
-        .build()
-    {
-        Ok(fa) => fa,
-        Err(e) => {
-            eprintln!("Error building updated address: {e}");
-            return;
+fn geocode_addresses(
+    cmd_svc: &mut AddressCommandService,
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
+    id: Option<String>,
+) -> Result<(), CliError> {
+    if !cmd_svc.has_geocoder() {
+        return Err(CliError::Usage(
+            "No geocoder configured, set BAN_INDEX_PATH.".to_string(),
+        ));
+    }
+    let addresses = match id {
+        Some(id) => vec![find_address(query_svc, &id)?],
+        None => all_addresses(query_svc)?,
+    };
+
+    let mut messages = Vec::new();
+    let mut results = Vec::new();
+    let mut failure = None;
+    for iso in addresses {
+        let id = iso.id.clone();
+        match cmd_svc.geocode_address(iso) {
+            Ok(geocoded) => match geocoded.geolocation {
+                Some(location) => {
+                    messages.push(format!(
+                        "Address with ID {} geocoded: {}, {} ({})",
+                        id, location.latitude, location.longitude, location.ban_id
+                    ));
+                    results.push(json!({
+                        "id": id,
+                        "latitude": location.latitude,
+                        "longitude": location.longitude,
+                        "ban_id": location.ban_id,
+                    }));
+                }
+                None => {
+                    messages.push(format!("Address with ID {} not found in the BAN.", id));
+                    results.push(json!({ "id": id, "latitude": null, "longitude": null }));
+                }
+            },
+            Err(e) => {
+                failure = Some(CliError::repository(
+                    &format!("Failed to geocode address {id}"),
+                    e,
+                ));
+                break;
+            }
         }
+    }
+    if !results.is_empty() {
+        out.print_result(&messages.join("\n"), Value::from(results));
+    }
+    failure.map_or(Ok(()), Err)
+}
+
+fn export_mt(query_svc: &AddressQueryService, id: String) -> Result<(), CliError> {
+    let iso = find_address(query_svc, &id)?;
+    let lines = to_mt_structured(&iso)
+        .map_err(|e| CliError::Validation(format!("Failed to build MT lines: {e}")))?;
+    println!("{}", lines.join("\n"));
+    Ok(())
+}
+
+fn import_mt(
+    cmd_svc: &mut AddressCommandService,
+    out: OutputFormat,
+    kind: String,
+    lines: Vec<String>,
+    free_format: bool,
+) -> Result<(), CliError> {
+    let id = Uuid::new_v4().to_string();
+    let kind_enum = parse_kind(&kind)?;
+    let iso = if free_format {
+        from_mt_free_format(id.clone(), &lines, kind_enum)
+    } else {
+        from_mt_structured(id.clone(), &lines, kind_enum)
     };
+    let iso = iso.map_err(|e| CliError::Validation(format!("Error reading MT lines: {e}")))?;
+    cmd_svc
+        .add_address(iso.clone())
+        .map_err(|e| CliError::repository("Failed to add address", e))?;
+    print_stored(
+        out,
+        &format!("Address added successfully with ID: {}", id),
+        &iso,
+    );
+    Ok(())
+}
+
+fn export_qr(query_svc: &AddressQueryService, id: String) -> Result<(), CliError> {
+    let iso = find_address(query_svc, &id)?;
+    let address = QrBillAddress::from_iso(&iso)
+        .map_err(|e| CliError::Validation(format!("Cannot write QR-bill address: {e}")))?;
+    println!("{}", address.to_lines().join("\n"));
+    Ok(())
+}
+
+fn import_qr(
+    cmd_svc: &mut AddressCommandService,
+    out: OutputFormat,
+    kind: String,
+    lines: Vec<String>,
+) -> Result<(), CliError> {
+    let id = Uuid::new_v4().to_string();
+    let kind_enum = parse_kind(&kind)?;
+    let iso = QrBillAddress::from_lines(&lines)
+        .and_then(|a| a.to_iso(id.clone(), kind_enum))
+        .map_err(|e| CliError::Validation(format!("Error reading QR-bill address: {e}")))?;
+    cmd_svc
+        .add_address(iso.clone())
+        .map_err(|e| CliError::repository("Failed to add address", e))?;
+    print_stored(
+        out,
+        &format!("Address added successfully with ID: {}", id),
+        &iso,
+    );
+    Ok(())
+}
+
+fn export_vcards(
+    query_svc: &AddressQueryService,
+    id: Option<String>,
+    version: &str,
+) -> Result<(), CliError> {
+    let version = version.parse::<VCardVersion>().map_err(CliError::Usage)?;
+    let addresses = match id {
+        Some(id) => vec![find_address(query_svc, &id)?],
+        None => all_addresses(query_svc)?,
+    };
+    for iso in &addresses {
+        print!("{}", to_vcard(iso, version));
+    }
+    Ok(())
+}
+
+/// Stores imported addresses and prints how many were added and merged.
+fn store_imported(
+    cmd_svc: &mut AddressCommandService,
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
+    addresses: Vec<ISO20022Address>,
+    details: Value,
+    describe: fn(&ImportSummary) -> String,
+) -> Result<(), CliError> {
+    let summary = cmd_svc
+        .import_addresses(addresses, all_addresses(query_svc)?)
+        .map_err(|e| CliError::repository("Failed to import addresses", e))?;
+    let mut value = json!({
+        "added": summary.added,
+        "merged": summary.merged,
+        "unchanged": summary.unchanged,
+    });
+    if let (Some(value), Value::Object(details)) = (value.as_object_mut(), details) {
+        value.extend(details);
+    }
+    out.print_result(&describe(&summary), value);
+    Ok(())
+}
+
+fn added_and_stored(summary: &ImportSummary) -> String {
+    format!(
+        "{} addresses added, {} already stored.",
+        summary.added,
+        summary.merged + summary.unchanged
+    )
+}
+
+fn import_vcards(
+    cmd_svc: &mut AddressCommandService,
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
+    file: &str,
+) -> Result<(), CliError> {
+    let contacts = fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse_vcards(&text))
+        .map_err(|e| CliError::Io(format!("Failed to read {file}: {e}")))?;
+
+    let mut addresses = Vec::new();
+    for (position, contact) in contacts.iter().enumerate() {
+        match contact.to_iso(String::new()) {
+            Ok(iso) => addresses.push(iso),
+            Err(e) => {
+                let name = contact.formatted_name.as_deref().unwrap_or("unnamed");
+                out.print_warning(
+                    &format!("Contact #{} ({}): skipped, {e}", position + 1, name),
+                    json!({ "contact": position + 1, "name": name, "error": e }),
+                );
+            }
+        }
+    }
+    store_imported(
+        cmd_svc,
+        query_svc,
+        out,
+        addresses,
+        Value::Null,
+        added_and_stored,
+    )
+}
 
-    let updated_iso = convert_to_iso(&updated_french, kind_enum);
+fn rewrite_payments(out: OutputFormat, input: &str, output: &str) -> Result<(), CliError> {
+    let reader = File::open(input)
+        .map(BufReader::new)
+        .map_err(|e| CliError::Io(format!("Cannot read {input}: {e}")))?;
+    let writer = File::create(output)
+        .map(BufWriter::new)
+        .map_err(|e| CliError::Io(format!("Cannot write {output}: {e}")))?;
 
-    match cmd_svc.update_address(updated_iso) {
-        Ok(_) => println!("Address with ID {} updated successfully.", id),
-        Err(e) => eprintln!("Failed to update address: {e}"),
+    let reports = rewrite_payment_document(reader, writer).map_err(|e| {
+        let _ = fs::remove_file(output);
+        CliError::Validation(format!("Failed to rewrite {input}: {e}"))
+    })?;
+
+    let mut messages = Vec::new();
+    let mut restructured = 0;
+    for report in &reports {
+        let party = match &report.name {
+            Some(name) => format!("#{} {} ({})", report.position, report.party, name),
+            None => format!("#{} {}", report.position, report.party),
+        };
+        messages.push(match &report.outcome {
+            RewriteOutcome::Restructured => {
+                restructured += 1;
+                format!("{party}: restructured")
+            }
+            RewriteOutcome::AlreadyStructured => format!("{party}: already structured"),
+            RewriteOutcome::Hybrid => format!("{party}: hybrid address left untouched"),
+            RewriteOutcome::Empty => format!("{party}: no postal address"),
+            RewriteOutcome::Untouched { reason } => format!("{party}: left untouched, {reason}"),
+        });
     }
+    messages.push(format!(
+        "{} of {} postal addresses restructured, written to {}.",
+        restructured,
+        reports.len(),
+        output
+    ));
+    out.print_result(
+        &messages.join("\n"),
+        json!({ "output": output, "restructured": restructured, "parties": reports }),
+    );
+    Ok(())
 }
 
-fn delete_address(cmd_svc: &mut AddressCommandService, id: String) {
-    match cmd_svc.delete_address(&id) {
-        Ok(_) => println!("Address with ID {} deleted successfully.", id),
-        Err(e) => eprintln!("Failed to delete address: {e}"),
+fn audit_payments(
+    out: OutputFormat,
+    dir: &str,
+    profile: &str,
+    json: Option<&str>,
+    csv_dir: Option<&str>,
+) -> Result<(), CliError> {
+    let profile = profile
+        .parse::<ValidationProfile>()
+        .map_err(CliError::Usage)?;
+    let report = audit_directory(dir, profile)
+        .map_err(|e| CliError::Io(format!("Failed to audit {dir}: {e}")))?;
+
+    for skipped in &report.skipped {
+        out.print_warning(
+            &format!("Skipped {}: {}", skipped.file, skipped.reason),
+            json!({ "file": skipped.file, "error": skipped.reason }),
+        );
     }
+    let report_json = serde_json::to_string_pretty(&report).unwrap();
+    match json {
+        Some(path) => {
+            fs::write(path, report_json)
+                .map_err(|e| CliError::Io(format!("Cannot write {path}: {e}")))?;
+            out.print_result(
+                &format!("JSON report written to {path}."),
+                json!({ "json_report": path }),
+            );
+        }
+        None => println!("{report_json}"),
+    }
+    if let Some(csv_dir) = csv_dir {
+        report
+            .write_csv(csv_dir)
+            .map_err(|e| CliError::Io(format!("Failed to write CSV reports: {e}")))?;
+        out.print_result(
+            &format!("CSV reports written to {csv_dir}."),
+            json!({ "csv_reports": csv_dir }),
+        );
+    }
+    Ok(())
 }
 
-fn get_address(query_svc: &AddressQueryService, id: String) {
-    match query_svc.get_address(&id) {
-        Some(iso) => {
-            println!("{iso:#?}");
+fn import_statements(
+    cmd_svc: &mut AddressCommandService,
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
+    files: &[String],
+) -> Result<(), CliError> {
+    let mut addresses = Vec::new();
+    let mut read = Vec::new();
+    for file in files {
+        let parsed = File::open(file)
+            .map_err(|e| e.to_string())
+            .and_then(|f| parse_statement_parties(BufReader::new(f)));
+        match parsed {
+            Ok((parties, skipped)) => {
+                if out == OutputFormat::Table {
+                    println!("{file}: {} party addresses read.", parties.len());
+                }
+                read.push(json!({ "file": file, "read": parties.len() }));
+                for party in skipped {
+                    let name = party.name.as_deref().unwrap_or("unnamed");
+                    out.print_warning(
+                        &format!(
+                            "{file}: skipped {} {} ({}): {}",
+                            party.entry_reference, party.role, name, party.reason
+                        ),
+                        json!({
+                            "file": file,
+                            "entry": party.entry_reference,
+                            "role": party.role,
+                            "name": name,
+                            "error": party.reason,
+                        }),
+                    );
+                }
+                addresses.extend(parties.into_iter().map(|party| party.address));
+            }
+            Err(e) => out.print_warning(
+                &format!("Skipped {file}: {e}"),
+                json!({ "file": file, "error": e }),
+            ),
         }
-        None => eprintln!("Address with ID {id} not found."),
     }
+    store_imported(
+        cmd_svc,
+        query_svc,
+        out,
+        addresses,
+        json!({ "files": read }),
+        |summary| {
+            format!(
+                "{} addresses added, {} merged with stored addresses, {} unchanged.",
+                summary.added, summary.merged, summary.unchanged
+            )
+        },
+    )
 }
 
-fn list_addresses(query_svc: &AddressQueryService) {
-    let all = query_svc.get_all_addresses();
-    if all.is_empty() {
-        println!("No addresses found.");
+fn import_csv_file(
+    cmd_svc: &mut AddressCommandService,
+    out: OutputFormat,
+    file: &str,
+    rejects: &str,
+    options: &CsvImportOptions,
+) -> Result<(), CliError> {
+    let reader = File::open(file)
+        .map(BufReader::new)
+        .map_err(|e| CliError::Io(format!("Cannot read {file}: {e}")))?;
+    let writer = File::create(rejects)
+        .map(BufWriter::new)
+        .map_err(|e| CliError::Io(format!("Cannot write {rejects}: {e}")))?;
+
+    let mut store_error = None;
+    let report = import_csv(reader, writer, options, |batch| {
+        cmd_svc.add_addresses(batch).map_err(|e| {
+            let message = e.to_string();
+            store_error = Some(e);
+            message
+        })
+    })
+    .map_err(|e| match store_error.take() {
+        Some(error) => CliError::repository(&format!("Failed to import {file}"), error),
+        None => CliError::Io(format!("Failed to import {file}: {e}")),
+    })?;
+
+    let mut message = format!(
+        "{} rows read, {} addresses imported, {} rejected.",
+        report.rows, report.imported, report.rejected
+    );
+    let rejects = if report.rejected > 0 {
+        message.push_str(&format!("\nRejected rows written to {rejects}."));
+        Some(rejects)
     } else {
-        for iso in all {
-            println!("{iso:#?}");
+        let _ = fs::remove_file(rejects);
+        None
+    };
+    let mut value = serde_json::to_value(&report).unwrap();
+    value["rejects"] = Value::from(rejects);
+    out.print_result(&message, value);
+    Ok(())
+}
+
+fn export_addresses_to(
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
+    format: &str,
+    layout: &str,
+    columns: Option<String>,
+    file: Option<&str>,
+) -> Result<(), CliError> {
+    let options = format
+        .parse::<ExportFormat>()
+        .and_then(|format| {
+            let layout = layout.parse::<ExportLayout>()?;
+            ExportOptions::new(format, layout, columns.as_deref())
+        })
+        .map_err(CliError::Usage)?;
+
+    let writer: Box<dyn Write> = match file {
+        Some(path) => Box::new(
+            File::create(path).map_err(|e| CliError::Io(format!("Cannot write {path}: {e}")))?,
+        ),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut failure = None;
+    let exported = export_addresses(
+        until_error(query_svc.stream_all_addresses(), &mut failure),
+        writer,
+        &options,
+    );
+    if let Some(e) = failure {
+        return Err(CliError::repository("Failed to export addresses", e));
+    }
+    let count = exported.map_err(|e| CliError::Io(format!("Failed to export addresses: {e}")))?;
+    if let Some(path) = file {
+        out.print_result(
+            &format!("{count} addresses exported to {path}."),
+            json!({ "exported": count, "file": path }),
+        );
+    }
+    Ok(())
+}
+
+fn open_input(file: Option<&str>) -> Result<Box<dyn Read>, CliError> {
+    match file {
+        Some(path) => File::open(path)
+            .map(|f| Box::new(BufReader::new(f)) as Box<dyn Read>)
+            .map_err(|e| CliError::Io(format!("Cannot read {path}: {e}"))),
+        None => Ok(Box::new(io::stdin().lock())),
+    }
+}
+
+/// Writes the converted addresses to stdout and the rejected ones to stderr,
+/// failing with a validation error once done if any was rejected.
+fn write_converted(
+    out: OutputFormat,
+    addresses: impl Iterator<Item = Result<ISO20022Address, RejectedRow>>,
+    options: &ExportOptions,
+) -> Result<(), CliError> {
+    let mut rejected = 0;
+    let converted = addresses.filter_map(|result| {
+        result
+            .map_err(|row| {
+                rejected += 1;
+                out.print_warning(
+                    &format!("Row {}: {}", row.row, row.error),
+                    json!({ "row": row.row, "error": row.error }),
+                );
+            })
+            .ok()
+    });
+    export_addresses(converted, io::stdout().lock(), options).map_err(CliError::Io)?;
+    match rejected {
+        0 => Ok(()),
+        n => Err(CliError::Validation(format!("{n} addresses rejected."))),
+    }
+}
+
+fn convert_to_iso_stream(
+    out: OutputFormat,
+    file: Option<&str>,
+    input_format: &str,
+    format: &str,
+    columns: Option<&str>,
+    options: &CsvImportOptions,
+) -> Result<(), CliError> {
+    let mut output = format
+        .parse()
+        .and_then(|format| ExportOptions::new(format, ExportLayout::Iso, columns))
+        .map_err(CliError::Usage)?;
+    output.delimiter = options.delimiter;
+    let input_format = input_format
+        .parse::<ExportFormat>()
+        .map_err(CliError::Usage)?;
+    let input = open_input(file)?;
+    match input_format {
+        ExportFormat::Csv => write_converted(
+            out,
+            CsvAddressReader::new(input, options).map_err(CliError::Io)?,
+            &output,
+        ),
+        ExportFormat::Ndjson => {
+            let addresses = read_french_json(
+                input,
+                options.default_kind,
+                options.postal_reference.clone(),
+            );
+            write_converted(out, addresses, &output)
         }
     }
 }
 
-fn convert_address(query_svc: &AddressQueryService, id: String) {
-    match query_svc.get_address(&id) {
-        Some(iso) => {
-            let french = convert_to_french(&iso);
-            println!("{french:#?}");
+fn convert_to_french_stream(
+    out: OutputFormat,
+    file: Option<&str>,
+    input_format: &str,
+    format: &str,
+    columns: Option<&str>,
+    delimiter: &str,
+) -> Result<(), CliError> {
+    let delimiter = CsvImportOptions::default()
+        .with_delimiter(delimiter)
+        .map_err(CliError::Usage)?
+        .delimiter;
+    let mut output = format
+        .parse()
+        .and_then(|format| ExportOptions::new(format, ExportLayout::French, columns))
+        .map_err(CliError::Usage)?;
+    output.delimiter = delimiter;
+    let input_format = input_format
+        .parse::<ExportFormat>()
+        .map_err(CliError::Usage)?;
+    let input = open_input(file)?;
+    let addresses: Box<dyn Iterator<Item = Result<ISO20022Address, RejectedRow>>> =
+        match input_format {
+            ExportFormat::Csv => Box::new(read_iso_csv(input, delimiter).map_err(CliError::Io)?),
+            ExportFormat::Ndjson => Box::new(read_iso_json(input)),
+        };
+    let validated = addresses.enumerate().map(|(index, result)| {
+        result.and_then(|iso| match validate_iso20022_address(&iso) {
+            Ok(()) => Ok(iso),
+            Err(e) => Err(RejectedRow {
+                row: index + 1,
+                fields: Vec::new(),
+                error: format!("Validation error: {:?}", e),
+            }),
+        })
+    });
+    write_converted(out, validated, &output)
+}
+
+/// Outcome of one address checked by `validate`.
+struct RecordCheck {
+    record: usize,
+    name: Option<String>,
+    error: Option<String>,
+}
+
+fn check_profile(
+    iso: &ISO20022Address,
+    address_lines: &[String],
+    profile: ValidationProfile,
+) -> Option<String> {
+    validate_with_profile(iso, address_lines, profile)
+        .err()
+        .map(|e| format!("Validation error: {:?}", e))
+}
+
+/// Checks the records of the input: French lines go through
+/// `validate_french_address` when converted, then every address through the
+/// profile.
+fn check_records(
+    file: Option<&str>,
+    input_format: &str,
+    layout: &str,
+    profile: ValidationProfile,
+    options: &CsvImportOptions,
+) -> Result<Vec<RecordCheck>, CliError> {
+    if input_format.trim().eq_ignore_ascii_case("xml") {
+        let input = BufReader::new(open_input(file)?);
+        let addresses = read_postal_addresses(input).map_err(CliError::Io)?;
+        return Ok(addresses
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, postal_address))| RecordCheck {
+                record: index + 1,
+                name,
+                error: check_profile(
+                    &postal_address.address,
+                    &postal_address.address_lines,
+                    profile,
+                ),
+            })
+            .collect());
+    }
+
+    let input_format = input_format
+        .parse::<ExportFormat>()
+        .map_err(CliError::Usage)?;
+    let layout = layout.parse::<ExportLayout>().map_err(CliError::Usage)?;
+    let input = open_input(file)?;
+    let records: Box<dyn Iterator<Item = Result<ISO20022Address, RejectedRow>>> =
+        match (input_format, layout) {
+            (ExportFormat::Csv, ExportLayout::French) => {
+                Box::new(CsvAddressReader::new(input, options).map_err(CliError::Io)?)
+            }
+            (ExportFormat::Ndjson, ExportLayout::French) => Box::new(read_french_json(
+                input,
+                options.default_kind,
+                options.postal_reference.clone(),
+            )),
+            (ExportFormat::Csv, ExportLayout::Iso) => {
+                Box::new(read_iso_csv(input, options.delimiter).map_err(CliError::Io)?)
+            }
+            (ExportFormat::Ndjson, ExportLayout::Iso) => Box::new(read_iso_json(input)),
+        };
+    Ok(records
+        .enumerate()
+        .map(|(index, result)| match result {
+            Ok(iso) => RecordCheck {
+                record: index + 1,
+                error: check_profile(&iso, &[], profile),
+                name: iso.recipient_name,
+            },
+            Err(rejected) => RecordCheck {
+                record: index + 1,
+                name: None,
+                error: Some(rejected.error),
+            },
+        })
+        .collect())
+}
+
+/// Prints the errors of the invalid records and a summary, failing with a
+/// validation error when any record is invalid.
+fn report_checks(
+    out: OutputFormat,
+    profile: ValidationProfile,
+    checks: &[RecordCheck],
+) -> Result<(), CliError> {
+    let profile_name = format!("{:?}", profile).to_lowercase();
+    let invalid = checks.iter().filter(|check| check.error.is_some()).count();
+
+    let mut messages: Vec<String> = checks
+        .iter()
+        .filter_map(|check| {
+            let error = check.error.as_ref()?;
+            Some(match &check.name {
+                Some(name) => format!("Record {} ({}): {}", check.record, name, error),
+                None => format!("Record {}: {}", check.record, error),
+            })
+        })
+        .collect();
+    messages.push(format!(
+        "{} addresses checked against the {} profile: {} valid, {} invalid.",
+        checks.len(),
+        profile_name,
+        checks.len() - invalid,
+        invalid
+    ));
+    let records: Vec<Value> = checks
+        .iter()
+        .map(|check| {
+            json!({
+                "record": check.record,
+                "name": check.name,
+                "valid": check.error.is_none(),
+                "error": check.error,
+            })
+        })
+        .collect();
+    out.print_result(
+        &messages.join("\n"),
+        json!({
+            "profile": profile_name,
+            "checked": checks.len(),
+            "valid": checks.len() - invalid,
+            "invalid": invalid,
+            "records": records,
+        }),
+    );
+
+    match invalid {
+        0 => Ok(()),
+        n => Err(CliError::Validation(format!(
+            "{} of {} addresses invalid.",
+            n,
+            checks.len()
+        ))),
+    }
+}
+
+fn export_invoice(
+    query_svc: &AddressQueryService,
+    id: String,
+    syntax: &str,
+) -> Result<(), CliError> {
+    let syntax = syntax.parse::<InvoiceSyntax>().map_err(CliError::Usage)?;
+    let iso = find_address(query_svc, &id)?;
+    println!(
+        "{}",
+        invoice_address_xml(syntax, &InvoiceAddress::from_iso(&iso))
+    );
+    Ok(())
+}
+
+fn import_invoice(
+    cmd_svc: &mut AddressCommandService,
+    query_svc: &AddressQueryService,
+    out: OutputFormat,
+    file: &str,
+    kind: &str,
+) -> Result<(), CliError> {
+    let kind_enum = parse_kind(kind)?;
+    let parties = File::open(file)
+        .map_err(|e| e.to_string())
+        .and_then(|f| read_invoice_addresses(BufReader::new(f)))
+        .map_err(|e| CliError::Io(format!("Failed to read {file}: {e}")))?;
+
+    let mut addresses = Vec::new();
+    let mut read = Vec::new();
+    for party in parties {
+        let name = party.name.as_deref().unwrap_or("unnamed");
+        let label = format!("{} ({})", party.role, name);
+        match party
+            .address
+            .to_iso(String::new(), party.name.clone(), kind_enum)
+        {
+            Ok(iso) => {
+                if out == OutputFormat::Table {
+                    println!("{label}: read");
+                }
+                read.push(json!({ "role": party.role, "name": name }));
+                addresses.push(iso);
+            }
+            Err(e) => out.print_warning(
+                &format!("{label}: skipped, {e}"),
+                json!({ "role": party.role, "name": name, "error": e }),
+            ),
         }
-        None => eprintln!("Address with ID {id} not found."),
     }
+    store_imported(
+        cmd_svc,
+        query_svc,
+        out,
+        addresses,
+        json!({ "parties": read }),
+        added_and_stored,
+    )
 }
 
-fn parse_kind(s: &str) -> Result<AddressKind, ()> {
+fn parse_country(s: &str) -> Result<&'static dyn NationalAddressFormat, CliError> {
+    format_for_country(s).ok_or_else(|| {
+        CliError::Usage(format!(
+            "Unsupported country '{}'. Use one of: {}.",
+            s,
+            SUPPORTED_COUNTRIES.join(", ")
+        ))
+    })
+}
+
+fn parse_kind(s: &str) -> Result<AddressKind, CliError> {
     match s.to_lowercase().as_str() {
         "company" => Ok(AddressKind::Company),
         "particular" => Ok(AddressKind::Particular),
-        _ => {
-            eprintln!("Invalid address kind. Use 'company' or 'particular'.");
-            Err(())
-        }
+        _ => Err(CliError::Usage(
+            "Invalid address kind. Use 'company' or 'particular'.".to_string(),
+        )),
     }
 }
@@ -1 +1,3 @@
-pub mod commands;
\ No newline at end of file
+pub mod commands;
+pub mod output;
+pub mod wizard;
@@ -0,0 +1,368 @@
+use crate::domain::formats::AddressLines;
+use crate::domain::models::ISO20022Address;
+use crate::domain::repository::RepositoryError;
+use crate::domain::usecases::convert_to_french;
+use crate::infrastructure::bulk_export::address_exporter::{
+    ExportLayout, ISO_COLUMNS, csv_cell, row,
+};
+use crate::infrastructure::iso20022_xml::address_document::{
+    address_lines_xml, address_xml, addresses_xml,
+};
+use clap::ValueEnum;
+use quick_xml::Writer;
+use quick_xml::events::BytesText;
+use serde_json::{Value, json};
+use std::fmt;
+
+/// Exit code of unreadable input and unwritable output files.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code of unusable option values, as clap for a malformed command line.
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NOT_FOUND: i32 = 3;
+pub const EXIT_VALIDATION: i32 = 4;
+pub const EXIT_STORAGE: i32 = 5;
+/// Exit code of a write clashing with the stored addresses, as an id already taken.
+pub const EXIT_CONFLICT: i32 = 6;
+
+/// Columns of `list` in table mode.
+const LIST_COLUMNS: [&str; 8] = [
+    "id",
+    "kind",
+    "recipient_name",
+    "building_number",
+    "street_name",
+    "post_code",
+    "town_name",
+    "country",
+];
+
+/// How the commands print addresses, results and errors (`--output`).
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Aligned columns and sentences.
+    #[default]
+    Table,
+    /// One indented JSON document.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+    /// ISO 20022 `PstlAdr` elements.
+    Xml,
+    /// The seven NF Z10-011 lines.
+    FrenchLines,
+}
+
+/// Why a command failed, which sets the exit code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliError {
+    /// An option value the command cannot use.
+    Usage(String),
+    NotFound(String),
+    /// An address, or rows of the input, failing validation.
+    Validation(String),
+    /// The repository failed to read or write.
+    Storage(String),
+    /// The repository refused a write clashing with the stored addresses.
+    Conflict(String),
+    Io(String),
+}
+
+impl CliError {
+    pub fn address_not_found(id: &str) -> Self {
+        Self::NotFound(format!("Address with ID {id} not found."))
+    }
+
+    /// `error` of the repository, after `context`, with the matching exit code.
+    pub fn repository(context: &str, error: RepositoryError) -> Self {
+        let message = format!("{context}: {error}");
+        match error {
+            RepositoryError::NotFound(_) => Self::NotFound(message),
+            RepositoryError::AlreadyExists(_) | RepositoryError::Conflict(_) => {
+                Self::Conflict(message)
+            }
+            RepositoryError::Storage(_) | RepositoryError::Serialization(_) => {
+                Self::Storage(message)
+            }
+        }
+    }
+
+    /// Name of the failure in the JSON and XML error outputs.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Usage(_) => "usage",
+            Self::NotFound(_) => "not_found",
+            Self::Validation(_) => "validation",
+            Self::Storage(_) => "storage",
+            Self::Conflict(_) => "conflict",
+            Self::Io(_) => "io",
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) => EXIT_USAGE,
+            Self::NotFound(_) => EXIT_NOT_FOUND,
+            Self::Validation(_) => EXIT_VALIDATION,
+            Self::Storage(_) => EXIT_STORAGE,
+            Self::Conflict(_) => EXIT_CONFLICT,
+            Self::Io(_) => EXIT_FAILURE,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Usage(message)
+            | Self::NotFound(message)
+            | Self::Validation(message)
+            | Self::Storage(message)
+            | Self::Conflict(message)
+            | Self::Io(message) => message,
+        }
+    }
+
+    /// Prints the error to stderr in the `output` format and exits with its code.
+    pub fn exit(&self, output: OutputFormat) -> ! {
+        let value = json!({ "error": self.code(), "message": self.message() });
+        eprintln!("{}", output.render_message(self.message(), "Error", &value));
+        std::process::exit(self.exit_code())
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl OutputFormat {
+    pub fn is_json(self) -> bool {
+        matches!(self, Self::Json | Self::Ndjson)
+    }
+
+    /// One stored address: its fields in table mode, as stored in JSON.
+    pub fn print_address(self, address: &ISO20022Address) {
+        match self {
+            Self::Table => print!("{}", address_table(address)),
+            Self::Json => println!("{}", serde_json::to_string_pretty(address).unwrap()),
+            Self::Ndjson => println!("{}", serde_json::to_string(address).unwrap()),
+            Self::Xml => println!("{}", address_xml(address)),
+            Self::FrenchLines => print!("{}", french_lines(address)),
+        }
+    }
+
+    /// Stored addresses: one row each in table mode, a JSON array, one JSON
+    /// object per line, an `Addresses` element or blocks of lines.
+    pub fn print_addresses(self, addresses: &[ISO20022Address]) {
+        match self {
+            Self::Table if addresses.is_empty() => println!("No addresses found."),
+            Self::Table => {
+                let header = LIST_COLUMNS.iter().map(|c| c.to_string()).collect();
+                let rows: Vec<Vec<String>> = std::iter::once(header)
+                    .chain(addresses.iter().map(|address| {
+                        row(address, ExportLayout::Iso, &LIST_COLUMNS)
+                            .iter()
+                            .map(csv_cell)
+                            .collect()
+                    }))
+                    .collect();
+                print!("{}", table(&rows));
+            }
+            Self::Json => println!("{}", serde_json::to_string_pretty(addresses).unwrap()),
+            Self::Ndjson => addresses.iter().for_each(|a| self.print_address(a)),
+            Self::Xml => println!("{}", addresses_xml(addresses)),
+            Self::FrenchLines => {
+                let blocks: Vec<String> = addresses.iter().map(french_lines).collect();
+                print!("{}", blocks.join("\n"));
+            }
+        }
+    }
+
+    /// The lines of an address in a national layout (`convert`).
+    pub fn print_lines(self, lines: &AddressLines) {
+        match self {
+            Self::Table => {
+                let rows: Vec<Vec<String>> = lines
+                    .lines
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(n, line)| Some(vec![format!("line{}", n + 1), line.clone()?]))
+                    .collect();
+                print!("{}", table(&rows));
+            }
+            Self::Json => println!("{}", serde_json::to_string_pretty(lines).unwrap()),
+            Self::Ndjson => println!("{}", serde_json::to_string(lines).unwrap()),
+            Self::Xml => println!("{}", address_lines_xml(lines)),
+            Self::FrenchLines => lines.lines.iter().flatten().for_each(|l| println!("{l}")),
+        }
+    }
+
+    /// What a command did: `message` in table and french-lines modes, `value`
+    /// otherwise (an array gives one line per item in NDJSON).
+    pub fn print_result(self, message: &str, value: Value) {
+        match (self, &value) {
+            (Self::Ndjson, Value::Array(items)) => {
+                items.iter().for_each(|item| println!("{item}"));
+            }
+            (Self::Json, _) => println!("{}", serde_json::to_string_pretty(&value).unwrap()),
+            _ => println!("{}", self.render_message(message, "Result", &value)),
+        }
+    }
+
+    /// A problem that does not stop the command, on stderr.
+    pub fn print_warning(self, message: &str, value: Value) {
+        eprintln!("{}", self.render_message(message, "Warning", &value));
+    }
+
+    fn render_message(self, message: &str, element: &str, value: &Value) -> String {
+        match self {
+            Self::Table | Self::FrenchLines => message.to_string(),
+            Self::Json | Self::Ndjson => value.to_string(),
+            Self::Xml => value_xml(element, value),
+        }
+    }
+}
+
+/// The fields of an address holding a value, one per row.
+pub fn address_table(address: &ISO20022Address) -> String {
+    let values = row(address, ExportLayout::Iso, &ISO_COLUMNS);
+    let rows: Vec<Vec<String>> = ISO_COLUMNS
+        .iter()
+        .zip(&values)
+        .map(|(column, value)| vec![column.to_string(), csv_cell(value)])
+        .filter(|row| !row[1].is_empty())
+        .collect();
+    table(&rows)
+}
+
+/// The fields of a flat JSON object, one per row, for table mode.
+pub fn fields_table(value: &Value) -> String {
+    let rows: Vec<Vec<String>> = value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| vec![key.clone(), csv_cell(value)])
+        .collect();
+    table(&rows).trim_end().to_string()
+}
+
+/// Rows with their cells padded to the widest of each column.
+fn table(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|n| {
+            rows.iter()
+                .filter_map(|row| row.get(n))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect();
+            format!("{}\n", cells.join("  ").trim_end())
+        })
+        .collect()
+}
+
+fn french_lines(address: &ISO20022Address) -> String {
+    let lines: AddressLines = convert_to_french(address).into();
+    lines
+        .lines
+        .iter()
+        .flatten()
+        .map(|line| format!("{line}\n"))
+        .collect()
+}
+
+/// `value` as an element named `name`: object keys give child elements,
+/// array items repeat the element and null values are left out.
+fn value_xml(name: &str, value: &Value) -> String {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    write_value(&mut writer, name, value).expect("writing to memory");
+    String::from_utf8(writer.into_inner()).expect("XML written from UTF-8 strings")
+}
+
+fn write_value(writer: &mut Writer<Vec<u8>>, name: &str, value: &Value) -> std::io::Result<()> {
+    match value {
+        Value::Null => {}
+        Value::Object(fields) => {
+            writer.create_element(name).write_inner_content(|writer| {
+                fields
+                    .iter()
+                    .try_for_each(|(key, value)| write_value(writer, key, value))
+            })?;
+        }
+        Value::Array(items) => {
+            for item in items {
+                write_value(writer, name, item)?;
+            }
+        }
+        Value::String(text) => {
+            writer
+                .create_element(name)
+                .write_text_content(BytesText::new(text))?;
+        }
+        other => {
+            writer
+                .create_element(name)
+                .write_text_content(BytesText::new(&other.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_alignment() {
+        let rows = vec![
+            vec!["id".to_string(), "town_name".to_string()],
+            vec!["1".to_string(), "LIBOURNE".to_string()],
+            vec!["12345".to_string(), String::new()],
+        ];
+
+        assert_eq!(table(&rows), "id     town_name\n1      LIBOURNE\n12345\n");
+        assert_eq!(
+            fields_table(&json!({"quality": "ExactHouse", "ban_id": null, "post_code": "33500"})),
+            "quality    ExactHouse\npost_code  33500"
+        );
+    }
+
+    #[test]
+    fn test_value_xml() {
+        let value = json!({"added": 2, "files": [{"file": "a.xml", "read": 1}, {"file": "b&c.xml", "read": 0}], "rejects": null});
+
+        assert_eq!(
+            value_xml("Result", &value),
+            "<Result>\n  <added>2</added>\n  <files>\n    <file>a.xml</file>\n    <read>1</read>\n  </files>\n  <files>\n    <file>b&amp;c.xml</file>\n    <read>0</read>\n  </files>\n</Result>"
+        );
+    }
+
+    #[test]
+    fn test_error_exit_codes() {
+        let error = CliError::address_not_found("42");
+
+        assert_eq!(error.code(), "not_found");
+        assert_eq!(error.exit_code(), EXIT_NOT_FOUND);
+        assert_eq!(error.to_string(), "Address with ID 42 not found.");
+        assert_eq!(CliError::Storage(String::new()).exit_code(), EXIT_STORAGE);
+
+        let error = CliError::repository(
+            "Failed to add address",
+            RepositoryError::AlreadyExists("42".to_string()),
+        );
+        assert_eq!(error.exit_code(), EXIT_CONFLICT);
+        assert_eq!(
+            error.message(),
+            "Failed to add address: Address 42 already exists"
+        );
+    }
+}
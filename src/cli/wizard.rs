@@ -0,0 +1,227 @@
+use crate::cli::output::{CliError, address_table};
+use crate::domain::conversion::to_checked_iso;
+use crate::domain::formats::{AddressLines, FrenchFormat};
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::domain::postal_reference::PostalReference;
+use crate::domain::validation::validate_french_line;
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+/// What each line holds under NF Z10-011 for a company.
+const COMPANY_LINES: [&str; 7] = [
+    "Company name",
+    "Recipient or department",
+    "Entrance, building, industrial zone",
+    "Street number and name",
+    "Special delivery (BP, TSA) or locality",
+    "Postal code and town, CEDEX included",
+    "Country",
+];
+
+/// What each line holds under NF Z10-011 for a private person.
+const PARTICULAR_LINES: [&str; 7] = [
+    "Title, first name and surname",
+    "Apartment, letterbox, floor",
+    "Entrance, building, residence",
+    "Street number and name",
+    "Special delivery (BP) or locality",
+    "Postal code and town",
+    "Country",
+];
+
+/// Line 7 is asked before line 6, whose layout depends on the destination.
+const PROMPT_ORDER: [usize; 7] = [1, 2, 3, 4, 5, 7, 6];
+
+/// Asks the lines of a French address one at a time on `prompts`, reading
+/// the answers from `input`, and checks each one as it is typed.
+pub struct AddressWizard<R, W> {
+    input: R,
+    prompts: W,
+    postal_reference: Option<Arc<PostalReference>>,
+}
+
+impl<R: BufRead, W: Write> AddressWizard<R, W> {
+    pub fn new(input: R, prompts: W) -> Self {
+        Self {
+            input,
+            prompts,
+            postal_reference: None,
+        }
+    }
+
+    /// Checks the completed lines against the postal reference, when loaded.
+    pub fn with_postal_reference(mut self, reference: Option<Arc<PostalReference>>) -> Self {
+        self.postal_reference = reference;
+        self
+    }
+
+    /// Asks company or particular; an empty answer takes `default` when given.
+    pub fn ask_kind(&mut self, default: Option<AddressKind>) -> Result<AddressKind, CliError> {
+        let prompt = match default {
+            Some(AddressKind::Company) => "Kind (company/particular) [company]",
+            Some(AddressKind::Particular) => "Kind (company/particular) [particular]",
+            None => "Kind (company/particular)",
+        };
+        loop {
+            match (self.read_answer(prompt)?.to_lowercase().as_str(), default) {
+                ("", Some(kind)) => return Ok(kind),
+                ("company" | "c", _) => return Ok(AddressKind::Company),
+                ("particular" | "p", _) => return Ok(AddressKind::Particular),
+                _ => self.say("  Answer 'company' or 'particular'.")?,
+            }
+        }
+    }
+
+    /// Asks the lines, previews the ISO 20022 address they convert to and
+    /// returns it once confirmed, `None` when the user gives up. The values of
+    /// `lines` are kept on an empty answer; `-` clears one.
+    pub fn run(
+        &mut self,
+        kind: AddressKind,
+        mut lines: AddressLines,
+    ) -> Result<Option<ISO20022Address>, CliError> {
+        self.say("Enter keeps the value in brackets, '-' clears it.")?;
+        loop {
+            lines = self.ask_lines(kind, lines)?;
+            let reference = self.postal_reference.as_deref();
+            match to_checked_iso(lines.clone(), kind, &FrenchFormat, reference) {
+                Ok(iso) => {
+                    self.say(&format!("\n{}", address_table(&iso)))?;
+                    let save = self.ask_yes_no("Save this address?", false)?;
+                    return Ok(save.then_some(iso));
+                }
+                Err(e) => {
+                    self.say(&format!("  {e}"))?;
+                    if !self.ask_yes_no("Correct the lines?", true)? {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+
+    fn ask_lines(
+        &mut self,
+        kind: AddressKind,
+        mut lines: AddressLines,
+    ) -> Result<AddressLines, CliError> {
+        let meanings = match kind {
+            AddressKind::Company => COMPANY_LINES,
+            AddressKind::Particular => PARTICULAR_LINES,
+        };
+        for number in PROMPT_ORDER {
+            let mut current = lines.lines[number - 1].clone();
+            if number == 7 {
+                current = current.or_else(|| Some("FRANCE".to_string()));
+            }
+            let prompt = match &current {
+                Some(value) => format!("Line {} - {} [{}]", number, meanings[number - 1], value),
+                None => format!("Line {} - {}", number, meanings[number - 1]),
+            };
+            loop {
+                let value = match self.read_answer(&prompt)?.as_str() {
+                    "" => current.clone(),
+                    "-" => None,
+                    answer => Some(answer.to_string()),
+                };
+                match validate_french_line(number, value.as_deref(), lines.lines[6].as_deref()) {
+                    Ok(()) => {
+                        lines.lines[number - 1] = value;
+                        break;
+                    }
+                    Err(e) => self.say(&format!("  Validation error: {:?}", e))?,
+                }
+            }
+        }
+        Ok(lines)
+    }
+
+    fn ask_yes_no(&mut self, question: &str, default: bool) -> Result<bool, CliError> {
+        let prompt = format!("{} [{}]", question, if default { "Y/n" } else { "y/N" });
+        loop {
+            match self.read_answer(&prompt)?.to_lowercase().as_str() {
+                "" => return Ok(default),
+                "y" | "yes" | "o" | "oui" => return Ok(true),
+                "n" | "no" | "non" => return Ok(false),
+                _ => self.say("  Answer 'y' or 'n'.")?,
+            }
+        }
+    }
+
+    fn read_answer(&mut self, prompt: &str) -> Result<String, CliError> {
+        write!(self.prompts, "{prompt}: ")
+            .and_then(|_| self.prompts.flush())
+            .map_err(|e| CliError::Io(e.to_string()))?;
+        let mut answer = String::new();
+        let read = self
+            .input
+            .read_line(&mut answer)
+            .map_err(|e| CliError::Io(e.to_string()))?;
+        if read == 0 {
+            return Err(CliError::Usage(
+                "Input ended before the address was complete.".to_string(),
+            ));
+        }
+        Ok(answer.trim().to_string())
+    }
+
+    fn say(&mut self, text: &str) -> Result<(), CliError> {
+        writeln!(self.prompts, "{text}").map_err(|e| CliError::Io(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wizard_checks_each_line() {
+        // Line 6 is refused until it holds a postal code, line 7 keeps FRANCE.
+        let answers = "c\nDURAND SA\nSERVICE ACHATS\n\n22BIS RUE DES FLEURS\n\n\nLIBOURNE\n33500 LIBOURNE\ny\n";
+        let mut prompts = Vec::new();
+        let mut wizard = AddressWizard::new(answers.as_bytes(), &mut prompts);
+
+        let kind = wizard.ask_kind(None).unwrap();
+        let iso = wizard.run(kind, AddressLines::default()).unwrap().unwrap();
+
+        assert_eq!(kind, AddressKind::Company);
+        assert_eq!(iso.department.as_deref(), Some("SERVICE ACHATS"));
+        assert_eq!(iso.post_code.as_deref(), Some("33500"));
+        assert_eq!(iso.country.as_deref(), Some("FR"));
+        let prompts = String::from_utf8(prompts).unwrap();
+        assert!(prompts.contains("Line 7 - Country [FRANCE]: "));
+        assert!(prompts.contains("InvalidPostalCode"));
+        assert!(prompts.contains("building_number  22BIS"));
+    }
+
+    #[test]
+    fn test_wizard_edit_declined() {
+        let lines = AddressLines {
+            id: "1".to_string(),
+            lines: [
+                Some("Jean Dupont".to_string()),
+                Some("APPARTEMENT 12".to_string()),
+                None,
+                Some("15 RUE DES LILAS".to_string()),
+                None,
+                Some("69001 LYON".to_string()),
+                Some("FRANCE".to_string()),
+            ],
+        };
+        let mut prompts = Vec::new();
+        let mut wizard = AddressWizard::new("\n-\n\n\n\n\n\nn\n".as_bytes(), &mut prompts);
+
+        assert!(
+            wizard
+                .run(AddressKind::Particular, lines)
+                .unwrap()
+                .is_none()
+        );
+        let prompts = String::from_utf8(prompts).unwrap();
+        assert!(prompts.contains("Line 2 - Apartment, letterbox, floor [APPARTEMENT 12]: "));
+        assert!(!prompts.contains("room"));
+
+        let mut wizard = AddressWizard::new("x\n".as_bytes(), Vec::new());
+        assert!(matches!(wizard.ask_kind(None), Err(CliError::Usage(_))));
+    }
+}
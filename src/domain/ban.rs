@@ -0,0 +1,349 @@
+use crate::domain::models::{GeoLocation, ISO20022Address};
+use crate::domain::postal_reference::{levenshtein, normalize_town};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// One housenumber of the Base Adresse Nationale.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BanAddress {
+    pub id: String,                   // BAN identifier, e.g. "33243_1234_00022_bis"
+    pub house_number: Option<String>, // numero followed by its suffix (rep), e.g. "22BIS"
+    pub street_name: String,          // nom_voie
+    pub post_code: String,
+    pub town_name: String, // nom_commune
+    pub insee_code: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MatchQuality {
+    ExactHouse,
+    StreetOnly,
+    CommuneOnly,
+    NotFound,
+}
+
+/// Result of looking an address up in the BAN, with the official spelling of
+/// whatever could be matched.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationResult {
+    pub quality: MatchQuality,
+    pub ban_id: Option<String>,
+    pub street_name: Option<String>,
+    pub building_number: Option<String>,
+    pub post_code: Option<String>,
+    pub town_name: Option<String>,
+}
+
+impl VerificationResult {
+    fn not_found() -> Self {
+        Self {
+            quality: MatchQuality::NotFound,
+            ban_id: None,
+            street_name: None,
+            building_number: None,
+            post_code: None,
+            town_name: None,
+        }
+    }
+
+    fn from_ban(quality: MatchQuality, ban: &BanAddress) -> Self {
+        Self {
+            quality,
+            ban_id: (quality == MatchQuality::ExactHouse).then(|| ban.id.clone()),
+            street_name: (quality != MatchQuality::CommuneOnly).then(|| ban.street_name.clone()),
+            building_number: ban
+                .house_number
+                .clone()
+                .filter(|_| quality == MatchQuality::ExactHouse),
+            post_code: Some(ban.post_code.clone()),
+            town_name: Some(ban.town_name.clone()),
+        }
+    }
+}
+
+/// BAN housenumbers grouped by postal code, the granularity at which
+/// `ISO20022Address` records are looked up.
+#[derive(Debug, Clone, Default)]
+pub struct BanIndex {
+    by_post_code: HashMap<String, Vec<BanAddress>>,
+}
+
+impl BanIndex {
+    pub fn new(addresses: Vec<BanAddress>) -> Self {
+        let mut index = Self::default();
+        index.extend(addresses);
+        index
+    }
+
+    pub fn extend(&mut self, addresses: impl IntoIterator<Item = BanAddress>) {
+        for address in addresses {
+            self.by_post_code
+                .entry(address.post_code.clone())
+                .or_default()
+                .push(address);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_post_code.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_post_code.is_empty()
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = &BanAddress> {
+        self.by_post_code.values().flatten()
+    }
+
+    /// Looks a French address up; addresses of any other country are
+    /// `NotFound` since the BAN only covers France.
+    pub fn verify(&self, iso: &ISO20022Address) -> VerificationResult {
+        if iso
+            .country
+            .as_deref()
+            .is_some_and(|country| !country.trim().eq_ignore_ascii_case("FR"))
+        {
+            return VerificationResult::not_found();
+        }
+        let Some(candidates) = iso
+            .post_code
+            .as_deref()
+            .and_then(|code| self.by_post_code.get(code.trim()))
+        else {
+            return VerificationResult::not_found();
+        };
+
+        let Some(street) = iso.street_name.as_deref().map(normalize_street) else {
+            return commune_only(candidates, iso);
+        };
+        let tolerance = (street.chars().count() / 6).max(1);
+
+        let mut best_street: Option<(usize, &BanAddress)> = None;
+        for candidate in candidates {
+            let distance = levenshtein(&street, &normalize_street(&candidate.street_name));
+            if distance > tolerance {
+                continue;
+            }
+            if best_street.is_none_or(|(best, _)| distance < best) {
+                best_street = Some((distance, candidate));
+            }
+        }
+
+        let Some((_, street_match)) = best_street else {
+            return commune_only(candidates, iso);
+        };
+
+        let official_street = normalize_street(&street_match.street_name);
+        let number = iso.building_number.as_deref().map(normalize_house_number);
+        let house = candidates.iter().find(|c| {
+            normalize_street(&c.street_name) == official_street
+                && number.is_some()
+                && c.house_number.as_deref().map(normalize_house_number) == number
+        });
+
+        match house {
+            Some(house) => VerificationResult::from_ban(MatchQuality::ExactHouse, house),
+            None => VerificationResult::from_ban(MatchQuality::StreetOnly, street_match),
+        }
+    }
+}
+
+/// A postal code can be shared by several communes: the one named in
+/// `town_name` is reported, or `NotFound` when none of them matches.
+fn commune_only(candidates: &[BanAddress], iso: &ISO20022Address) -> VerificationResult {
+    let commune = match iso.town_name.as_deref().map(normalize_town) {
+        Some(town) => candidates
+            .iter()
+            .find(|c| normalize_town(&c.town_name) == town),
+        None => candidates.first(),
+    };
+    commune.map_or_else(VerificationResult::not_found, |commune| {
+        VerificationResult::from_ban(MatchQuality::CommuneOnly, commune)
+    })
+}
+
+/// Finds the coordinates of an address. Implemented by `BanIndex`; kept as a
+/// trait so that the command service does not depend on a particular source.
+pub trait Geocoder: Send + Sync {
+    fn geocode(&self, address: &ISO20022Address) -> Option<GeoLocation>;
+}
+
+impl Geocoder for BanIndex {
+    /// Only addresses matched down to the housenumber are geocoded.
+    fn geocode(&self, address: &ISO20022Address) -> Option<GeoLocation> {
+        let result = self.verify(address);
+        let ban_id = result.ban_id?;
+        let post_code = result.post_code?;
+        self.by_post_code
+            .get(&post_code)?
+            .iter()
+            .find(|a| a.id == ban_id)
+            .map(|a| GeoLocation {
+                latitude: a.latitude,
+                longitude: a.longitude,
+                ban_id: a.id.clone(),
+            })
+    }
+}
+
+/// `normalize_town` plus expansion of the usual street type abbreviations.
+fn normalize_street(street: &str) -> String {
+    normalize_town(street)
+        .split(' ')
+        .map(|word| match word {
+            "AV" | "AVE" => "AVENUE",
+            "BD" | "BLD" => "BOULEVARD",
+            "CHE" | "CHEM" => "CHEMIN",
+            "IMP" => "IMPASSE",
+            "PL" => "PLACE",
+            "R" => "RUE",
+            "RTE" => "ROUTE",
+            "ALL" => "ALLEE",
+            "FBG" => "FAUBOURG",
+            "QU" => "QUAI",
+            other => other,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn normalize_house_number(number: &str) -> String {
+    number
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ban(id: &str, number: Option<&str>, street: &str) -> BanAddress {
+        BanAddress {
+            id: id.to_string(),
+            house_number: number.map(str::to_string),
+            street_name: street.to_string(),
+            post_code: "33500".to_string(),
+            town_name: "Libourne".to_string(),
+            insee_code: "33243".to_string(),
+            latitude: 44.915,
+            longitude: -0.244,
+        }
+    }
+
+    fn index() -> BanIndex {
+        BanIndex::new(vec![
+            ban("33243_1234_00022_bis", Some("22BIS"), "Rue des Fleurs"),
+            ban("33243_1234_00024", Some("24"), "Rue des Fleurs"),
+            ban("33243_0456_00001", Some("1"), "Avenue de l'Europe"),
+        ])
+    }
+
+    fn iso(number: Option<&str>, street: Option<&str>, post_code: &str) -> ISO20022Address {
+        ISO20022Address {
+            building_number: number.map(str::to_string),
+            street_name: street.map(str::to_string),
+            post_code: Some(post_code.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_verify_exact_house() {
+        let result = index().verify(&iso(Some("22 bis"), Some("RUE DES FLEURS"), "33500"));
+
+        assert_eq!(result.quality, MatchQuality::ExactHouse);
+        assert_eq!(result.ban_id, Some("33243_1234_00022_bis".to_string()));
+        assert_eq!(result.street_name, Some("Rue des Fleurs".to_string()));
+        assert_eq!(result.building_number, Some("22BIS".to_string()));
+    }
+
+    #[test]
+    fn test_verify_street_only_with_misspelling() {
+        let result = index().verify(&iso(Some("99"), Some("AV DE L EUROP"), "33500"));
+
+        assert_eq!(result.quality, MatchQuality::StreetOnly);
+        assert_eq!(result.street_name, Some("Avenue de l'Europe".to_string()));
+        assert_eq!(result.ban_id, None);
+    }
+
+    #[test]
+    fn test_geocode_exact_house_only() {
+        let location = index()
+            .geocode(&iso(Some("24"), Some("Rue des Fleurs"), "33500"))
+            .unwrap();
+        assert_eq!(location.ban_id, "33243_1234_00024");
+        assert_eq!(location.latitude, 44.915);
+        assert_eq!(location.longitude, -0.244);
+
+        assert!(
+            index()
+                .geocode(&iso(Some("99"), Some("Rue des Fleurs"), "33500"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_verify_commune_only_and_not_found() {
+        let commune = index().verify(&iso(Some("1"), Some("Rue Inconnue"), "33500"));
+        assert_eq!(commune.quality, MatchQuality::CommuneOnly);
+        assert_eq!(commune.town_name, Some("Libourne".to_string()));
+        assert_eq!(commune.street_name, None);
+
+        let missing = index().verify(&iso(Some("1"), Some("Rue des Fleurs"), "75001"));
+        assert_eq!(missing.quality, MatchQuality::NotFound);
+    }
+
+    #[test]
+    fn test_verify_foreign_country_not_found() {
+        let foreign = ISO20022Address {
+            country: Some("BE".to_string()),
+            ..iso(Some("24"), Some("Rue des Fleurs"), "33500")
+        };
+        assert_eq!(index().verify(&foreign).quality, MatchQuality::NotFound);
+
+        let french = ISO20022Address {
+            country: Some("FR".to_string()),
+            ..foreign
+        };
+        assert_eq!(index().verify(&french).quality, MatchQuality::ExactHouse);
+    }
+
+    #[test]
+    fn test_verify_commune_only_matches_town_name() {
+        // Code postal partagé par deux communes
+        let mut index = index();
+        index.extend([BanAddress {
+            town_name: "Arveyres".to_string(),
+            insee_code: "33012".to_string(),
+            ..ban("33012_0001_00003", Some("3"), "Chemin du Port")
+        }]);
+
+        let arveyres = ISO20022Address {
+            town_name: Some("ARVEYRES".to_string()),
+            ..iso(Some("1"), Some("Rue Inconnue"), "33500")
+        };
+        let result = index.verify(&arveyres);
+        assert_eq!(result.quality, MatchQuality::CommuneOnly);
+        assert_eq!(result.town_name, Some("Arveyres".to_string()));
+
+        let without_street = ISO20022Address {
+            street_name: None,
+            ..arveyres.clone()
+        };
+        assert_eq!(
+            index.verify(&without_street).town_name,
+            Some("Arveyres".to_string())
+        );
+
+        let unknown_town = ISO20022Address {
+            town_name: Some("SAINT-EMILION".to_string()),
+            ..arveyres
+        };
+        assert_eq!(index.verify(&unknown_town).quality, MatchQuality::NotFound);
+    }
+}
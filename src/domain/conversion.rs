@@ -0,0 +1,266 @@
+use crate::domain::formats::{AddressLines, NationalAddressFormat};
+use crate::domain::models::{AddressKind, FrenchAddress, ISO20022Address};
+use crate::domain::postal_reference::{PostalReference, TownCorrectionMode, normalize_town};
+use crate::domain::validation::{
+    ValidationProfile, validate_iso20022_address, validate_postal_consistency,
+    validate_with_profile,
+};
+use serde::Serialize;
+
+/// Result of a conversion that stores nothing: the converted address when the
+/// input is valid, the validation errors otherwise, and what was done.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversionOutcome<T> {
+    pub address: Option<T>,
+    pub errors: Vec<String>,
+    pub report: ConversionReport,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConversionReport {
+    /// Country whose postal layout the lines follow.
+    pub layout: String,
+    /// Line 6 as received, when the postal reference replaced its town.
+    pub corrected_line6: Option<String>,
+    /// Structured fields holding a value.
+    pub filled_fields: Vec<&'static str>,
+    /// Structured fields the lines cannot carry: they differ once the lines
+    /// are read back.
+    pub lost_fields: Vec<&'static str>,
+    /// The ISO address checked against each validation profile.
+    pub profiles: Vec<ProfileCheck>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileCheck {
+    pub profile: ValidationProfile,
+    pub error: Option<String>,
+}
+
+impl<T> ConversionOutcome<T> {
+    pub fn is_valid(&self) -> bool {
+        self.address.is_some()
+    }
+}
+
+/// Converts postal lines to ISO 20022 with `format`; French lines are also
+/// checked against `reference` when given, their town first replaced by the
+/// official delivery label when the reference auto-corrects.
+pub fn to_checked_iso(
+    mut lines: AddressLines,
+    kind: AddressKind,
+    format: &dyn NationalAddressFormat,
+    reference: Option<&PostalReference>,
+) -> Result<ISO20022Address, String> {
+    let Some(reference) = reference.filter(|_| format.country_code() == "FR") else {
+        return format.to_iso(lines, kind);
+    };
+    if let Some(corrected) = corrected_line6(&lines, reference) {
+        lines.lines[5] = Some(corrected);
+    }
+    let french = FrenchAddress::from(lines.clone());
+    let iso = format.to_iso(lines, kind)?;
+    validate_postal_consistency(&french, reference)
+        .map_err(|e| format!("Validation error: {:?}", e))?;
+    Ok(iso)
+}
+
+/// Line 6 with the official town, when `reference` corrects a misspelt one.
+fn corrected_line6(lines: &AddressLines, reference: &PostalReference) -> Option<String> {
+    if reference.correction_mode() != TownCorrectionMode::AutoCorrect {
+        return None;
+    }
+    lines.lines[5]
+        .as_deref()
+        .and_then(|line6| reference.correct_line6(line6))
+}
+
+/// Converts postal lines to ISO 20022 like `to_checked_iso`, reporting the
+/// town correction, the fields filled and lost, and the profile checks.
+pub fn lines_to_iso(
+    lines: AddressLines,
+    kind: AddressKind,
+    format: &dyn NationalAddressFormat,
+    reference: Option<&PostalReference>,
+) -> ConversionOutcome<ISO20022Address> {
+    let mut report = ConversionReport {
+        layout: format.country_code().to_string(),
+        ..Default::default()
+    };
+    if format.country_code() == "FR"
+        && reference.is_some_and(|reference| corrected_line6(&lines, reference).is_some())
+    {
+        report.corrected_line6 = lines.lines[5].clone();
+    }
+
+    match to_checked_iso(lines, kind, format, reference) {
+        Ok(iso) => {
+            complete_report(&mut report, &iso, format);
+            ConversionOutcome {
+                address: Some(iso),
+                errors: Vec::new(),
+                report,
+            }
+        }
+        Err(e) => ConversionOutcome {
+            address: None,
+            errors: vec![e],
+            report,
+        },
+    }
+}
+
+/// Lays an ISO 20022 address out with `format` once it passes
+/// `validate_iso20022_address`, reporting the fields the lines leave out.
+pub fn iso_to_lines(
+    iso: &ISO20022Address,
+    format: &dyn NationalAddressFormat,
+) -> ConversionOutcome<AddressLines> {
+    let mut report = ConversionReport {
+        layout: format.country_code().to_string(),
+        ..Default::default()
+    };
+    complete_report(&mut report, iso, format);
+
+    match validate_iso20022_address(iso) {
+        Ok(()) => ConversionOutcome {
+            address: Some(format.to_lines(iso)),
+            errors: Vec::new(),
+            report,
+        },
+        Err(e) => ConversionOutcome {
+            address: None,
+            errors: vec![format!("Validation error: {:?}", e)],
+            report,
+        },
+    }
+}
+
+fn complete_report(
+    report: &mut ConversionReport,
+    iso: &ISO20022Address,
+    format: &dyn NationalAddressFormat,
+) {
+    report.filled_fields = iso
+        .named_fields()
+        .into_iter()
+        .filter(|(_, value)| value.is_some())
+        .map(|(name, _)| name)
+        .collect();
+
+    let read_back = format.to_iso(format.to_lines(iso), iso.kind).ok();
+    let normalized = |value: &Option<String>| value.as_deref().map(normalize_town);
+    report.lost_fields = iso
+        .named_fields()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, (_, value))| value.is_some())
+        .filter(|(index, (_, value))| {
+            read_back
+                .as_ref()
+                .is_none_or(|back| normalized(back.named_fields()[*index].1) != normalized(value))
+        })
+        .map(|(_, (name, _))| name)
+        .collect();
+
+    report.profiles = [
+        ValidationProfile::Basic,
+        ValidationProfile::Cbpr,
+        ValidationProfile::Sepa,
+    ]
+    .into_iter()
+    .map(|profile| ProfileCheck {
+        profile,
+        error: validate_with_profile(iso, &[], profile)
+            .err()
+            .map(|e| format!("Validation error: {:?}", e)),
+    })
+    .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::formats::FrenchFormat;
+
+    #[test]
+    fn test_lines_to_iso_report() {
+        let lines = AddressLines {
+            id: "1".to_string(),
+            lines: [
+                Some("DURAND SA".to_string()),
+                Some("SERVICE ACHATS".to_string()),
+                None,
+                Some("22BIS RUE DES FLEURS".to_string()),
+                Some("BP 40122".to_string()),
+                Some("33506 LIBOURNE CEDEX".to_string()),
+                Some("FRANCE".to_string()),
+            ],
+        };
+
+        let outcome = lines_to_iso(lines.clone(), AddressKind::Company, &FrenchFormat, None);
+
+        assert!(outcome.is_valid());
+        assert_eq!(outcome.report.layout, "FR");
+        assert!(outcome.report.filled_fields.contains(&"post_box"));
+        assert!(outcome.report.lost_fields.is_empty());
+        assert!(outcome.report.profiles.iter().all(|p| p.error.is_none()));
+
+        // With the postal reference, a misspelt town is corrected, a wrong one refused
+        let reference = PostalReference::from_csv_str(
+            "#Code_commune_INSEE;Nom_de_la_commune;Code_postal;Libellé_d_acheminement;Ligne_5
+33243;LIBOURNE;33500;LIBOURNE;",
+        )
+        .unwrap()
+        .with_correction_mode(TownCorrectionMode::AutoCorrect);
+        let mut misspelt = lines.clone();
+        misspelt.lines[5] = Some("33500 LIBOUNRE".to_string());
+        let outcome = lines_to_iso(
+            misspelt,
+            AddressKind::Company,
+            &FrenchFormat,
+            Some(&reference),
+        );
+        assert_eq!(
+            outcome.report.corrected_line6.as_deref(),
+            Some("33500 LIBOUNRE")
+        );
+        assert_eq!(
+            outcome.address.unwrap().town_name.as_deref(),
+            Some("LIBOURNE")
+        );
+
+        let mut wrong = lines;
+        wrong.lines[5] = Some("33500 PARIS".to_string());
+        let outcome = lines_to_iso(wrong, AddressKind::Company, &FrenchFormat, Some(&reference));
+        assert!(outcome.errors[0].contains("PostalCodeTownMismatch"));
+    }
+
+    #[test]
+    fn test_iso_to_lines_lost_fields() {
+        let iso = ISO20022Address {
+            id: "2".to_string(),
+            recipient_name: Some("Jean Dupont".to_string()),
+            street_name: Some("RUE DES LILAS".to_string()),
+            building_number: Some("15".to_string()),
+            district_name: Some("PRESQU'ILE".to_string()),
+            post_code: Some("69001".to_string()),
+            town_name: Some("LYON".to_string()),
+            country: Some("FR".to_string()),
+            ..Default::default()
+        };
+
+        let outcome = iso_to_lines(&iso, &FrenchFormat);
+
+        assert!(outcome.is_valid());
+        assert_eq!(outcome.report.lost_fields, vec!["district_name"]);
+
+        let invalid = ISO20022Address {
+            country: Some("FRA".to_string()),
+            ..iso
+        };
+        let outcome = iso_to_lines(&invalid, &FrenchFormat);
+        assert!(outcome.address.is_none());
+        assert!(outcome.errors[0].contains("InvalidCountryCode"));
+    }
+}
@@ -0,0 +1,293 @@
+use crate::domain::postal_reference::normalize_town;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Postal conventions of a destination country.
+#[derive(Debug)]
+pub struct Country {
+    /// ISO 3166-1 alpha-2 code.
+    pub code: &'static str,
+    /// French name first (as printed on line 7), then the usual spellings.
+    pub names: &'static [&'static str],
+    /// `None` when the country has no postcodes.
+    pub postcode_pattern: Option<&'static str>,
+    /// "LONDON SW1A 1AA" rather than "10115 BERLIN".
+    pub postcode_after_town: bool,
+    /// "NEW YORK NY 10001".
+    pub state_before_postcode: bool,
+}
+
+/// Postcode and town read from a destination line such as "10115 BERLIN".
+#[derive(Debug, Clone, PartialEq)]
+pub struct TownLine {
+    pub post_code: Option<String>,
+    pub town: String,
+    pub country_sub_division: Option<String>,
+}
+
+const fn country(
+    code: &'static str,
+    names: &'static [&'static str],
+    postcode_pattern: Option<&'static str>,
+) -> Country {
+    Country {
+        code,
+        names,
+        postcode_pattern,
+        postcode_after_town: false,
+        state_before_postcode: false,
+    }
+}
+
+const fn country_town_first(
+    code: &'static str,
+    names: &'static [&'static str],
+    postcode_pattern: &'static str,
+    state_before_postcode: bool,
+) -> Country {
+    Country {
+        code,
+        names,
+        postcode_pattern: Some(postcode_pattern),
+        postcode_after_town: true,
+        state_before_postcode,
+    }
+}
+
+pub static COUNTRIES: &[Country] = &[
+    country("FR", &["FRANCE"], Some(r"\d{5}")),
+    country("MC", &["MONACO"], Some(r"980\d{2}")),
+    country(
+        "DE",
+        &["ALLEMAGNE", "DEUTSCHLAND", "GERMANY"],
+        Some(r"(?:D-)?\d{5}"),
+    ),
+    country(
+        "BE",
+        &["BELGIQUE", "BELGIE", "BELGIEN", "BELGIUM"],
+        Some(r"(?:B-)?\d{4}"),
+    ),
+    country("LU", &["LUXEMBOURG", "LUXEMBURG"], Some(r"(?:L-)?\d{4}")),
+    country(
+        "CH",
+        &["SUISSE", "SCHWEIZ", "SVIZZERA", "SWITZERLAND"],
+        Some(r"(?:CH-)?\d{4}"),
+    ),
+    country("ES", &["ESPAGNE", "ESPANA", "SPAIN"], Some(r"\d{5}")),
+    country("IT", &["ITALIE", "ITALIA", "ITALY"], Some(r"(?:I-)?\d{5}")),
+    country("PT", &["PORTUGAL"], Some(r"\d{4}-\d{3}")),
+    country(
+        "NL",
+        &["PAYS-BAS", "NEDERLAND", "NETHERLANDS"],
+        Some(r"\d{4} ?[A-Z]{2}"),
+    ),
+    country(
+        "AT",
+        &["AUTRICHE", "OSTERREICH", "AUSTRIA"],
+        Some(r"(?:A-)?\d{4}"),
+    ),
+    country(
+        "DK",
+        &["DANEMARK", "DANMARK", "DENMARK"],
+        Some(r"(?:DK-)?\d{4}"),
+    ),
+    country(
+        "SE",
+        &["SUEDE", "SVERIGE", "SWEDEN"],
+        Some(r"(?:SE-)?\d{3} ?\d{2}"),
+    ),
+    country("NO", &["NORVEGE", "NORGE", "NORWAY"], Some(r"\d{4}")),
+    country(
+        "FI",
+        &["FINLANDE", "SUOMI", "FINLAND"],
+        Some(r"(?:FI-)?\d{5}"),
+    ),
+    country("PL", &["POLOGNE", "POLSKA", "POLAND"], Some(r"\d{2}-\d{3}")),
+    country(
+        "CZ",
+        &["REPUBLIQUE TCHEQUE", "TCHEQUIE", "CZECHIA"],
+        Some(r"\d{3} ?\d{2}"),
+    ),
+    country("GR", &["GRECE", "GREECE"], Some(r"\d{3} ?\d{2}")),
+    country("MA", &["MAROC", "MOROCCO"], Some(r"\d{5}")),
+    country("TN", &["TUNISIE", "TUNISIA"], Some(r"\d{4}")),
+    country("DZ", &["ALGERIE", "ALGERIA"], Some(r"\d{5}")),
+    country("JP", &["JAPON", "JAPAN"], Some(r"\d{3}-\d{4}")),
+    country("IE", &["IRLANDE", "IRELAND"], None), // Eircodes are optional on mail
+    country("HK", &["HONG KONG"], None),
+    country("AE", &["EMIRATS ARABES UNIS", "UNITED ARAB EMIRATES"], None),
+    country_town_first(
+        "GB",
+        &["ROYAUME-UNI", "UNITED KINGDOM", "GRANDE-BRETAGNE"],
+        r"[A-Z]{1,2}\d[A-Z\d]? ?\d[A-Z]{2}",
+        false,
+    ),
+    country_town_first(
+        "US",
+        &["ETATS-UNIS", "USA", "UNITED STATES"],
+        r"\d{5}(?:-\d{4})?",
+        true,
+    ),
+    country_town_first("CA", &["CANADA"], r"[A-Z]\d[A-Z] ?\d[A-Z]\d", true),
+    country_town_first("AU", &["AUSTRALIE", "AUSTRALIA"], r"\d{4}", true),
+];
+
+/// Postcode and destination line regexes of the countries with postcodes, by code.
+static REGEXES: LazyLock<HashMap<&'static str, (Regex, Regex)>> = LazyLock::new(|| {
+    COUNTRIES
+        .iter()
+        .filter_map(|c| {
+            let pattern = c.postcode_pattern?;
+            let post_code = Regex::new(&format!("^(?i:{})$", pattern)).unwrap();
+            let town_line = Regex::new(&c.town_line_layout(pattern)).unwrap();
+            Some((c.code, (post_code, town_line)))
+        })
+        .collect()
+});
+
+/// Finds a country by ISO code or by any of its names (accents and case ignored).
+pub fn find_country(name_or_code: &str) -> Option<&'static Country> {
+    let value = normalize_town(name_or_code);
+    COUNTRIES
+        .iter()
+        .find(|c| c.code == value || c.names.iter().any(|name| normalize_town(name) == value))
+}
+
+impl Country {
+    pub fn french_name(&self) -> &'static str {
+        self.names[0]
+    }
+
+    /// True when the country has no postcode system or the value follows its pattern.
+    pub fn is_valid_post_code(&self, post_code: &str) -> bool {
+        match REGEXES.get(self.code) {
+            Some((pattern, _)) => pattern.is_match(post_code.trim()),
+            None => true,
+        }
+    }
+
+    /// Reads the destination line of a foreign address ("10115 BERLIN",
+    /// "LONDON SW1A 1AA", "NEW YORK NY 10001"). Returns `None` when the country
+    /// uses postcodes and none can be found where it is expected.
+    pub fn parse_town_line(&self, line: &str) -> Option<TownLine> {
+        let line = line.trim();
+        let Some((_, layout)) = REGEXES.get(self.code) else {
+            return (!line.is_empty()).then(|| TownLine {
+                post_code: None,
+                town: line.to_string(),
+                country_sub_division: None,
+            });
+        };
+
+        let caps = layout.captures(line)?;
+        Some(TownLine {
+            post_code: caps.name("code").map(|m| m.as_str().to_uppercase()),
+            town: caps["town"].trim().to_string(),
+            country_sub_division: caps.name("state").map(|m| m.as_str().to_uppercase()),
+        })
+    }
+
+    fn town_line_layout(&self, pattern: &str) -> String {
+        match (self.postcode_after_town, self.state_before_postcode) {
+            (false, _) => format!(r"^(?i)(?P<code>{})\s+(?P<town>.+)$", pattern),
+            (true, false) => format!(r"^(?i)(?P<town>.+?)\s+(?P<code>{})$", pattern),
+            (true, true) => format!(
+                r"^(?i)(?P<town>.+?)\s+(?:(?P<state>[A-Z]{{2,3}})\s+)?(?P<code>{})$",
+                pattern
+            ),
+        }
+    }
+
+    /// Writes the destination line back in the country's order.
+    pub fn format_town_line(
+        &self,
+        post_code: Option<&str>,
+        town: Option<&str>,
+        country_sub_division: Option<&str>,
+    ) -> Option<String> {
+        let parts: Vec<&str> = if self.postcode_after_town {
+            let state = country_sub_division.filter(|_| self.state_before_postcode);
+            [town, state, post_code].into_iter().flatten().collect()
+        } else {
+            [post_code, town].into_iter().flatten().collect()
+        };
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_country_by_name_or_code() {
+        assert_eq!(find_country("Allemagne").unwrap().code, "DE");
+        assert_eq!(find_country("ROYAUME-UNI").unwrap().code, "GB");
+        assert_eq!(find_country("États-Unis").unwrap().code, "US");
+        assert_eq!(find_country("nl").unwrap().code, "NL");
+        assert!(find_country("ATLANTIDE").is_none());
+    }
+
+    #[test]
+    fn test_is_valid_post_code() {
+        let valid = [
+            ("GB", "SW1A 1AA"),
+            ("NL", "1234 AB"),
+            ("CA", "K1A 0B1"),
+            ("US", "10001-1234"),
+            ("LU", "L-1234"),
+            ("PT", "1000-001"),
+        ];
+        for (code, post_code) in valid {
+            assert!(
+                find_country(code).unwrap().is_valid_post_code(post_code),
+                "{code} {post_code}"
+            );
+        }
+
+        assert!(!find_country("DE").unwrap().is_valid_post_code("1234"));
+        assert!(!find_country("NL").unwrap().is_valid_post_code("1234"));
+        assert!(!find_country("US").unwrap().is_valid_post_code("1000"));
+    }
+
+    #[test]
+    fn test_parse_town_line() {
+        let de = find_country("DE")
+            .unwrap()
+            .parse_town_line("10115 BERLIN")
+            .unwrap();
+        assert_eq!(de.post_code, Some("10115".to_string()));
+        assert_eq!(de.town, "BERLIN");
+
+        let gb = find_country("GB")
+            .unwrap()
+            .parse_town_line("LONDON SW1A 1AA")
+            .unwrap();
+        assert_eq!(gb.post_code, Some("SW1A 1AA".to_string()));
+        assert_eq!(gb.town, "LONDON");
+
+        let us = find_country("US")
+            .unwrap()
+            .parse_town_line("NEW YORK NY 10001")
+            .unwrap();
+        assert_eq!(us.town, "NEW YORK");
+        assert_eq!(us.country_sub_division, Some("NY".to_string()));
+        assert_eq!(us.post_code, Some("10001".to_string()));
+
+        assert!(
+            find_country("DE")
+                .unwrap()
+                .parse_town_line("BERLIN")
+                .is_none()
+        );
+        assert_eq!(
+            find_country("US").unwrap().format_town_line(
+                Some("10001"),
+                Some("NEW YORK"),
+                Some("NY")
+            ),
+            Some("NEW YORK NY 10001".to_string())
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::domain::formats::continental::ContinentalLayout;
+use crate::domain::formats::{AddressLines, NationalAddressFormat};
+use crate::domain::models::{AddressKind, ISO20022Address};
+use regex::Regex;
+use std::sync::LazyLock;
+
+static LAYOUT: ContinentalLayout = ContinentalLayout {
+    country_code: "BE",
+    postcode: LazyLock::new(|| Regex::new(r"^(?:B-)?(\d{4})\s+(.+)$").unwrap()),
+    box_markers: &["bte", "boîte", "bus", "box"],
+    max_line_length: 38,
+};
+
+/// bpost layout: "Rue de la Loi 16 bte 3" then "1000 BRUXELLES".
+pub struct BelgianFormat;
+
+impl NationalAddressFormat for BelgianFormat {
+    fn country_code(&self) -> &'static str {
+        LAYOUT.country_code
+    }
+
+    fn to_iso(&self, address: AddressLines, kind: AddressKind) -> Result<ISO20022Address, String> {
+        LAYOUT.to_iso(address, kind)
+    }
+
+    fn to_lines(&self, iso: &ISO20022Address) -> AddressLines {
+        LAYOUT.to_lines(iso)
+    }
+
+    fn split_street_line(&self, line: &str) -> (Option<String>, Option<String>) {
+        LAYOUT.split_street_line(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: [Option<&str>; 7]) -> AddressLines {
+        AddressLines {
+            id: "1".to_string(),
+            lines: lines.map(|l| l.map(str::to_string)),
+        }
+    }
+
+    #[test]
+    fn test_to_iso_with_mailbox() {
+        let address = lines([
+            Some("Marie DUPONT"),
+            Some("Appartement 12"),
+            None,
+            Some("Rue de la Loi 16 bte 3"),
+            None,
+            Some("1000 BRUXELLES"),
+            Some("BELGIQUE"),
+        ]);
+
+        let iso = BelgianFormat
+            .to_iso(address, AddressKind::Particular)
+            .unwrap();
+
+        assert_eq!(iso.recipient_name, Some("Marie DUPONT".to_string()));
+        assert_eq!(iso.street_name, Some("Rue de la Loi".to_string()));
+        assert_eq!(iso.building_number, Some("16 bte 3".to_string()));
+        assert_eq!(iso.room, Some("Appartement 12".to_string()));
+        assert_eq!(iso.post_code, Some("1000".to_string()));
+        assert_eq!(iso.town_name, Some("BRUXELLES".to_string()));
+        assert_eq!(iso.country, Some("BE".to_string()));
+
+        let address = BelgianFormat.to_lines(&iso);
+        assert_eq!(address.lines[1], Some("Appartement 12".to_string()));
+        assert_eq!(address.lines[3], Some("Rue de la Loi 16 bte 3".to_string()));
+    }
+
+    #[test]
+    fn test_to_iso_rejects_french_postcode() {
+        let address = lines([
+            Some("SA DURAND"),
+            None,
+            None,
+            Some("Wetstraat 16"),
+            None,
+            Some("75001 PARIS"),
+            None,
+        ]);
+
+        assert!(BelgianFormat.to_iso(address, AddressKind::Company).is_err());
+    }
+
+    #[test]
+    fn test_to_lines_round_trip() {
+        let iso = ISO20022Address {
+            id: "1".to_string(),
+            recipient_name: Some("SA DURAND".to_string()),
+            kind: AddressKind::Company,
+            department: Some("Service achats".to_string()),
+            street_name: Some("Wetstraat".to_string()),
+            building_number: Some("16".to_string()),
+            post_code: Some("1000".to_string()),
+            town_name: Some("BRUSSEL".to_string()),
+            country: Some("BE".to_string()),
+            ..Default::default()
+        };
+
+        let address = BelgianFormat.to_lines(&iso);
+        assert_eq!(address.lines[3], Some("Wetstraat 16".to_string()));
+        assert_eq!(address.lines[5], Some("1000 BRUSSEL".to_string()));
+        assert_eq!(address.lines[6], Some("BELGIQUE".to_string()));
+
+        let back = BelgianFormat.to_iso(address, AddressKind::Company).unwrap();
+        assert_eq!(back.department, iso.department);
+        assert_eq!(back.street_name, iso.street_name);
+        assert_eq!(back.building_number, iso.building_number);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::domain::countries::find_country;
+use crate::domain::formats::AddressLines;
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::domain::validation::{ValidationError, validate_iso20022_address};
+use regex::Regex;
+use std::sync::LazyLock;
+
+const LINE_FIELDS: [&str; 7] = [
+    "line1", "line2", "line3", "line4", "line5", "line6", "line7",
+];
+
+/// A house number: digits with an optional letter, or a range ("12a", "12-14").
+static HOUSE_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d+[a-zA-Z]?(?:[-/]\d+[a-zA-Z]?)?$").unwrap());
+
+/// Layout shared by the countries that write the building number after the
+/// street name and put a numeric postcode in front of the town.
+pub(crate) struct ContinentalLayout {
+    pub country_code: &'static str, // Names are looked up in `countries::COUNTRIES`
+    pub postcode: LazyLock<Regex>,  // Captures the postcode and the town
+    pub box_markers: &'static [&'static str], // Mailbox suffixes of the street line ("bte 3")
+    pub max_line_length: usize,
+}
+
+impl ContinentalLayout {
+    pub fn to_iso(
+        &self,
+        address: AddressLines,
+        kind: AddressKind,
+    ) -> Result<ISO20022Address, String> {
+        self.validate(&address)
+            .map_err(|e| format!("Validation error: {:?}", e))?;
+        let [line1, line2, line3, line4, line5, line6, _] = address.lines.map(non_empty);
+
+        let mut iso = ISO20022Address {
+            id: address.id,
+            recipient_name: line1,
+            kind,
+            floor: line3,
+            post_box: line5,
+            country: Some(self.country_code.to_string()),
+            ..Default::default()
+        };
+
+        match kind {
+            AddressKind::Company => iso.department = line2,
+            AddressKind::Particular => iso.room = line2,
+        }
+
+        if let Some(street_line) = line4 {
+            let (street_name, building_number) = self.split_street_line(&street_line);
+            iso.street_name = street_name;
+            iso.building_number = building_number;
+        }
+
+        if let Some(caps) = line6.as_deref().and_then(|l6| self.postcode.captures(l6)) {
+            iso.post_code = caps.get(1).map(|m| m.as_str().to_string());
+            iso.town_name = caps.get(2).map(|m| m.as_str().to_string());
+        }
+
+        validate_iso20022_address(&iso).map_err(|e| format!("Validation error: {:?}", e))?;
+        Ok(iso)
+    }
+
+    pub fn to_lines(&self, iso: &ISO20022Address) -> AddressLines {
+        let second_line = match iso.kind {
+            AddressKind::Company => iso.department.clone().or(iso.sub_department.clone()),
+            AddressKind::Particular => iso.room.clone(),
+        };
+        let street_line = match (&iso.street_name, &iso.building_number) {
+            (Some(street), Some(number)) => Some(format!("{} {}", street, number)),
+            (street, number) => street.clone().or(number.clone()),
+        };
+        let town_line = match (&iso.post_code, &iso.town_name) {
+            (Some(code), Some(town)) => Some(format!("{} {}", code, town)),
+            (code, town) => code.clone().or(town.clone()),
+        };
+        let country_line = iso.country.as_ref().map(|c| match find_country(c) {
+            Some(country) if country.code == self.country_code => country.french_name().to_string(),
+            _ => c.clone(),
+        });
+
+        AddressLines {
+            id: iso.id.clone(),
+            lines: [
+                iso.recipient_name.clone(),
+                second_line,
+                iso.floor.clone(),
+                street_line,
+                iso.post_box.clone(),
+                town_line,
+                country_line,
+            ],
+        }
+    }
+
+    fn validate(&self, address: &AddressLines) -> Result<(), ValidationError> {
+        for (line, field) in address.lines.iter().zip(LINE_FIELDS) {
+            if let Some(line) = line
+                && line.chars().count() > self.max_line_length
+            {
+                return Err(ValidationError::TooLong {
+                    field,
+                    max_len: self.max_line_length,
+                    actual_len: line.chars().count(),
+                });
+            }
+        }
+
+        match address.lines[5].as_deref().map(str::trim) {
+            None | Some("") => return Err(ValidationError::EmptyField { field: "line6" }),
+            Some(line6) if !self.postcode.is_match(line6) => {
+                return Err(ValidationError::InvalidPostalCode {
+                    value: line6.to_string(),
+                });
+            }
+            Some(_) => {}
+        }
+
+        if let Some(line7) = address.lines[6].as_deref().map(str::trim)
+            && !line7.is_empty()
+            && !self.is_country(line7)
+        {
+            return Err(ValidationError::InvalidCountryCode {
+                value: line7.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn is_country(&self, value: &str) -> bool {
+        find_country(value).is_some_and(|country| country.code == self.country_code)
+    }
+
+    /// Splits "Rue de la Loi 16 bte 3" into the street and the number. The mailbox
+    /// keeps its marker and stays with the number ("16 bte 3"), so that line 2 is
+    /// left to the room and the street line prints back unchanged. The last token
+    /// is a number only if it looks like one: "Straße des 17. Juni" has none.
+    pub fn split_street_line(&self, line: &str) -> (Option<String>, Option<String>) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let marker = tokens
+            .iter()
+            .position(|t| self.box_markers.iter().any(|m| t.eq_ignore_ascii_case(m)));
+        let (street_tokens, mailbox) = match marker {
+            Some(pos) => (&tokens[..pos], Some(tokens[pos..].join(" "))),
+            None => (&tokens[..], None),
+        };
+        let mailbox = mailbox.filter(|m| m.contains(' '));
+
+        let is_letter = |t: &str| t.len() == 1 && t.chars().all(|c| c.is_ascii_alphabetic());
+        let number_start = match street_tokens {
+            [_, .., number, letter] if HOUSE_NUMBER.is_match(number) && is_letter(letter) => {
+                Some(street_tokens.len() - 2)
+            }
+            [_, .., number] if HOUSE_NUMBER.is_match(number) => Some(street_tokens.len() - 1),
+            _ => None,
+        };
+        let (street, number) = match number_start {
+            Some(pos) => (&street_tokens[..pos], Some(street_tokens[pos..].join(" "))),
+            None => (street_tokens, None),
+        };
+        let number = match (number, mailbox) {
+            (Some(number), Some(mailbox)) => Some(format!("{} {}", number, mailbox)),
+            (number, mailbox) => number.or(mailbox),
+        };
+        (Some(street.join(" ")).filter(|s| !s.is_empty()), number)
+    }
+}
+
+fn non_empty(line: Option<String>) -> Option<String> {
+    line.map(|l| l.trim().to_string()).filter(|l| !l.is_empty())
+}
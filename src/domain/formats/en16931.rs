@@ -0,0 +1,215 @@
+use crate::domain::countries::find_country;
+use crate::domain::formats::french::is_post_box;
+use crate::domain::formats::{AddressLines, FrenchFormat, format_for_country};
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::domain::validation::{ValidationError, validate_iso20022_address};
+use serde::{Deserialize, Serialize};
+
+/// Seller / buyer postal address of an EN 16931 invoice (BG-5, BG-8), as carried
+/// by UBL `cac:PostalAddress` and CII `ram:PostalTradeAddress`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InvoiceAddress {
+    /// BT-35: UBL `StreetName`, CII `LineOne`.
+    pub line_one: Option<String>,
+    /// BT-36: UBL `AdditionalStreetName`, CII `LineTwo`.
+    pub line_two: Option<String>,
+    /// BT-162: UBL `AddressLine/Line`, CII `LineThree`.
+    pub line_three: Option<String>,
+    /// BT-37: `CityName`.
+    pub city_name: Option<String>,
+    /// BT-38: UBL `PostalZone`, CII `PostcodeCode`.
+    pub postal_zone: Option<String>,
+    /// BT-39: UBL `CountrySubentity`, CII `CountrySubDivisionName`.
+    pub country_subentity: Option<String>,
+    /// BT-40: UBL `Country/IdentificationCode`, CII `CountryID`.
+    pub country_code: Option<String>,
+}
+
+impl InvoiceAddress {
+    /// Lays the address out with the destination country's format (French
+    /// layout for the others): the street line goes to line one, the building
+    /// or floor line to line two and the post box to line three. Department and
+    /// room have no slot in EN 16931 and are left out.
+    pub fn from_iso(iso: &ISO20022Address) -> Self {
+        let format = iso
+            .country
+            .as_deref()
+            .and_then(format_for_country)
+            .unwrap_or(&FrenchFormat);
+        let [_, _, building, street, post_box, _, _] = format.to_lines(iso).lines;
+
+        Self {
+            line_one: street,
+            line_two: building,
+            line_three: post_box,
+            city_name: iso.town_name.clone(),
+            postal_zone: iso.post_code.clone(),
+            country_subentity: iso.country_sub_division.clone(),
+            country_code: iso.country.clone(),
+        }
+    }
+
+    /// Reads the lines with the destination country's format. Additional lines
+    /// holding a post box (BP, CS, TSA, Postfach, PO Box) become the post box,
+    /// the others the floor.
+    pub fn to_iso(
+        &self,
+        id: String,
+        name: Option<String>,
+        kind: AddressKind,
+    ) -> Result<ISO20022Address, String> {
+        let country_code = self
+            .country_code
+            .as_deref()
+            .map(|c| c.trim().to_uppercase())
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| {
+                format!(
+                    "Validation error: {:?}",
+                    ValidationError::EmptyField {
+                        field: "country_code"
+                    }
+                )
+            })?;
+
+        let (post_boxes, others): (Vec<&String>, Vec<&String>) = [&self.line_two, &self.line_three]
+            .into_iter()
+            .flatten()
+            .filter(|l| !l.trim().is_empty())
+            .partition(|l| is_post_box_line(l));
+        let building = (!others.is_empty()).then(|| {
+            others
+                .iter()
+                .map(|l| l.trim())
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+
+        let town_line = match find_country(&country_code) {
+            Some(country) => country.format_town_line(
+                self.postal_zone.as_deref(),
+                self.city_name.as_deref(),
+                self.country_subentity.as_deref(),
+            ),
+            None => {
+                let parts: Vec<&str> = [&self.postal_zone, &self.city_name]
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .collect();
+                (!parts.is_empty()).then(|| parts.join(" "))
+            }
+        };
+
+        let envelope = AddressLines {
+            id,
+            lines: [
+                name,
+                None,
+                building,
+                self.line_one.clone(),
+                post_boxes.first().map(|l| l.trim().to_string()),
+                town_line,
+                Some(country_code.clone()),
+            ],
+        };
+        let mut iso = format_for_country(&country_code)
+            .unwrap_or(&FrenchFormat)
+            .to_iso(envelope, kind)?;
+
+        // The structured fields of the invoice win over what the town line gave.
+        iso.post_code = self.postal_zone.clone();
+        iso.town_name = self.city_name.clone();
+        iso.country_sub_division = self.country_subentity.clone().or(iso.country_sub_division);
+        validate_iso20022_address(&iso).map_err(|e| format!("Validation error: {:?}", e))?;
+        Ok(iso)
+    }
+}
+
+pub(crate) fn is_post_box_line(line: &str) -> bool {
+    let upper = line.trim().to_uppercase();
+    is_post_box(line)
+        || ["POSTFACH ", "PO BOX ", "P.O. BOX "]
+            .iter()
+            .any(|p| upper.starts_with(p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_iso_french() {
+        let iso = ISO20022Address {
+            id: "1".to_string(),
+            recipient_name: Some("DURAND SA".to_string()),
+            department: Some("SERVICE ACHATS".to_string()),
+            floor: Some("BATIMENT B".to_string()),
+            building_number: Some("22BIS".to_string()),
+            street_name: Some("RUE DES FLEURS".to_string()),
+            post_box: Some("BP 40122".to_string()),
+            post_code: Some("33500".to_string()),
+            town_name: Some("LIBOURNE".to_string()),
+            country: Some("FR".to_string()),
+            ..Default::default()
+        };
+
+        let invoice = InvoiceAddress::from_iso(&iso);
+
+        assert_eq!(invoice.line_one, Some("22BIS RUE DES FLEURS".to_string()));
+        assert_eq!(invoice.line_two, Some("BATIMENT B".to_string()));
+        assert_eq!(invoice.line_three, Some("BP 40122".to_string()));
+        assert_eq!(invoice.postal_zone, Some("33500".to_string()));
+        assert_eq!(invoice.country_code, Some("FR".to_string()));
+
+        let back = invoice
+            .to_iso("1".to_string(), iso.recipient_name.clone(), iso.kind)
+            .unwrap();
+        assert_eq!(back.recipient_name, iso.recipient_name);
+        assert_eq!(back.department, None);
+        assert_eq!(back.floor, iso.floor);
+        assert_eq!(back.building_number, iso.building_number);
+        assert_eq!(back.street_name, iso.street_name);
+        assert_eq!(back.post_box, iso.post_box);
+        assert_eq!(back.post_code, iso.post_code);
+        assert_eq!(back.town_name, iso.town_name);
+        assert_eq!(back.country, iso.country);
+    }
+
+    #[test]
+    fn test_to_iso_german_street_order() {
+        let invoice = InvoiceAddress {
+            line_one: Some("Unter den Linden 77".to_string()),
+            line_two: Some("Postfach 1234".to_string()),
+            city_name: Some("Berlin".to_string()),
+            postal_zone: Some("10117".to_string()),
+            country_code: Some("de".to_string()),
+            ..Default::default()
+        };
+
+        let iso = invoice
+            .to_iso("2".to_string(), None, AddressKind::Company)
+            .unwrap();
+
+        assert_eq!(iso.street_name, Some("Unter den Linden".to_string()));
+        assert_eq!(iso.building_number, Some("77".to_string()));
+        assert_eq!(iso.post_box, Some("Postfach 1234".to_string()));
+        assert_eq!(iso.floor, None);
+        assert_eq!(iso.town_name, Some("Berlin".to_string()));
+        assert_eq!(iso.country, Some("DE".to_string()));
+        assert_eq!(InvoiceAddress::from_iso(&iso).line_one, invoice.line_one);
+    }
+
+    #[test]
+    fn test_to_iso_requires_country() {
+        let invoice = InvoiceAddress {
+            city_name: Some("Paris".to_string()),
+            ..Default::default()
+        };
+        assert!(
+            invoice
+                .to_iso("3".to_string(), None, AddressKind::Company)
+                .is_err()
+        );
+    }
+}
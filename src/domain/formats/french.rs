@@ -0,0 +1,93 @@
+use crate::domain::countries::find_country;
+use crate::domain::formats::{AddressLines, NationalAddressFormat};
+use crate::domain::models::{AddressKind, FrenchAddress, FrenchAddressBuilder, ISO20022Address};
+use crate::domain::usecases::{convert_to_french, convert_to_iso, process_street};
+
+/// NF Z10-011 layout, backed by `FrenchAddressBuilder` and the French conversion use cases.
+pub struct FrenchFormat;
+
+impl NationalAddressFormat for FrenchFormat {
+    fn country_code(&self) -> &'static str {
+        "FR"
+    }
+
+    fn to_iso(&self, address: AddressLines, kind: AddressKind) -> Result<ISO20022Address, String> {
+        let address = FrenchAddress::from(address);
+        let french = FrenchAddressBuilder::new()
+            .id(address.id)
+            .line1(address.line1)
+            .line2(address.line2)
+            .line3(address.line3)
+            .line4(address.line4)
+            .line5(address.line5)
+            .line6(address.line6)
+            .line7(address.line7)
+            .build()?;
+        convert_to_iso(&french, kind).map_err(|e| format!("Validation error: {:?}", e))
+    }
+
+    fn to_lines(&self, iso: &ISO20022Address) -> AddressLines {
+        convert_to_french(iso).into()
+    }
+
+    fn split_street_line(&self, line: &str) -> (Option<String>, Option<String>) {
+        let (building_number, street_name) = process_street(&Some(line.to_string()));
+        (street_name, building_number)
+    }
+}
+
+/// Lays unstructured address lines (street, post box, "33500 LIBOURNE", optional
+/// country line) out on the French envelope, as found in MT 50K / 59 fields or
+/// ISO 20022 `AdrLine`s. The last line naming a country, or else `country`,
+/// gives line 7; without either the address is taken to be in France.
+pub fn envelope_from_free_text(
+    id: String,
+    recipient: Option<String>,
+    lines: &[String],
+    country: Option<&str>,
+) -> AddressLines {
+    let mut rest: Vec<String> = lines
+        .iter()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    let country_line = match rest.last().and_then(|l| find_country(l)) {
+        Some(found) if rest.len() > 1 => {
+            rest.pop();
+            Some(found)
+        }
+        _ => country.and_then(find_country),
+    };
+    let line7 = match (country_line, country) {
+        (Some(found), _) if found.code != "FR" => found.french_name().to_string(),
+        (None, Some(unknown)) => unknown.to_string(),
+        _ => "FRANCE".to_string(),
+    };
+    let line6 = rest.pop();
+
+    let (post_boxes, others): (Vec<String>, Vec<String>) =
+        rest.into_iter().partition(|l| is_post_box(l));
+    let mut others = others.into_iter().rev();
+    let line4 = others.next();
+    let line3 = others.next();
+    let line2 = others.next();
+
+    AddressLines {
+        id,
+        lines: [
+            recipient,
+            line2,
+            line3,
+            line4,
+            post_boxes.into_iter().next(),
+            line6,
+            Some(line7),
+        ],
+    }
+}
+
+pub(crate) fn is_post_box(line: &str) -> bool {
+    let first = line.split_whitespace().next().unwrap_or("");
+    ["BP", "CS", "TSA"].contains(&first.to_uppercase().as_str())
+}
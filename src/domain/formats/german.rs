@@ -0,0 +1,128 @@
+use crate::domain::formats::continental::ContinentalLayout;
+use crate::domain::formats::{AddressLines, NationalAddressFormat};
+use crate::domain::models::{AddressKind, ISO20022Address};
+use regex::Regex;
+use std::sync::LazyLock;
+
+static LAYOUT: ContinentalLayout = ContinentalLayout {
+    country_code: "DE",
+    postcode: LazyLock::new(|| Regex::new(r"^(?:D-)?(\d{5})\s+(.+)$").unwrap()),
+    box_markers: &[],
+    max_line_length: 38,
+};
+
+/// Deutsche Post layout: "Musterstraße 12a" then "10115 Berlin"; a "Postfach" goes on line 5.
+pub struct GermanFormat;
+
+impl NationalAddressFormat for GermanFormat {
+    fn country_code(&self) -> &'static str {
+        LAYOUT.country_code
+    }
+
+    fn to_iso(&self, address: AddressLines, kind: AddressKind) -> Result<ISO20022Address, String> {
+        LAYOUT.to_iso(address, kind)
+    }
+
+    fn to_lines(&self, iso: &ISO20022Address) -> AddressLines {
+        LAYOUT.to_lines(iso)
+    }
+
+    fn split_street_line(&self, line: &str) -> (Option<String>, Option<String>) {
+        LAYOUT.split_street_line(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_iso_company() {
+        let address = AddressLines {
+            id: "1".to_string(),
+            lines: [
+                Some("Muster GmbH".to_string()),
+                Some("Einkauf".to_string()),
+                None,
+                Some("Straße des 17. Juni 135".to_string()),
+                None,
+                Some("10623 Berlin".to_string()),
+                Some("Deutschland".to_string()),
+            ],
+        };
+
+        let iso = GermanFormat.to_iso(address, AddressKind::Company).unwrap();
+
+        assert_eq!(iso.department, Some("Einkauf".to_string()));
+        assert_eq!(iso.street_name, Some("Straße des 17. Juni".to_string()));
+        assert_eq!(iso.building_number, Some("135".to_string()));
+        assert_eq!(iso.post_code, Some("10623".to_string()));
+        assert_eq!(iso.town_name, Some("Berlin".to_string()));
+        assert_eq!(iso.country, Some("DE".to_string()));
+    }
+
+    #[test]
+    fn test_to_iso_street_without_number() {
+        let address = AddressLines {
+            id: "1".to_string(),
+            lines: [
+                Some("Muster GmbH".to_string()),
+                None,
+                None,
+                Some("Straße des 17. Juni".to_string()),
+                None,
+                Some("10623 Berlin".to_string()),
+                None,
+            ],
+        };
+
+        let iso = GermanFormat.to_iso(address, AddressKind::Company).unwrap();
+
+        assert_eq!(iso.street_name, Some("Straße des 17. Juni".to_string()));
+        assert_eq!(iso.building_number, None);
+    }
+
+    #[test]
+    fn test_to_iso_house_number_with_letter_or_range() {
+        for (line, number) in [
+            ("Musterstraße 12 a", "12 a"),
+            ("Musterstraße 12-14", "12-14"),
+        ] {
+            let address = AddressLines {
+                id: "1".to_string(),
+                lines: [
+                    Some("Muster GmbH".to_string()),
+                    None,
+                    None,
+                    Some(line.to_string()),
+                    None,
+                    Some("10115 Berlin".to_string()),
+                    None,
+                ],
+            };
+
+            let iso = GermanFormat.to_iso(address, AddressKind::Company).unwrap();
+
+            assert_eq!(iso.street_name, Some("Musterstraße".to_string()));
+            assert_eq!(iso.building_number, Some(number.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_to_iso_rejects_other_country_line() {
+        let address = AddressLines {
+            id: "1".to_string(),
+            lines: [
+                Some("Muster GmbH".to_string()),
+                None,
+                None,
+                Some("Musterstraße 12a".to_string()),
+                None,
+                Some("10115 Berlin".to_string()),
+                Some("Österreich".to_string()),
+            ],
+        };
+
+        assert!(GermanFormat.to_iso(address, AddressKind::Company).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::domain::models::{AddressKind, FrenchAddress, ISO20022Address};
+use serde::{Deserialize, Serialize};
+
+pub mod belgian;
+mod continental;
+pub mod en16931;
+pub mod french;
+pub mod german;
+pub mod swift_mt;
+pub mod swiss_qr;
+pub mod vcard;
+
+pub use belgian::BelgianFormat;
+pub use french::FrenchFormat;
+pub use german::GermanFormat;
+
+/// Envelope lines of a postal address. Every national layout uses the same seven
+/// slots as NF Z10-011 (recipient, department or room, building or floor, street,
+/// post box, postcode and town, country), so that partial updates can be merged
+/// slot by slot whatever the country.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AddressLines {
+    pub id: String,
+    pub lines: [Option<String>; 7],
+}
+
+impl From<FrenchAddress> for AddressLines {
+    fn from(address: FrenchAddress) -> Self {
+        Self {
+            id: address.id,
+            lines: [
+                address.line1,
+                address.line2,
+                address.line3,
+                address.line4,
+                address.line5,
+                address.line6,
+                address.line7,
+            ],
+        }
+    }
+}
+
+impl From<AddressLines> for FrenchAddress {
+    fn from(address: AddressLines) -> Self {
+        let [line1, line2, line3, line4, line5, line6, line7] = address.lines;
+        Self {
+            id: address.id,
+            line1,
+            line2,
+            line3,
+            line4,
+            line5,
+            line6,
+            line7,
+        }
+    }
+}
+
+/// A national postal layout that can be converted to and from `ISO20022Address`.
+pub trait NationalAddressFormat: Send + Sync {
+    /// ISO 3166-1 alpha-2 code of the country whose layout this is.
+    fn country_code(&self) -> &'static str;
+
+    /// Validates the lines and converts them to the structured ISO 20022 form.
+    fn to_iso(&self, address: AddressLines, kind: AddressKind) -> Result<ISO20022Address, String>;
+
+    /// Lays a structured address out in this country's postal format.
+    fn to_lines(&self, iso: &ISO20022Address) -> AddressLines;
+
+    /// Splits a street line into the street name and the building number, in
+    /// this country's order.
+    fn split_street_line(&self, line: &str) -> (Option<String>, Option<String>);
+}
+
+pub const SUPPORTED_COUNTRIES: [&str; 3] = ["FR", "BE", "DE"];
+
+pub fn format_for_country(country: &str) -> Option<&'static dyn NationalAddressFormat> {
+    match country.trim().to_uppercase().as_str() {
+        "FR" => Some(&FrenchFormat),
+        "BE" => Some(&BelgianFormat),
+        "DE" => Some(&GermanFormat),
+        _ => None,
+    }
+}
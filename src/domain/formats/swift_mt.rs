@@ -0,0 +1,407 @@
+use crate::domain::countries::find_country;
+use crate::domain::formats::en16931::is_post_box_line;
+use crate::domain::formats::french::envelope_from_free_text;
+use crate::domain::formats::{FrenchFormat, NationalAddressFormat, format_for_country};
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::domain::validation::{ValidationError, validate_iso20022_address};
+
+/// FIN limits of the party fields 50F/50K/59/59F: four lines of 35 characters
+/// (not counting the optional `/account` line).
+pub const MT_LINE_LENGTH: usize = 35;
+pub const MT_MAX_LINES: usize = 4;
+
+const MT_LINE_FIELDS: [&str; MT_MAX_LINES] = ["mt_line1", "mt_line2", "mt_line3", "mt_line4"];
+
+/// True for the SWIFT "X" character set:
+/// `a-z A-Z 0-9 / - ? : ( ) . , ' +` and space.
+pub fn is_x_charset(value: &str) -> bool {
+    value.chars().all(is_x_char)
+}
+
+fn is_x_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "/-?:().,'+ ".contains(c)
+}
+
+/// Uppercases and transliterates a value into the X character set
+/// ("Avenue de l'Opéra & Co" → "AVENUE DE L'OPERA + CO").
+pub fn to_x_charset(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars().flat_map(char::to_uppercase) {
+        match c {
+            'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' => out.push('A'),
+            'Ç' => out.push('C'),
+            'È' | 'É' | 'Ê' | 'Ë' => out.push('E'),
+            'Ì' | 'Í' | 'Î' | 'Ï' => out.push('I'),
+            'Ñ' => out.push('N'),
+            'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' => out.push('O'),
+            'Ù' | 'Ú' | 'Û' | 'Ü' => out.push('U'),
+            'Ý' | 'Ÿ' => out.push('Y'),
+            'Æ' => out.push_str("AE"),
+            'Œ' => out.push_str("OE"),
+            'ß' => out.push_str("SS"),
+            '&' => out.push('+'),
+            '’' | '‘' => out.push('\''),
+            c if is_x_char(c) => out.push(c),
+            _ => out.push(' '),
+        }
+    }
+    out
+}
+
+/// Checks the FIN rules shared by all party fields: at most four lines of 35
+/// X characters, none of them starting with ':' or '-'.
+pub fn validate_mt_lines(lines: &[String]) -> Result<(), ValidationError> {
+    if lines.len() > MT_MAX_LINES {
+        return Err(ValidationError::TooLong {
+            field: "mt_lines",
+            max_len: MT_MAX_LINES,
+            actual_len: lines.len(),
+        });
+    }
+    for (line, field) in lines.iter().zip(MT_LINE_FIELDS) {
+        if line.trim().is_empty() {
+            return Err(ValidationError::EmptyField { field });
+        }
+        if line.chars().count() > MT_LINE_LENGTH {
+            return Err(ValidationError::TooLong {
+                field,
+                max_len: MT_LINE_LENGTH,
+                actual_len: line.chars().count(),
+            });
+        }
+        if !is_x_charset(line) || line.starts_with(':') || line.starts_with('-') {
+            return Err(ValidationError::InvalidCharacters {
+                field,
+                value: line.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Lays an address out as the numbered lines of MT 50F / 59F:
+/// `1/` name, `2/` address line, `3/CC/town`. Long values continue on a new
+/// line with the same number; the post box is dropped if the four lines are
+/// not enough.
+pub fn to_mt_structured(iso: &ISO20022Address) -> Result<Vec<String>, String> {
+    let name = iso
+        .recipient_name
+        .as_deref()
+        .ok_or("Validation error: EmptyField { field: \"recipient_name\" }")?;
+    let country = iso
+        .country
+        .as_deref()
+        .ok_or("Validation error: EmptyField { field: \"country\" }")?
+        .to_uppercase();
+
+    let street_line = match format_for_country(&country) {
+        Some(format) => format.to_lines(iso).lines[3].clone(),
+        None => match (&iso.building_number, &iso.street_name) {
+            (Some(number), Some(street)) => Some(format!("{} {}", number, street)),
+            (number, street) => street.clone().or(number.clone()),
+        },
+    };
+    let town_line = match find_country(&country) {
+        Some(c) => c.format_town_line(
+            iso.post_code.as_deref(),
+            iso.town_name.as_deref(),
+            iso.country_sub_division.as_deref(),
+        ),
+        None => match (&iso.post_code, &iso.town_name) {
+            (Some(code), Some(town)) => Some(format!("{} {}", code, town)),
+            (code, town) => code.clone().or(town.clone()),
+        },
+    };
+    let town = match town_line {
+        Some(town) => format!("{}/{}", country, town),
+        None => country,
+    };
+
+    let name_lines = numbered_lines('1', name);
+    let street_lines = street_line
+        .map(|street| numbered_lines('2', &street))
+        .unwrap_or_default();
+    let post_box_lines = iso
+        .post_box
+        .as_deref()
+        .map(|post_box| numbered_lines('2', post_box))
+        .unwrap_or_default();
+    let town_lines = numbered_lines('3', &town);
+
+    let mut lines: Vec<String> = [&name_lines, &street_lines, &post_box_lines, &town_lines]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    if lines.len() > MT_MAX_LINES {
+        lines = [&name_lines, &street_lines, &town_lines]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+    }
+
+    validate_mt_lines(&lines).map_err(|e| format!("Validation error: {:?}", e))?;
+    Ok(lines)
+}
+
+/// Word-wraps a value on lines of 35 characters prefixed with `n/`.
+fn numbered_lines(number: char, value: &str) -> Vec<String> {
+    let width = MT_LINE_LENGTH - 2;
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in to_x_charset(value).split_whitespace() {
+        let chars: Vec<char> = word.chars().collect();
+        for piece in chars.chunks(width) {
+            let piece: String = piece.iter().collect();
+            if !current.is_empty() && current.chars().count() + 1 + piece.chars().count() > width {
+                chunks.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(&piece);
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+        .into_iter()
+        .map(|chunk| format!("{}/{}", number, chunk))
+        .collect()
+}
+
+/// Reads the numbered lines of MT 50F / 59F. A leading `/account` line is
+/// ignored. The street line is split in the order of the country's postal
+/// format, the French one for countries without a format.
+pub fn from_mt_structured(
+    id: String,
+    lines: &[String],
+    kind: AddressKind,
+) -> Result<ISO20022Address, String> {
+    let lines = without_account(lines);
+    validate_mt_lines(lines).map_err(|e| format!("Validation error: {:?}", e))?;
+
+    let mut name: Vec<&str> = Vec::new();
+    let mut address: Vec<&str> = Vec::new();
+    let mut town: Vec<&str> = Vec::new();
+    for line in lines {
+        match line.split_once('/') {
+            Some(("1", value)) => name.push(value.trim()),
+            Some(("2", value)) => address.push(value.trim()),
+            Some(("3", value)) => town.push(value.trim()),
+            _ => return Err(format!("Unsupported 50F/59F line: {}", line)),
+        }
+    }
+
+    let town = town.join(" ");
+    let (country, town_line) = town.split_once('/').unwrap_or((town.as_str(), ""));
+    let country = country.trim().to_uppercase();
+    if country.len() != 2 {
+        return Err(format!(
+            "Validation error: {:?}",
+            ValidationError::InvalidCountryCode { value: country }
+        ));
+    }
+
+    let mut iso = ISO20022Address {
+        id,
+        kind,
+        recipient_name: (!name.is_empty()).then(|| name.join(" ")),
+        country: Some(country.clone()),
+        ..Default::default()
+    };
+
+    // A long street continues on the next `2/` lines; the post box starts at
+    // the first line that reads as one.
+    let post_box_start = address
+        .iter()
+        .position(|line| is_post_box_line(line))
+        .unwrap_or(address.len());
+    let (street, post_box) = address.split_at(post_box_start);
+    if !street.is_empty() {
+        let format = format_for_country(&country).unwrap_or(&FrenchFormat);
+        let (street_name, building_number) = format.split_street_line(&street.join(" "));
+        iso.street_name = street_name;
+        iso.building_number = building_number;
+    }
+    if !post_box.is_empty() {
+        iso.post_box = Some(post_box.join(" "));
+    }
+
+    let town_line = town_line.trim();
+    if !town_line.is_empty() {
+        match find_country(&country).and_then(|c| c.parse_town_line(town_line)) {
+            Some(parsed) => {
+                iso.post_code = parsed.post_code;
+                iso.town_name = Some(parsed.town);
+                iso.country_sub_division = parsed.country_sub_division;
+            }
+            None => iso.town_name = Some(town_line.to_string()),
+        }
+    }
+
+    validate_iso20022_address(&iso).map_err(|e| format!("Validation error: {:?}", e))?;
+    Ok(iso)
+}
+
+/// Reads the free-format lines of MT 50K / 59 (name, address, town, optional
+/// country) as a French envelope and converts it with the French rules.
+/// Without a country line the address is taken to be in France.
+pub fn from_mt_free_format(
+    id: String,
+    lines: &[String],
+    kind: AddressKind,
+) -> Result<ISO20022Address, String> {
+    let lines = without_account(lines);
+    validate_mt_lines(lines).map_err(|e| format!("Validation error: {:?}", e))?;
+
+    let mut lines = lines.iter().map(|l| l.trim().to_string());
+    let recipient = lines.next();
+    let rest: Vec<String> = lines.collect();
+
+    FrenchFormat.to_iso(envelope_from_free_text(id, recipient, &rest, None), kind)
+}
+
+fn without_account(lines: &[String]) -> &[String] {
+    match lines.first() {
+        Some(first) if first.starts_with('/') => &lines[1..],
+        _ => lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_to_mt_structured() {
+        let iso = ISO20022Address {
+            id: "1".to_string(),
+            recipient_name: Some("Société Générale des Eaux & Forêts de Bordeaux".to_string()),
+            building_number: Some("22BIS".to_string()),
+            street_name: Some("Rue des Fleurs".to_string()),
+            post_box: Some("BP 40122".to_string()),
+            post_code: Some("33500".to_string()),
+            town_name: Some("LIBOURNE".to_string()),
+            country: Some("FR".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            to_mt_structured(&iso).unwrap(),
+            lines(&[
+                "1/SOCIETE GENERALE DES EAUX +",
+                "1/FORETS DE BORDEAUX",
+                "2/22BIS RUE DES FLEURS",
+                "3/FR/33500 LIBOURNE",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_from_mt_structured() {
+        let iso = from_mt_structured(
+            "2".to_string(),
+            &lines(&[
+                "/DE89370400440532013000",
+                "1/HANS MUELLER",
+                "2/UNTER DEN LINDEN 77",
+                "3/DE/10117 BERLIN",
+            ]),
+            AddressKind::Particular,
+        )
+        .unwrap();
+
+        assert_eq!(iso.recipient_name, Some("HANS MUELLER".to_string()));
+        assert_eq!(iso.street_name, Some("UNTER DEN LINDEN".to_string()));
+        assert_eq!(iso.building_number, Some("77".to_string()));
+        assert_eq!(iso.post_code, Some("10117".to_string()));
+        assert_eq!(iso.town_name, Some("BERLIN".to_string()));
+        assert_eq!(iso.country, Some("DE".to_string()));
+    }
+
+    #[test]
+    fn test_mt_structured_long_street_round_trip() {
+        let iso = ISO20022Address {
+            id: "3".to_string(),
+            recipient_name: Some("DURAND SA".to_string()),
+            building_number: Some("12".to_string()),
+            street_name: Some("AVENUE DU MARECHAL DE LATTRE DE TASSIGNY".to_string()),
+            post_code: Some("33500".to_string()),
+            town_name: Some("LIBOURNE".to_string()),
+            country: Some("FR".to_string()),
+            ..Default::default()
+        };
+
+        let mt = to_mt_structured(&iso).unwrap();
+        assert_eq!(mt.iter().filter(|l| l.starts_with("2/")).count(), 2);
+        let back = from_mt_structured("3".to_string(), &mt, AddressKind::Company).unwrap();
+        assert_eq!(back.street_name, iso.street_name);
+        assert_eq!(back.building_number, iso.building_number);
+        assert_eq!(back.post_box, None);
+
+        // Seule une vraie boîte postale va dans post_box
+        let with_post_box = from_mt_structured(
+            "4".to_string(),
+            &lines(&[
+                "1/DURAND SA",
+                "2/22BIS RUE DES FLEURS",
+                "2/BP 40122",
+                "3/FR/33500 LIBOURNE",
+            ]),
+            AddressKind::Company,
+        )
+        .unwrap();
+        assert_eq!(
+            with_post_box.street_name,
+            Some("RUE DES FLEURS".to_string())
+        );
+        assert_eq!(with_post_box.post_box, Some("BP 40122".to_string()));
+    }
+
+    #[test]
+    fn test_from_mt_free_format() {
+        let iso = from_mt_free_format(
+            "3".to_string(),
+            &lines(&[
+                "DURAND SA",
+                "22 RUE DES FLEURS",
+                "BP 40122",
+                "33506 LIBOURNE CEDEX",
+            ]),
+            AddressKind::Company,
+        )
+        .unwrap();
+
+        assert_eq!(iso.recipient_name, Some("DURAND SA".to_string()));
+        assert_eq!(iso.building_number, Some("22".to_string()));
+        assert_eq!(iso.street_name, Some("RUE DES FLEURS".to_string()));
+        assert_eq!(iso.post_box, Some("BP 40122".to_string()));
+        assert_eq!(iso.post_code, Some("33506".to_string()));
+        assert_eq!(iso.country, Some("FR".to_string()));
+    }
+
+    #[test]
+    fn test_mt_lines_rejected() {
+        let accented = lines(&["1/JOSÉ", "3/FR/75001 PARIS"]);
+        assert!(matches!(
+            validate_mt_lines(&accented),
+            Err(ValidationError::InvalidCharacters {
+                field: "mt_line1",
+                ..
+            })
+        ));
+
+        let too_long = lines(&["1/A NAME THAT IS MUCH TOO LONG FOR FIN"]);
+        assert!(matches!(
+            validate_mt_lines(&too_long),
+            Err(ValidationError::TooLong { max_len: 35, .. })
+        ));
+    }
+}
@@ -0,0 +1,294 @@
+use crate::domain::countries::find_country;
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::domain::usecases::process_street;
+use crate::domain::validation::{ValidationError, validate_iso20022_address};
+use serde::{Deserialize, Serialize};
+
+/// Field limits of a structured ("S") address of the Swiss QR-bill
+/// (Swiss Implementation Guidelines, section 4.3.2).
+pub const QR_NAME_LENGTH: usize = 70;
+pub const QR_STREET_LENGTH: usize = 70;
+pub const QR_BUILDING_NUMBER_LENGTH: usize = 16;
+pub const QR_POST_CODE_LENGTH: usize = 16;
+pub const QR_TOWN_LENGTH: usize = 35;
+
+/// Number of lines of an address block in the QR code payload.
+pub const QR_ADDRESS_LINES: usize = 7;
+
+/// Creditor or debtor address of a QR-bill, address type "S".
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QrBillAddress {
+    pub name: String,
+    pub street_name: Option<String>,
+    pub building_number: Option<String>,
+    pub post_code: String,
+    pub town_name: String,
+    pub country: String, // ISO 3166-1 alpha-2
+}
+
+/// True for the characters allowed in QR-bill data: printable Basic Latin,
+/// Latin-1 Supplement and Latin Extended-A, plus Ș ș Ț ț and €.
+pub fn is_qr_charset(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| matches!(c, ' '..='~' | '\u{A0}'..='\u{17F}' | 'Ș' | 'ș' | 'Ț' | 'ț' | '€'))
+}
+
+/// Checks the QR-bill rules: name, postcode, town and country are mandatory,
+/// fields fit their limits and character set, and the postcode follows the
+/// country's pattern when the country is known, without a "CH-" prefix.
+pub fn validate_qr_address(address: &QrBillAddress) -> Result<(), ValidationError> {
+    let fields: [(&'static str, Option<&str>, usize, bool); 5] = [
+        ("name", Some(&address.name), QR_NAME_LENGTH, true),
+        (
+            "street_name",
+            address.street_name.as_deref(),
+            QR_STREET_LENGTH,
+            false,
+        ),
+        (
+            "building_number",
+            address.building_number.as_deref(),
+            QR_BUILDING_NUMBER_LENGTH,
+            false,
+        ),
+        (
+            "post_code",
+            Some(&address.post_code),
+            QR_POST_CODE_LENGTH,
+            true,
+        ),
+        ("town_name", Some(&address.town_name), QR_TOWN_LENGTH, true),
+    ];
+    for (field, value, max_len, required) in fields {
+        let value = value.unwrap_or("");
+        if value.trim().is_empty() {
+            if required {
+                return Err(ValidationError::EmptyField { field });
+            }
+            continue;
+        }
+        let actual_len = value.chars().count();
+        if actual_len > max_len {
+            return Err(ValidationError::TooLong {
+                field,
+                max_len,
+                actual_len,
+            });
+        }
+        if !is_qr_charset(value) {
+            return Err(ValidationError::InvalidCharacters {
+                field,
+                value: value.to_string(),
+            });
+        }
+    }
+
+    let country = &address.country;
+    if country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(ValidationError::InvalidCountryCode {
+            value: country.clone(),
+        });
+    }
+    let post_code = address.post_code.trim();
+    let prefixed = post_code
+        .get(..3)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{}-", country)));
+    let known_pattern_ok = find_country(country).is_none_or(|c| c.is_valid_post_code(post_code));
+    if prefixed || !known_pattern_ok {
+        return Err(ValidationError::InvalidPostalCode {
+            value: post_code.to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl QrBillAddress {
+    /// Takes the QR-bill fields of a structured address. A building number
+    /// written at the start of the street ("22 Rue du Lac") is split off, and
+    /// the post box stands in for a missing street.
+    pub fn from_iso(iso: &ISO20022Address) -> Result<Self, String> {
+        let (building_number, street_name) = match &iso.building_number {
+            Some(number) => (Some(number.clone()), iso.street_name.clone()),
+            None => process_street(&iso.street_name),
+        };
+        let address = Self {
+            name: iso.recipient_name.clone().unwrap_or_default(),
+            street_name: street_name.or(iso.post_box.clone()),
+            building_number,
+            post_code: iso.post_code.clone().unwrap_or_default(),
+            town_name: iso.town_name.clone().unwrap_or_default(),
+            country: iso.country.clone().unwrap_or_default().to_uppercase(),
+        };
+        validate_qr_address(&address).map_err(|e| format!("Validation error: {:?}", e))?;
+        Ok(address)
+    }
+
+    pub fn to_iso(&self, id: String, kind: AddressKind) -> Result<ISO20022Address, String> {
+        validate_qr_address(self).map_err(|e| format!("Validation error: {:?}", e))?;
+        let (building_number, street_name) = match &self.building_number {
+            Some(number) => (Some(number.clone()), self.street_name.clone()),
+            None => process_street(&self.street_name),
+        };
+        let iso = ISO20022Address {
+            id,
+            recipient_name: Some(self.name.clone()),
+            kind,
+            street_name,
+            building_number,
+            post_code: Some(self.post_code.clone()),
+            town_name: Some(self.town_name.clone()),
+            country: Some(self.country.clone()),
+            ..Default::default()
+        };
+        validate_iso20022_address(&iso).map_err(|e| format!("Validation error: {:?}", e))?;
+        Ok(iso)
+    }
+
+    /// Lines of the address block in the QR code payload: `S`, name, street,
+    /// building number, postcode, town and country (empty lines kept).
+    pub fn to_lines(&self) -> Vec<String> {
+        vec![
+            "S".to_string(),
+            self.name.clone(),
+            self.street_name.clone().unwrap_or_default(),
+            self.building_number.clone().unwrap_or_default(),
+            self.post_code.clone(),
+            self.town_name.clone(),
+            self.country.clone(),
+        ]
+    }
+
+    /// Reads the seven lines of an address block of the QR code payload.
+    pub fn from_lines(lines: &[String]) -> Result<Self, String> {
+        let [address_type, name, street, number, post_code, town, country] = lines else {
+            return Err(format!(
+                "Expected {} QR-bill address lines, got {}",
+                QR_ADDRESS_LINES,
+                lines.len()
+            ));
+        };
+        if address_type.trim() != "S" {
+            return Err(format!(
+                "Unsupported QR-bill address type {}, expected S",
+                address_type.trim()
+            ));
+        }
+        let optional = |line: &String| Some(line.trim().to_string()).filter(|l| !l.is_empty());
+        let address = Self {
+            name: name.trim().to_string(),
+            street_name: optional(street),
+            building_number: optional(number),
+            post_code: post_code.trim().to_string(),
+            town_name: town.trim().to_string(),
+            country: country.trim().to_uppercase(),
+        };
+        validate_qr_address(&address).map_err(|e| format!("Validation error: {:?}", e))?;
+        Ok(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_from_iso() {
+        let iso = ISO20022Address {
+            id: "1".to_string(),
+            recipient_name: Some("Robert Schneider AG".to_string()),
+            street_name: Some("Rue du Lac".to_string()),
+            building_number: Some("1268".to_string()),
+            post_code: Some("2501".to_string()),
+            town_name: Some("Biel".to_string()),
+            country: Some("CH".to_string()),
+            ..Default::default()
+        };
+
+        let address = QrBillAddress::from_iso(&iso).unwrap();
+
+        assert_eq!(
+            address.to_lines(),
+            lines(&[
+                "S",
+                "Robert Schneider AG",
+                "Rue du Lac",
+                "1268",
+                "2501",
+                "Biel",
+                "CH"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_from_lines_splits_leading_number() {
+        let address = QrBillAddress::from_lines(&lines(&[
+            "S",
+            "DURAND SA",
+            "22BIS RUE DES FLEURS",
+            "",
+            "33500",
+            "LIBOURNE",
+            "fr",
+        ]))
+        .unwrap();
+        let iso = address
+            .to_iso("2".to_string(), AddressKind::Company)
+            .unwrap();
+
+        assert_eq!(iso.building_number, Some("22BIS".to_string()));
+        assert_eq!(iso.street_name, Some("RUE DES FLEURS".to_string()));
+        assert_eq!(iso.country, Some("FR".to_string()));
+    }
+
+    #[test]
+    fn test_qr_address_rejected() {
+        let valid = QrBillAddress {
+            name: "Pia-Maria Rutschmann-Schnyder".to_string(),
+            street_name: Some("Grosse Marktgasse".to_string()),
+            building_number: Some("28".to_string()),
+            post_code: "9400".to_string(),
+            town_name: "Rorschach".to_string(),
+            country: "CH".to_string(),
+        };
+        assert!(validate_qr_address(&valid).is_ok());
+
+        let prefixed = QrBillAddress {
+            post_code: "CH-9400".to_string(),
+            ..valid.clone()
+        };
+        assert!(matches!(
+            validate_qr_address(&prefixed),
+            Err(ValidationError::InvalidPostalCode { .. })
+        ));
+
+        let long_number = QrBillAddress {
+            building_number: Some("28 Hinterhaus links".to_string()),
+            ..valid.clone()
+        };
+        assert!(matches!(
+            validate_qr_address(&long_number),
+            Err(ValidationError::TooLong {
+                field: "building_number",
+                max_len: 16,
+                ..
+            })
+        ));
+
+        let no_town = QrBillAddress {
+            town_name: " ".to_string(),
+            ..valid
+        };
+        assert!(matches!(
+            validate_qr_address(&no_town),
+            Err(ValidationError::EmptyField { field: "town_name" })
+        ));
+
+        assert!(QrBillAddress::from_lines(&lines(&["K", "A", "B", "C"])).is_err());
+    }
+}
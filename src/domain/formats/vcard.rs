@@ -0,0 +1,422 @@
+use crate::domain::countries::find_country;
+use crate::domain::formats::{
+    AddressLines, FrenchFormat, NationalAddressFormat, format_for_country,
+};
+use crate::domain::models::{AddressKind, ISO20022Address};
+use std::str::FromStr;
+
+/// Content lines longer than this many octets are folded (RFC 6350 §3.2).
+pub const VCARD_LINE_OCTETS: usize = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VCardVersion {
+    V3,
+    V4,
+}
+
+impl FromStr for VCardVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "3" | "3.0" => Ok(Self::V3),
+            "4" | "4.0" => Ok(Self::V4),
+            other => Err(format!(
+                "Unsupported vCard version {}, expected 3.0 or 4.0",
+                other
+            )),
+        }
+    }
+}
+
+/// Writes an address as a vCard: `ORG` (with the department as unit) for a
+/// company, `N` for a private person, and one `ADR` whose extended address
+/// lists the room and the floor. Lines end with CRLF and are folded.
+pub fn to_vcard(iso: &ISO20022Address, version: VCardVersion) -> String {
+    let name = iso.recipient_name.as_deref().unwrap_or_default();
+    let mut lines = vec![
+        "BEGIN:VCARD".to_string(),
+        match version {
+            VCardVersion::V3 => "VERSION:3.0".to_string(),
+            VCardVersion::V4 => "VERSION:4.0".to_string(),
+        },
+    ];
+    if !iso.id.is_empty() {
+        lines.push(format!("UID:{}", escape(&iso.id)));
+    }
+    lines.push(format!("FN:{}", escape(name)));
+
+    let address_type = match iso.kind {
+        AddressKind::Company => {
+            if version == VCardVersion::V4 {
+                lines.push("KIND:org".to_string());
+            } else {
+                lines.push("N:;;;;".to_string());
+            }
+            let units: Vec<String> = [Some(name), iso.department.as_deref()]
+                .into_iter()
+                .flatten()
+                .map(escape)
+                .collect();
+            lines.push(format!("ORG:{}", units.join(";")));
+            "work"
+        }
+        AddressKind::Particular => {
+            lines.push(format!("N:{};;;;", escape(name)));
+            "home"
+        }
+    };
+
+    let format = iso
+        .country
+        .as_deref()
+        .and_then(format_for_country)
+        .unwrap_or(&FrenchFormat);
+    let street = format.to_lines(iso).lines[3].clone();
+    let extended: Vec<String> = [iso.room.as_deref(), iso.floor.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(escape)
+        .collect();
+    let country_name = iso.country.as_deref().map(|code| match find_country(code) {
+        Some(country) => country.french_name(),
+        None => code,
+    });
+    let components = [
+        iso.post_box.as_deref().map(escape).unwrap_or_default(),
+        extended.join(","),
+        street.as_deref().map(escape).unwrap_or_default(),
+        iso.town_name.as_deref().map(escape).unwrap_or_default(),
+        iso.country_sub_division
+            .as_deref()
+            .map(escape)
+            .unwrap_or_default(),
+        iso.post_code.as_deref().map(escape).unwrap_or_default(),
+        country_name.map(escape).unwrap_or_default(),
+    ];
+    lines.push(format!(
+        "ADR;TYPE={}:{}",
+        address_type,
+        components.join(";")
+    ));
+    lines.push("END:VCARD".to_string());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// A contact read from a vCard, reduced to what makes a postal address.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VCardContact {
+    pub uid: Option<String>,
+    pub formatted_name: Option<String>,
+    pub organization: Vec<String>, // Name then units
+    /// Components of the first `ADR`: post box, extended address (list),
+    /// street, locality, region, postal code, country.
+    pub address: Option<[Vec<String>; 7]>,
+}
+
+impl VCardContact {
+    /// Converts the contact through the French envelope: organisation or
+    /// formatted name on line 1, department or room on line 2, floor on line 3,
+    /// street, post box, postcode and town, country. The contact is a company
+    /// when it has an `ORG`.
+    pub fn to_iso(&self, id: String) -> Result<ISO20022Address, String> {
+        let Some(address) = &self.address else {
+            return Err("No ADR property".to_string());
+        };
+        let joined = |values: &[String]| {
+            let value = values.join(", ");
+            (!value.trim().is_empty()).then(|| value.trim().to_string())
+        };
+        let [
+            post_box,
+            extended,
+            street,
+            locality,
+            region,
+            postal_code,
+            country,
+        ] = address;
+
+        let kind = if self.organization.is_empty() {
+            AddressKind::Particular
+        } else {
+            AddressKind::Company
+        };
+        let recipient = match kind {
+            AddressKind::Company => self.organization.first().cloned(),
+            AddressKind::Particular => self.formatted_name.clone(),
+        };
+        let extended: Vec<String> = extended
+            .iter()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect();
+        let (line2, line3) = match (kind, extended.as_slice()) {
+            (AddressKind::Company, _) => (self.organization.get(1).cloned(), joined(&extended)),
+            (AddressKind::Particular, [room, floor, ..]) => {
+                (Some(room.clone()), Some(floor.clone()))
+            }
+            (AddressKind::Particular, _) => (None, joined(&extended)),
+        };
+
+        let country = joined(country);
+        let destination = country.as_deref().and_then(find_country);
+        let town_line = match destination {
+            Some(found) if found.code != "FR" => found.format_town_line(
+                joined(postal_code).as_deref(),
+                joined(locality).as_deref(),
+                joined(region).as_deref(),
+            ),
+            _ => {
+                let parts: Vec<String> = [joined(postal_code), joined(locality)]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                (!parts.is_empty()).then(|| parts.join(" "))
+            }
+        };
+
+        let envelope = AddressLines {
+            id,
+            lines: [
+                recipient,
+                line2,
+                line3,
+                joined(street),
+                joined(post_box),
+                town_line,
+                Some(country.unwrap_or_else(|| "FRANCE".to_string())),
+            ],
+        };
+        FrenchFormat.to_iso(envelope, kind)
+    }
+}
+
+/// Reads every `BEGIN:VCARD` … `END:VCARD` block of a vCard 3.0 / 4.0 file,
+/// after unfolding the lines.
+pub fn parse_vcards(text: &str) -> Result<Vec<VCardContact>, String> {
+    let mut contacts = Vec::new();
+    let mut current: Option<VCardContact> = None;
+
+    for line in unfold(text) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (name, value) =
+            split_content_line(&line).ok_or_else(|| format!("Malformed vCard line: {}", line))?;
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VCARD") => {
+                current = Some(VCardContact::default())
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VCARD") => {
+                contacts.extend(current.take())
+            }
+            ("UID", Some(contact)) => contact.uid = Some(unescape(value)),
+            ("FN", Some(contact)) => contact.formatted_name = Some(unescape(value)),
+            ("ORG", Some(contact)) => {
+                contact.organization = split_unescaped(value, ';')
+                    .iter()
+                    .map(|unit| unescape(unit))
+                    .filter(|unit| !unit.is_empty())
+                    .collect()
+            }
+            ("ADR", Some(contact)) if contact.address.is_none() => {
+                let mut components = split_unescaped(value, ';').into_iter().map(|c| {
+                    split_unescaped(c, ',')
+                        .iter()
+                        .map(|v| unescape(v))
+                        .collect::<Vec<_>>()
+                });
+                contact.address = Some(std::array::from_fn(|_| {
+                    components.next().unwrap_or_default()
+                }));
+            }
+            (_, Some(_)) => {}
+            _ => return Err(format!("Unexpected vCard line: {}", line)),
+        }
+    }
+
+    if current.is_some() {
+        return Err("Missing END:VCARD".to_string());
+    }
+    Ok(contacts)
+}
+
+/// Joins the continuation lines (starting with a space or a tab) to the
+/// line before them.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Folds a content line at 75 octets without splitting a UTF-8 character.
+fn fold(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + 8);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > VCARD_LINE_OCTETS {
+            out.push_str("\r\n ");
+            octets = 1;
+        }
+        out.push(c);
+        octets += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+/// Upper-cased property name (group and parameters removed) and raw value.
+fn split_content_line(line: &str) -> Option<(String, &str)> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let name = head.split(';').next()?;
+    let name = name.rsplit('.').next()?;
+    Some((name.trim().to_uppercase(), value))
+}
+
+/// Splits on `separator` when it is not escaped with a backslash.
+fn split_unescaped(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == separator => {
+                parts.push(&value[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => out.push('\n'),
+                Some(escaped) => out.push(escaped),
+                None => out.push('\\'),
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn company() -> ISO20022Address {
+        ISO20022Address {
+            id: "42".to_string(),
+            recipient_name: Some("Durand; Frères & Cie".to_string()),
+            kind: AddressKind::Company,
+            department: Some("SERVICE ACHATS".to_string()),
+            floor: Some("BATIMENT B".to_string()),
+            building_number: Some("22BIS".to_string()),
+            street_name: Some("RUE DES FLEURS".to_string()),
+            post_box: Some("BP 40122".to_string()),
+            post_code: Some("33500".to_string()),
+            town_name: Some("LIBOURNE".to_string()),
+            country: Some("FR".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_to_vcard_folds_and_escapes() {
+        let vcard = to_vcard(&company(), VCardVersion::V4);
+
+        assert!(vcard.starts_with("BEGIN:VCARD\r\nVERSION:4.0\r\nUID:42\r\n"));
+        assert!(vcard.contains("KIND:org\r\n"));
+        assert!(vcard.contains("ORG:Durand\\; Frères & Cie;SERVICE ACHATS"));
+        assert!(vcard.ends_with("END:VCARD\r\n"));
+        for line in vcard.split("\r\n") {
+            assert!(line.len() <= VCARD_LINE_OCTETS, "{line}");
+        }
+        let adr: String = unfold(&vcard)
+            .into_iter()
+            .find(|l| l.starts_with("ADR"))
+            .unwrap();
+        let accents = "é".repeat(60);
+        let folded = fold(&accents);
+        assert!(folded.split("\r\n").all(|l| l.len() <= VCARD_LINE_OCTETS));
+        assert_eq!(unfold(&folded), vec![accents]);
+
+        assert_eq!(
+            adr,
+            "ADR;TYPE=work:BP 40122;BATIMENT B;22BIS RUE DES FLEURS;LIBOURNE;;33500;FRANCE"
+        );
+    }
+
+    #[test]
+    fn test_vcard_round_trip() {
+        let vcard = to_vcard(&company(), VCardVersion::V3);
+        let contacts = parse_vcards(&vcard).unwrap();
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].uid, Some("42".to_string()));
+
+        let iso = contacts[0].to_iso("42".to_string()).unwrap();
+        let expected = company();
+        assert_eq!(iso.kind, AddressKind::Company);
+        assert_eq!(iso.recipient_name, expected.recipient_name);
+        assert_eq!(iso.department, expected.department);
+        assert_eq!(iso.floor, expected.floor);
+        assert_eq!(iso.building_number, expected.building_number);
+        assert_eq!(iso.street_name, expected.street_name);
+        assert_eq!(iso.post_box, expected.post_box);
+        assert_eq!(iso.post_code, expected.post_code);
+        assert_eq!(iso.town_name, expected.town_name);
+        assert_eq!(iso.country, expected.country);
+    }
+
+    #[test]
+    fn test_parse_vcards_particular() {
+        let text = "BEGIN:VCARD\r\nVERSION:3.0\r\nN:Dupont;Jean;;;\r\nFN:Jean Dupont\r\n\
+                    item1.ADR;TYPE=\"home,pref\":;Apt. 32,Entrée 4;15 Rue des Lil\r\n as;\
+                    Lyon;;69001;\r\nEND:VCARD\r\n";
+        let contacts = parse_vcards(text).unwrap();
+        let iso = contacts[0].to_iso("7".to_string()).unwrap();
+
+        assert_eq!(iso.kind, AddressKind::Particular);
+        assert_eq!(iso.recipient_name, Some("Jean Dupont".to_string()));
+        assert_eq!(iso.room, Some("Apt. 32".to_string()));
+        assert_eq!(iso.floor, Some("Entrée 4".to_string()));
+        assert_eq!(iso.street_name, Some("Rue des Lilas".to_string()));
+        assert_eq!(iso.post_code, Some("69001".to_string()));
+        assert_eq!(iso.country, Some("FR".to_string()));
+
+        assert!(parse_vcards("BEGIN:VCARD\r\nFN:X\r\n").is_err());
+    }
+}
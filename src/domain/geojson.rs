@@ -0,0 +1,72 @@
+use crate::domain::models::ISO20022Address;
+use serde_json::{Value, json};
+
+/// Builds a GeoJSON `FeatureCollection` with one `Point` per geocoded address.
+/// Addresses without coordinates are left out.
+pub fn to_geojson(addresses: &[ISO20022Address]) -> Value {
+    let features: Vec<Value> = addresses
+        .iter()
+        .filter_map(|address| {
+            let location = address.geolocation.as_ref()?;
+            Some(json!({
+                "type": "Feature",
+                "id": address.id,
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": [location.longitude, location.latitude],
+                },
+                "properties": {
+                    "recipient_name": address.recipient_name,
+                    "kind": address.kind,
+                    "building_number": address.building_number,
+                    "street_name": address.street_name,
+                    "post_code": address.post_code,
+                    "town_name": address.town_name,
+                    "country": address.country,
+                    "ban_id": location.ban_id,
+                },
+            }))
+        })
+        .collect();
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::GeoLocation;
+
+    #[test]
+    fn test_to_geojson_skips_addresses_without_coordinates() {
+        let geocoded = ISO20022Address {
+            id: "1".to_string(),
+            town_name: Some("LIBOURNE".to_string()),
+            geolocation: Some(GeoLocation {
+                latitude: 44.915,
+                longitude: -0.244,
+                ban_id: "33243_1234_00024".to_string(),
+            }),
+            ..Default::default()
+        };
+        let not_geocoded = ISO20022Address {
+            id: "2".to_string(),
+            ..Default::default()
+        };
+
+        let collection = to_geojson(&[geocoded, not_geocoded]);
+        let features = collection["features"].as_array().unwrap();
+
+        assert_eq!(collection["type"], "FeatureCollection");
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0]["id"], "1");
+        assert_eq!(
+            features[0]["geometry"]["coordinates"],
+            json!([-0.244, 44.915])
+        );
+        assert_eq!(features[0]["properties"]["ban_id"], "33243_1234_00024");
+    }
+}
@@ -1,4 +1,11 @@
+pub mod ban;
+pub mod conversion;
+pub mod countries;
+pub mod formats;
+pub mod geojson;
 pub mod models;
+pub mod postal_reference;
 pub mod usecases;
 pub mod repository;
+pub mod search;
 pub mod validation;
\ No newline at end of file
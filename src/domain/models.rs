@@ -1,3 +1,4 @@
+use crate::domain::postal_reference::normalize_town;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
@@ -120,6 +121,73 @@ pub struct ISO20022Address {
     pub country: Option<String>,   // Nation with its own government.
     pub district_name: Option<String>, //Identifies a subdivision within a country sub-division.
     pub country_sub_division: Option<String>, // Identifies a subdivision of a country such as state, region, county.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geolocation: Option<GeoLocation>, // Coordinates found in the Base Adresse Nationale, if geocoded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_references: Vec<String>, // Statement entries the address was imported from (camt.053/054).
+}
+
+impl ISO20022Address {
+    /// Recipient and postal fields with their serialized names.
+    pub fn named_fields(&self) -> [(&'static str, &Option<String>); 15] {
+        [
+            ("recipient_name", &self.recipient_name),
+            ("department", &self.department),
+            ("sub_department", &self.sub_department),
+            ("building_name", &self.building_name),
+            ("floor", &self.floor),
+            ("room", &self.room),
+            ("street_name", &self.street_name),
+            ("building_number", &self.building_number),
+            ("post_box", &self.post_box),
+            ("town_location_name", &self.town_location_name),
+            ("post_code", &self.post_code),
+            ("town_name", &self.town_name),
+            ("country", &self.country),
+            ("district_name", &self.district_name),
+            ("country_sub_division", &self.country_sub_division),
+        ]
+    }
+
+    /// Normalized recipient and postal fields, equal for two records of the
+    /// same party at the same address whatever their case, accents or id.
+    pub fn dedup_key(&self) -> String {
+        [
+            &self.recipient_name,
+            &self.street_name,
+            &self.building_number,
+            &self.post_box,
+            &self.post_code,
+            &self.town_name,
+            &self.country,
+        ]
+        .iter()
+        .map(|field| field.as_deref().map(normalize_town).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("|")
+    }
+
+    /// Carries over from the stored record what its lines do not hold: the
+    /// statement references, and the geolocation as long as the fields it was
+    /// found from are unchanged.
+    pub fn keep_stored_data(&mut self, stored: &ISO20022Address) {
+        self.source_references = stored.source_references.clone();
+        let same_location = self.street_name == stored.street_name
+            && self.building_number == stored.building_number
+            && self.post_code == stored.post_code
+            && self.town_name == stored.town_name
+            && self.country == stored.country;
+        if same_location {
+            self.geolocation = stored.geolocation.clone();
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeoLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub ban_id: String, // Identifier of the matching BAN housenumber.
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
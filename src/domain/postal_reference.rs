@@ -0,0 +1,346 @@
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+
+/// One row of the La Poste "base officielle des codes postaux" (HexaSmal) dataset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostalReferenceEntry {
+    pub insee_code: String,
+    pub commune_name: String,
+    pub post_code: String,
+    pub delivery_label: String, // Libellé d'acheminement, the town printed on line 6
+    pub line5: Option<String>,  // Lieu-dit or former commune, when the row carries one
+}
+
+/// Outcome of checking a postal code / town pair against the reference.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostalCheck {
+    Consistent,
+    UnknownPostalCode,
+    Mismatch {
+        expected_towns: Vec<String>,
+        suggested_post_codes: Vec<String>,
+    },
+}
+
+/// What to do when a typed town only approximately matches the official label.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TownCorrectionMode {
+    #[default]
+    SuggestOnly,
+    AutoCorrect,
+}
+
+impl FromStr for TownCorrectionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "suggest" => Ok(TownCorrectionMode::SuggestOnly),
+            "auto" => Ok(TownCorrectionMode::AutoCorrect),
+            invalid => Err(format!(
+                "Invalid town correction mode: {}, must be 'suggest' or 'auto'",
+                invalid
+            )),
+        }
+    }
+}
+
+/// Official delivery label found for a typed town, with the edit distance
+/// between their normalized forms (0 when they only differ by accents, case,
+/// hyphens or "ST"/"SAINT").
+#[derive(Debug, Clone, PartialEq)]
+pub struct TownMatch {
+    pub official_label: String,
+    pub distance: usize,
+}
+
+/// In-memory index of the postal code ↔ libellé d'acheminement pairs.
+#[derive(Debug, Clone, Default)]
+pub struct PostalReference {
+    by_post_code: HashMap<String, Vec<PostalReferenceEntry>>,
+    by_town: HashMap<String, BTreeSet<String>>,
+    correction_mode: TownCorrectionMode,
+}
+
+impl PostalReference {
+    pub fn new(entries: Vec<PostalReferenceEntry>) -> Self {
+        let mut reference = Self::default();
+        for entry in entries {
+            for name in [
+                Some(&entry.delivery_label),
+                Some(&entry.commune_name),
+                entry.line5.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                reference
+                    .by_town
+                    .entry(normalize_town(name))
+                    .or_default()
+                    .insert(entry.post_code.clone());
+            }
+            reference
+                .by_post_code
+                .entry(entry.post_code.clone())
+                .or_default()
+                .push(entry);
+        }
+        reference
+    }
+
+    /// Parses the La Poste CSV export. Both the historical `;`-separated file
+    /// and the `,`-separated datanova export are accepted; the header line is skipped.
+    pub fn from_csv_str(data: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        let mut lines = data.lines().enumerate();
+        let separator = match lines.next() {
+            Some((_, header)) if header.contains(';') => ';',
+            Some(_) => ',',
+            None => return Ok(Self::default()),
+        };
+
+        for (index, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(separator).map(str::trim).collect();
+            if fields.len() < 4 {
+                return Err(format!(
+                    "Invalid postal reference row {}: expected at least 4 columns",
+                    index + 1
+                ));
+            }
+            entries.push(PostalReferenceEntry {
+                insee_code: fields[0].to_string(),
+                commune_name: fields[1].to_string(),
+                post_code: fields[2].to_string(),
+                delivery_label: fields[3].to_string(),
+                line5: fields
+                    .get(4)
+                    .filter(|l5| !l5.is_empty())
+                    .map(|l5| l5.to_string()),
+            });
+        }
+
+        Ok(Self::new(entries))
+    }
+
+    pub fn with_correction_mode(mut self, mode: TownCorrectionMode) -> Self {
+        self.correction_mode = mode;
+        self
+    }
+
+    pub fn correction_mode(&self) -> TownCorrectionMode {
+        self.correction_mode
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_post_code.is_empty()
+    }
+
+    pub fn entries_for_post_code(&self, post_code: &str) -> &[PostalReferenceEntry] {
+        self.by_post_code
+            .get(post_code)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Distinct delivery labels served by a postal code.
+    pub fn towns_for_post_code(&self, post_code: &str) -> Vec<String> {
+        let towns: BTreeSet<&str> = self
+            .entries_for_post_code(post_code)
+            .iter()
+            .map(|e| e.delivery_label.as_str())
+            .collect();
+        towns.into_iter().map(str::to_string).collect()
+    }
+
+    /// Postal codes whose delivery label, commune or lieu-dit matches the town.
+    pub fn post_codes_for_town(&self, town: &str) -> Vec<String> {
+        self.by_town
+            .get(&normalize_town(town))
+            .map(|codes| codes.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn check(&self, post_code: &str, town: &str) -> PostalCheck {
+        let entries = self.entries_for_post_code(post_code);
+        if entries.is_empty() {
+            return PostalCheck::UnknownPostalCode;
+        }
+
+        let town = normalize_town(town);
+        let matches = entries.iter().any(|e| {
+            normalize_town(&e.delivery_label) == town
+                || normalize_town(&e.commune_name) == town
+                || e.line5.as_deref().map(normalize_town).as_ref() == Some(&town)
+        });
+
+        if matches {
+            PostalCheck::Consistent
+        } else {
+            PostalCheck::Mismatch {
+                expected_towns: self.towns_for_post_code(post_code),
+                suggested_post_codes: self.post_codes_for_town(&town),
+            }
+        }
+    }
+
+    /// Closest delivery label of the postal code, if it is within the tolerated
+    /// number of typos for the length of the typed town.
+    pub fn closest_town(&self, post_code: &str, town: &str) -> Option<TownMatch> {
+        let typed = &normalize_town(town);
+        let tolerance = (typed.chars().count() / 4).max(1);
+
+        self.entries_for_post_code(post_code)
+            .iter()
+            .flat_map(|e| {
+                [
+                    Some(&e.delivery_label),
+                    Some(&e.commune_name),
+                    e.line5.as_ref(),
+                ]
+                .into_iter()
+                .flatten()
+                .map(move |name| TownMatch {
+                    official_label: e.delivery_label.clone(),
+                    distance: levenshtein(typed, &normalize_town(name)),
+                })
+            })
+            .filter(|m| m.distance <= tolerance)
+            .min_by_key(|m| m.distance)
+    }
+
+    /// Rewrites a "CODE TOWN" line 6 with the official delivery label when the
+    /// typed town is a recognisable variant of it. Returns `None` when the line
+    /// is already official or no label is close enough.
+    pub fn correct_line6(&self, line6: &str) -> Option<String> {
+        let (post_code, town) = line6.trim().split_once(char::is_whitespace)?;
+        let town = town.trim();
+
+        self.closest_town(post_code, town)
+            .filter(|m| m.official_label != town)
+            .map(|m| format!("{} {}", post_code, m.official_label))
+    }
+}
+
+/// Uppercases, removes French diacritics, turns hyphens/apostrophes into spaces
+/// and expands "ST"/"STE", so that "St-Étienne" and "SAINT ETIENNE" compare equal.
+pub fn normalize_town(town: &str) -> String {
+    town.chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ä' | 'À' | 'Á' | 'Â' | 'Ä' => 'A',
+            'ç' | 'Ç' => 'C',
+            'è' | 'é' | 'ê' | 'ë' | 'È' | 'É' | 'Ê' | 'Ë' => 'E',
+            'ì' | 'í' | 'î' | 'ï' | 'Ì' | 'Í' | 'Î' | 'Ï' => 'I',
+            'ò' | 'ó' | 'ô' | 'ö' | 'Ò' | 'Ó' | 'Ô' | 'Ö' => 'O',
+            'ù' | 'ú' | 'û' | 'ü' | 'Ù' | 'Ú' | 'Û' | 'Ü' => 'U',
+            'ÿ' | 'Ÿ' => 'Y',
+            '-' | '\'' | '’' => ' ',
+            other => other.to_ascii_uppercase(),
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .map(|word| match word {
+            "ST" => "SAINT",
+            "STE" => "SAINTE",
+            other => other,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub(crate) fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str =
+        "#Code_commune_INSEE;Nom_de_la_commune;Code_postal;Libellé_d_acheminement;Ligne_5
+33243;LIBOURNE;33500;LIBOURNE;
+42218;ST ETIENNE;42000;ST ETIENNE;
+42218;ST ETIENNE;42100;ST ETIENNE;
+75101;PARIS 01;75001;PARIS;
+69381;LYON 01;69001;LYON;
+01004;AMBERIEU EN BUGEY;01500;AMBERIEU EN BUGEY;
+01004;AMBERIEU EN BUGEY;01500;AMBERIEU EN BUGEY;ST GERMAIN";
+
+    #[test]
+    fn test_from_csv_str_indexes_both_directions() {
+        let reference = PostalReference::from_csv_str(SAMPLE).unwrap();
+
+        assert_eq!(reference.towns_for_post_code("75001"), vec!["PARIS"]);
+        assert_eq!(
+            reference.post_codes_for_town("st-étienne"),
+            vec!["42000", "42100"]
+        );
+    }
+
+    #[test]
+    fn test_check_pairs() {
+        let reference = PostalReference::from_csv_str(SAMPLE).unwrap();
+
+        assert_eq!(reference.check("75001", "Paris"), PostalCheck::Consistent);
+        assert_eq!(
+            reference.check("01500", "ST-GERMAIN"),
+            PostalCheck::Consistent
+        );
+        assert_eq!(
+            reference.check("00000", "X"),
+            PostalCheck::UnknownPostalCode
+        );
+        assert_eq!(
+            reference.check("75001", "LYON"),
+            PostalCheck::Mismatch {
+                expected_towns: vec!["PARIS".to_string()],
+                suggested_post_codes: vec!["69001".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_normalize_town_variants() {
+        assert_eq!(normalize_town("St-Étienne"), "SAINT ETIENNE");
+        assert_eq!(normalize_town("Saint-Etienne"), "SAINT ETIENNE");
+        assert_eq!(normalize_town("ST ETIENNE"), "SAINT ETIENNE");
+        assert_eq!(levenshtein("LIBOUNRE", "LIBOURNE"), 2);
+    }
+
+    #[test]
+    fn test_correct_line6() {
+        let reference = PostalReference::from_csv_str(SAMPLE).unwrap();
+
+        assert_eq!(
+            reference.correct_line6("42000 Saint-Etienne"),
+            Some("42000 ST ETIENNE".to_string())
+        );
+        assert_eq!(
+            reference.correct_line6("33500 LIBOUNRE"),
+            Some("33500 LIBOURNE".to_string())
+        );
+        assert_eq!(reference.correct_line6("33500 LIBOURNE"), None);
+        assert_eq!(reference.correct_line6("33500 BORDEAUX"), None);
+    }
+
+    #[test]
+    fn test_from_csv_str_rejects_short_rows() {
+        let data = "code_commune_insee,nom_de_la_commune,code_postal,libelle_d_acheminement\n33243,LIBOURNE";
+        assert!(PostalReference::from_csv_str(data).is_err());
+    }
+}
@@ -1,12 +1,101 @@
 use crate::domain::models::ISO20022Address;
+use crate::domain::search::{AddressPage, AddressQuery};
+use std::collections::HashSet;
+use std::fmt;
 
+/// Why a repository refused or failed an operation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepositoryError {
+    /// No address is stored with this id.
+    NotFound(String),
+    /// An address is already stored with this id.
+    AlreadyExists(String),
+    /// The write contradicts itself, as a batch holding an id twice.
+    Conflict(String),
+    /// The backend could not be read or written.
+    Storage(String),
+    /// Stored data or an address could not be encoded or decoded.
+    Serialization(String),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(id) => write!(f, "Address {} not found", id),
+            Self::AlreadyExists(id) => write!(f, "Address {} already exists", id),
+            Self::Conflict(message) => write!(f, "Conflict: {}", message),
+            Self::Storage(message) => write!(f, "Storage error: {}", message),
+            Self::Serialization(message) => write!(f, "Serialization error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+/// Fails with `Conflict` when a batch holds the same id twice.
+pub fn check_unique_ids(addresses: &[ISO20022Address]) -> Result<(), RepositoryError> {
+    let mut ids = HashSet::new();
+    match addresses
+        .iter()
+        .find(|address| !ids.insert(address.id.as_str()))
+    {
+        Some(address) => Err(RepositoryError::Conflict(format!(
+            "address {} appears twice in the batch",
+            address.id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Writes of the addresses. `save` and `save_all` refuse ids already stored
+/// (`AlreadyExists`), `update` and `delete` ids that are not (`NotFound`).
 pub trait AddressRepository: Send + Sync {
-    fn save(&mut self, address: ISO20022Address) -> Result<(), String>;
-    fn update(&mut self, address: ISO20022Address) -> Result<(), String>;
-    fn delete(&mut self, address_id: &str) -> Result<(), String>;
+    fn save(&mut self, address: ISO20022Address) -> Result<(), RepositoryError>;
+    fn update(&mut self, address: ISO20022Address) -> Result<(), RepositoryError>;
+    fn delete(&mut self, address_id: &str) -> Result<(), RepositoryError>;
+
+    /// Saves a batch of addresses; backends that can write them at once override it.
+    fn save_all(&mut self, addresses: Vec<ISO20022Address>) -> Result<(), RepositoryError> {
+        check_unique_ids(&addresses)?;
+        addresses
+            .into_iter()
+            .try_for_each(|address| self.save(address))
+    }
 }
 
+/// Reads of the addresses. An unreachable backend or unreadable data is an
+/// error, never an empty result.
 pub trait ReadAddressRepository: Send + Sync {
-    fn find_by_id(&self, address_id: &str) -> Option<ISO20022Address>;
-    fn find_all(&self) -> Vec<ISO20022Address>;
-}
\ No newline at end of file
+    fn find_by_id(&self, address_id: &str) -> Result<Option<ISO20022Address>, RepositoryError>;
+    fn find_all(&self) -> Result<Vec<ISO20022Address>, RepositoryError>;
+
+    /// Iterates over all addresses; backends that can read them one at a time
+    /// override it so large exports don't hold the whole collection. A failed
+    /// read is yielded as an error, after which the iteration should stop.
+    fn stream_all(&self) -> AddressStream {
+        match self.find_all() {
+            Ok(addresses) => Box::new(addresses.into_iter().map(Ok)),
+            Err(e) => Box::new(std::iter::once(Err(e))),
+        }
+    }
+
+    /// The page of addresses matching `query`; backends that can filter,
+    /// sort and count where the addresses are stored override it.
+    fn search(&self, query: &AddressQuery) -> Result<AddressPage, RepositoryError> {
+        let mut failure = None;
+        let page = query.apply(until_error(self.stream_all(), &mut failure));
+        failure.map_or(Ok(page), Err)
+    }
+}
+
+/// Owns what it reads from, so that it can outlive the borrow of the repository.
+pub type AddressStream = Box<dyn Iterator<Item = Result<ISO20022Address, RepositoryError>>>;
+
+/// The addresses of `stream` up to its first error, which is kept in `failure`,
+/// for consumers that take plain addresses.
+pub fn until_error<'a>(
+    stream: impl Iterator<Item = Result<ISO20022Address, RepositoryError>> + 'a,
+    failure: &'a mut Option<RepositoryError>,
+) -> impl Iterator<Item = ISO20022Address> + 'a {
+    stream.map_while(move |address| address.map_err(|e| *failure = Some(e)).ok())
+}
@@ -0,0 +1,272 @@
+use crate::domain::models::{AddressKind, ISO20022Address};
+use serde::Serialize;
+use std::borrow::Borrow;
+use std::str::FromStr;
+
+/// Page size of a search asking for a page without giving its size.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+/// Largest page a search returns.
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Field the matching addresses are sorted on, then by id.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SortField {
+    #[default]
+    Id,
+    RecipientName,
+    PostCode,
+    TownName,
+    Country,
+    StreetName,
+}
+
+impl SortField {
+    /// Name of the field in the stored address.
+    pub fn field_name(self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::RecipientName => "recipient_name",
+            Self::PostCode => "post_code",
+            Self::TownName => "town_name",
+            Self::Country => "country",
+            Self::StreetName => "street_name",
+        }
+    }
+
+    fn value(self, address: &ISO20022Address) -> Option<&str> {
+        match self {
+            Self::Id => Some(&address.id),
+            Self::RecipientName => address.recipient_name.as_deref(),
+            Self::PostCode => address.post_code.as_deref(),
+            Self::TownName => address.town_name.as_deref(),
+            Self::Country => address.country.as_deref(),
+            Self::StreetName => address.street_name.as_deref(),
+        }
+    }
+}
+
+impl FromStr for SortField {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "id" => Ok(Self::Id),
+            "recipient_name" | "name" => Ok(Self::RecipientName),
+            "post_code" => Ok(Self::PostCode),
+            "town_name" | "town" => Ok(Self::TownName),
+            "country" => Ok(Self::Country),
+            "street_name" | "street" => Ok(Self::StreetName),
+            other => Err(format!(
+                "Invalid sort field {}, must be 'id', 'recipient_name', 'post_code', 'town_name', 'country' or 'street_name'",
+                other
+            )),
+        }
+    }
+}
+
+/// Criteria of `ReadAddressRepository::search`; an address must match all
+/// those given. Text criteria ignore case.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddressQuery {
+    /// Start of the postal code: "75" for Paris, "75008" for one arrondissement.
+    pub post_code: Option<String>,
+    /// Whole town name.
+    pub town: Option<String>,
+    /// ISO 3166 alpha-2 code.
+    pub country: Option<String>,
+    pub kind: Option<AddressKind>,
+    /// Part of the recipient name.
+    pub recipient_name: Option<String>,
+    /// Part of the street name.
+    pub street_name: Option<String>,
+    pub sort: SortField,
+    pub descending: bool,
+    /// Page to return, from 1.
+    pub page: usize,
+    /// Addresses per page; all the matching ones when `None`.
+    pub page_size: Option<usize>,
+}
+
+impl Default for AddressQuery {
+    fn default() -> Self {
+        Self {
+            post_code: None,
+            town: None,
+            country: None,
+            kind: None,
+            recipient_name: None,
+            street_name: None,
+            sort: SortField::default(),
+            descending: false,
+            page: 1,
+            page_size: None,
+        }
+    }
+}
+
+/// One page of the addresses matching a query, with how many match in all.
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressPage {
+    pub addresses: Vec<ISO20022Address>,
+    pub total: usize,
+    pub page: usize,
+    pub page_size: Option<usize>,
+}
+
+impl AddressQuery {
+    /// Rejects page 0 and page sizes outside 1 to `MAX_PAGE_SIZE`.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.page == 0 {
+            return Err("Invalid page 0, pages start at 1".to_string());
+        }
+        match self.page_size {
+            Some(size) if size == 0 || size > MAX_PAGE_SIZE => Err(format!(
+                "Invalid page size {}, must be between 1 and {}",
+                size, MAX_PAGE_SIZE
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Addresses before the requested page.
+    pub fn skip(&self) -> usize {
+        self.page_size
+            .map_or(0, |size| self.page.saturating_sub(1).saturating_mul(size))
+    }
+
+    pub fn matches(&self, address: &ISO20022Address) -> bool {
+        let field =
+            |value: &Option<String>, test: fn(&str, &str) -> bool, criterion: &Option<String>| {
+                criterion.as_deref().is_none_or(|criterion| {
+                    value.as_deref().is_some_and(|value| {
+                        test(&value.to_lowercase(), &criterion.trim().to_lowercase())
+                    })
+                })
+            };
+        self.kind.is_none_or(|kind| address.kind == kind)
+            && field(&address.post_code, |v, c| v.starts_with(c), &self.post_code)
+            && field(&address.town_name, |v, c| v == c, &self.town)
+            && field(&address.country, |v, c| v == c, &self.country)
+            && field(
+                &address.recipient_name,
+                |v, c| v.contains(c),
+                &self.recipient_name,
+            )
+            && field(
+                &address.street_name,
+                |v, c| v.contains(c),
+                &self.street_name,
+            )
+    }
+
+    /// Filters, sorts and pages addresses read from a backend that cannot do it
+    /// itself.
+    pub fn apply<A: Borrow<ISO20022Address>>(
+        &self,
+        addresses: impl IntoIterator<Item = A>,
+    ) -> AddressPage {
+        let mut matching: Vec<A> = addresses
+            .into_iter()
+            .filter(|address| self.matches(address.borrow()))
+            .collect();
+        matching.sort_by(|a, b| {
+            let (a, b) = (a.borrow(), b.borrow());
+            let order = self
+                .sort
+                .value(a)
+                .cmp(&self.sort.value(b))
+                .then_with(|| a.id.cmp(&b.id));
+            if self.descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        let total = matching.len();
+        let addresses = matching
+            .iter()
+            .skip(self.skip())
+            .take(self.page_size.unwrap_or(usize::MAX))
+            .map(|address| address.borrow().clone())
+            .collect();
+        AddressPage {
+            addresses,
+            total,
+            page: self.page,
+            page_size: self.page_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(id: &str, name: &str, post_code: &str, town: &str) -> ISO20022Address {
+        ISO20022Address {
+            id: id.to_string(),
+            kind: AddressKind::Company,
+            recipient_name: Some(name.to_string()),
+            street_name: Some("RUE DU FAUBOURG SAINT-HONORÉ".to_string()),
+            post_code: Some(post_code.to_string()),
+            town_name: Some(town.to_string()),
+            country: Some("FR".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_filters_sorts_and_pages() {
+        let addresses = vec![
+            address("1", "DURAND SA", "75008", "PARIS"),
+            address("2", "Martin Conseil", "75001", "PARIS"),
+            address("3", "ACME", "69001", "LYON"),
+            address("4", "Boulangerie Martin", "75008", "PARIS"),
+        ];
+        let query = AddressQuery {
+            post_code: Some("75".to_string()),
+            town: Some("paris".to_string()),
+            recipient_name: Some("MARTIN".to_string()),
+            street_name: Some("saint-honoré".to_string()),
+            sort: SortField::RecipientName,
+            ..Default::default()
+        };
+
+        let page = query.apply(&addresses);
+
+        assert_eq!(page.total, 2);
+        let ids: Vec<&str> = page.addresses.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["4", "2"]);
+
+        let query = AddressQuery {
+            descending: true,
+            page: 2,
+            page_size: Some(3),
+            ..Default::default()
+        };
+        let page = query.apply(addresses.clone());
+        assert_eq!((page.total, page.addresses[0].id.as_str()), (4, "1"));
+
+        let query = AddressQuery {
+            kind: Some(AddressKind::Particular),
+            ..Default::default()
+        };
+        assert_eq!(query.apply(&addresses).total, 0);
+    }
+
+    #[test]
+    fn test_query_validation() {
+        let query = AddressQuery {
+            page: 0,
+            ..Default::default()
+        };
+        assert!(query.validate().is_err());
+        let query = AddressQuery {
+            page_size: Some(MAX_PAGE_SIZE + 1),
+            ..Default::default()
+        };
+        assert!(query.validate().is_err());
+        assert_eq!("town".parse::<SortField>(), Ok(SortField::TownName));
+        assert!("zip".parse::<SortField>().is_err());
+    }
+}
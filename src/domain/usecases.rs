@@ -1,4 +1,7 @@
+use crate::domain::countries::find_country;
 use crate::domain::models::{AddressKind, FrenchAddress, ISO20022Address};
+use crate::domain::postal_reference::normalize_town;
+use crate::domain::validation::{ValidationError, validate_iso20022_address};
 use regex::Regex;
 
 fn parse_postal_code_and_town(s: &str) -> (Option<String>, Option<String>) {
@@ -13,20 +16,23 @@ fn parse_postal_code_and_town(s: &str) -> (Option<String>, Option<String>) {
     }
 }
 
-fn process_street(line4: &Option<String>) -> (Option<String>, Option<String>) {
+pub(crate) fn process_street(line4: &Option<String>) -> (Option<String>, Option<String>) {
     if let Some(line) = line4 {
         let mut parts = line.split_whitespace();
-        if let Some(first_token) = parts.next() {
-            if first_token.chars().next().map_or(false, |c| c.is_digit(10)) {
-                let building_number = Some(first_token.to_string());
-                let street_name = parts.collect::<Vec<&str>>().join(" ");
-                let street_name = if street_name.is_empty() {
-                    None
-                } else {
-                    Some(street_name)
-                };
-                return (building_number, street_name);
-            }
+        if let Some(first_token) = parts.next()
+            && first_token
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_digit())
+        {
+            let building_number = Some(first_token.to_string());
+            let street_name = parts.collect::<Vec<&str>>().join(" ");
+            let street_name = if street_name.is_empty() {
+                None
+            } else {
+                Some(street_name)
+            };
+            return (building_number, street_name);
         }
         return (None, Some(line.clone()));
     }
@@ -36,17 +42,37 @@ fn process_street(line4: &Option<String>) -> (Option<String>, Option<String>) {
 fn process_common_fields(address: &FrenchAddress, iso: &mut ISO20022Address) {
     iso.post_box = address.line5.clone();
 
+    let destination = address.line7.as_deref().and_then(find_country);
+
+    let unlisted = destination.is_none()
+        && address
+            .line7
+            .as_deref()
+            .is_some_and(|l7| !l7.trim().is_empty());
+
     if let Some(ref line6) = address.line6 {
-        let (postal_code, town) = parse_postal_code_and_town(line6);
-        iso.post_code = postal_code;
-        iso.town_name = town;
+        match destination.filter(|c| c.code != "FR") {
+            // No known layout for the town line of other countries: kept whole
+            None if unlisted => iso.town_name = Some(line6.trim().to_string()),
+            Some(country) => {
+                if let Some(town_line) = country.parse_town_line(line6) {
+                    iso.post_code = town_line.post_code;
+                    iso.town_name = Some(town_line.town);
+                    iso.country_sub_division = town_line.country_sub_division;
+                }
+            }
+            None => {
+                let (postal_code, town) = parse_postal_code_and_town(line6);
+                iso.post_code = postal_code;
+                iso.town_name = town;
+            }
+        }
     }
 
     if let Some(ref line7) = address.line7 {
-        iso.country = Some(if line7.trim().eq_ignore_ascii_case("france") {
-            "FR".to_string()
-        } else {
-            line7.clone()
+        iso.country = Some(match destination {
+            Some(country) => country.code.to_string(),
+            None => line7.trim().to_uppercase(),
         });
     }
 
@@ -55,15 +81,74 @@ fn process_common_fields(address: &FrenchAddress, iso: &mut ISO20022Address) {
     iso.street_name = street_name;
 }
 
-pub fn convert_to_iso(address: &FrenchAddress, kind: AddressKind) -> ISO20022Address {
-    println!("DEBUG - AddressKind: {:?}", kind);
-
-    let mut iso = ISO20022Address::default();
-    iso.id = address.id.clone();
-    iso.recipient_name = address.line1.clone();
-    iso.kind = kind;
+/// Legal forms and words that mark line 1 as an organisation ("DURAND SA",
+/// "MAIRIE DE LYON", "Muster GmbH").
+const COMPANY_MARKERS: [&str; 24] = [
+    "SA",
+    "SAS",
+    "SASU",
+    "SARL",
+    "EURL",
+    "SNC",
+    "SCI",
+    "SCP",
+    "SCOP",
+    "SELARL",
+    "GIE",
+    "EARL",
+    "ASSOCIATION",
+    "MAIRIE",
+    "SOCIETE",
+    "CABINET",
+    "GMBH",
+    "AG",
+    "LTD",
+    "LLC",
+    "INC",
+    "BV",
+    "NV",
+    "SPA",
+];
+
+/// Guesses whether an address is a company's: line 1 carries a legal form or an
+/// organisation word, or line 2 names a department ("SERVICE ACHATS").
+pub fn detect_kind(address: &FrenchAddress) -> AddressKind {
+    let words = |line: &Option<String>| -> Vec<String> {
+        line.as_deref()
+            .map(normalize_town)
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    };
+    let recipient = words(&address.line1);
+    let department = words(&address.line2);
+    let is_company = recipient
+        .iter()
+        .any(|w| COMPANY_MARKERS.contains(&w.as_str()))
+        || department
+            .first()
+            .is_some_and(|w| ["SERVICE", "DIRECTION", "DEPARTEMENT"].contains(&w.as_str()));
+    if is_company {
+        AddressKind::Company
+    } else {
+        AddressKind::Particular
+    }
+}
 
-    println!("DEBUG - ISO20022Address before update: {:#?}", iso);
+/// Converts a French address to ISO 20022, returning the ISO validation error
+/// when a line does not fit its field (e.g. a post box line longer than the 16
+/// characters of `PstBx`).
+pub fn convert_to_iso(
+    address: &FrenchAddress,
+    kind: AddressKind,
+) -> Result<ISO20022Address, ValidationError> {
+    let mut iso = ISO20022Address {
+        id: address.id.clone(),
+        recipient_name: address.line1.clone(),
+        kind,
+        ..Default::default()
+    };
 
     process_common_fields(address, &mut iso);
 
@@ -78,13 +163,8 @@ pub fn convert_to_iso(address: &FrenchAddress, kind: AddressKind) -> ISO20022Add
         }
     }
 
-    println!("DEBUG - ISO20022Address after update: {:#?}", iso);
-
-    if let Err(e) = crate::domain::validation::validate_iso20022_address(&iso) {
-        return Err(format!("Validation error: {:?}", e)).unwrap();
-    }
-
-    iso
+    validate_iso20022_address(&iso)?;
+    Ok(iso)
 }
 
 pub fn convert_to_french(iso: &ISO20022Address) -> FrenchAddress {
@@ -104,7 +184,21 @@ pub fn convert_to_french(iso: &ISO20022Address) -> FrenchAddress {
                 ))
             }),
         line5: iso.post_box.clone(),
-        line6: iso
+        line6: None, // Dépend de l'ordre code postal / localité du pays
+        line7: iso.country.clone().map(|c| match find_country(&c) {
+            Some(country) if country.code == "FR" => "France".to_string(),
+            Some(country) => country.french_name().to_string(),
+            None => c,
+        }),
+    };
+
+    french.line6 = match iso.country.as_deref().and_then(find_country) {
+        Some(country) if country.code != "FR" => country.format_town_line(
+            iso.post_code.as_deref(),
+            iso.town_name.as_deref(),
+            iso.country_sub_division.as_deref(),
+        ),
+        _ => iso
             .post_code
             .as_ref()
             .map_or(iso.town_name.clone(), |code| {
@@ -114,10 +208,6 @@ pub fn convert_to_french(iso: &ISO20022Address) -> FrenchAddress {
                     iso.town_name.as_deref().unwrap_or("")
                 ))
             }),
-        line7: iso
-            .country
-            .clone()
-            .map(|c| if c == "FR" { "France".to_string() } else { c }),
     };
 
     match iso.kind {
@@ -138,6 +228,7 @@ pub fn convert_to_french(iso: &ISO20022Address) -> FrenchAddress {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::validation::validate_french_address;
 
     //////////////////////////////////////CONVERT TO ISO//////////////////////////////////////
     #[test]
@@ -210,7 +301,7 @@ mod tests {
             line7: Some("France".to_string()),
         };
 
-        let iso = convert_to_iso(&address, AddressKind::Company);
+        let iso = convert_to_iso(&address, AddressKind::Company).unwrap();
 
         assert_eq!(iso.id, "123");
         assert_eq!(iso.department, Some("Finance Department".to_string()));
@@ -235,7 +326,7 @@ mod tests {
             line7: Some("France".to_string()),
         };
 
-        let iso = convert_to_iso(&address, AddressKind::Particular);
+        let iso = convert_to_iso(&address, AddressKind::Particular).unwrap();
 
         assert_eq!(iso.id, "456");
         assert_eq!(iso.room, Some("Apt. 12B".to_string()));
@@ -329,4 +420,82 @@ mod tests {
         assert_eq!(french.line6, Some("33506 LIBOURNE CEDEX".to_string()));
         assert_eq!(french.line7, Some("France".to_string()));
     }
+
+    #[test]
+    fn test_convert_foreign_destination_round_trip() {
+        let address = FrenchAddress {
+            id: "789".to_string(),
+            line1: Some("John Smith".to_string()),
+            line2: None,
+            line3: None,
+            line4: Some("10 Downing Street".to_string()),
+            line5: None,
+            line6: Some("LONDON SW1A 2AA".to_string()),
+            line7: Some("ROYAUME-UNI".to_string()),
+        };
+
+        let iso = convert_to_iso(&address, AddressKind::Particular).unwrap();
+        assert_eq!(iso.post_code, Some("SW1A 2AA".to_string()));
+        assert_eq!(iso.town_name, Some("LONDON".to_string()));
+        assert_eq!(iso.country, Some("GB".to_string()));
+
+        let french = convert_to_french(&iso);
+        assert_eq!(french.line6, Some("LONDON SW1A 2AA".to_string()));
+        assert_eq!(french.line7, Some("ROYAUME-UNI".to_string()));
+
+        let unlisted = FrenchAddress {
+            line6: Some("01310-100 SAO PAULO".to_string()),
+            line7: Some("BR".to_string()),
+            ..address
+        };
+        let iso = convert_to_iso(&unlisted, AddressKind::Particular).unwrap();
+        assert_eq!(iso.post_code, None);
+        assert_eq!(iso.town_name, Some("01310-100 SAO PAULO".to_string()));
+        assert_eq!(iso.country, Some("BR".to_string()));
+
+        // Un nom de pays hors table ne peut pas devenir un code pays
+        let unknown_name = FrenchAddress {
+            line7: Some("BRESIL".to_string()),
+            ..unlisted
+        };
+        assert!(matches!(
+            convert_to_iso(&unknown_name, AddressKind::Particular),
+            Err(ValidationError::InvalidCountryCode { value }) if value == "BRESIL"
+        ));
+        assert!(matches!(
+            validate_french_address(&unknown_name),
+            Err(ValidationError::InvalidCountryCode { value }) if value == "BRESIL"
+        ));
+    }
+
+    #[test]
+    fn test_detect_kind() {
+        let address = |line1: &str, line2: Option<&str>| FrenchAddress {
+            id: "1".to_string(),
+            line1: Some(line1.to_string()),
+            line2: line2.map(str::to_string),
+            line3: None,
+            line4: Some("1 RUE DE LA PAIX".to_string()),
+            line5: None,
+            line6: Some("75002 PARIS".to_string()),
+            line7: None,
+        };
+
+        assert_eq!(
+            detect_kind(&address("Société Durand SA", None)),
+            AddressKind::Company
+        );
+        assert_eq!(
+            detect_kind(&address("Mairie de Lyon", None)),
+            AddressKind::Company
+        );
+        assert_eq!(
+            detect_kind(&address("Durand", Some("Service Achats"))),
+            AddressKind::Company
+        );
+        assert_eq!(
+            detect_kind(&address("Madame Sabine Sagan", None)),
+            AddressKind::Particular
+        );
+    }
 }
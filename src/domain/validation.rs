@@ -1,5 +1,10 @@
+use crate::domain::countries;
+use crate::domain::formats::swift_mt::is_x_charset;
 use crate::domain::models::{FrenchAddress, ISO20022Address};
+use crate::domain::postal_reference::{PostalCheck, PostalReference};
 use regex::Regex;
+use serde::Serialize;
+use std::str::FromStr;
 
 #[derive(Debug)]
 pub enum ValidationError {
@@ -17,6 +22,24 @@ pub enum ValidationError {
     InvalidCountryCode {
         value: String,
     },
+    InvalidCharacters {
+        field: &'static str,
+        value: String,
+    },
+    UnstructuredAddress,
+    UnknownLine {
+        number: usize,
+    },
+    UnknownPostalCode {
+        value: String,
+    },
+    PostalCodeTownMismatch {
+        post_code: String,
+        town: String,
+        expected_towns: Vec<String>,
+        suggested_post_codes: Vec<String>,
+        suggested_town: Option<String>,
+    },
 }
 
 fn check_line_length(
@@ -24,14 +47,14 @@ fn check_line_length(
     field: &'static str,
     max_len: usize,
 ) -> Result<(), ValidationError> {
-    if let Some(v) = val {
-        if v.len() > max_len {
-            return Err(ValidationError::TooLong {
-                field,
-                max_len,
-                actual_len: v.len(),
-            });
-        }
+    if let Some(v) = val
+        && v.len() > max_len
+    {
+        return Err(ValidationError::TooLong {
+            field,
+            max_len,
+            actual_len: v.len(),
+        });
     }
     Ok(())
 }
@@ -45,29 +68,139 @@ pub fn validate_french_address(addr: &FrenchAddress) -> Result<(), ValidationErr
     check_line_length(&addr.line6, "line6", 38)?;
     check_line_length(&addr.line7, "line7", 38)?;
 
-    //Check that line6 is present and looks like a 5-digit code plus a city (optional)
-    if let Some(line6) = &addr.line6 {
-        let re = Regex::new(r"^(\d{5})(?:\s+(.+))?$").unwrap();
-        if !re.is_match(line6.trim()) {
-            return Err(ValidationError::InvalidPostalCode {
-                value: line6.to_string(),
-            });
-        }
-    } else {
-        return Err(ValidationError::EmptyField { field: "line6" });
+    check_town_line(addr.line6.as_deref(), destination(addr.line7.as_deref()))?;
+    check_country_line(addr.line7.as_deref())?;
+
+    Ok(())
+}
+
+/// Checks line `number` (1 to 7) of a French address on its own, with the
+/// rules of `validate_french_address`; line 6 follows the destination given
+/// by `line7`, France when `None`. Any other number is an `UnknownLine`.
+pub fn validate_french_line(
+    number: usize,
+    value: Option<&str>,
+    line7: Option<&str>,
+) -> Result<(), ValidationError> {
+    const FIELDS: [&str; 7] = [
+        "line1", "line2", "line3", "line4", "line5", "line6", "line7",
+    ];
+    let field = number
+        .checked_sub(1)
+        .and_then(|index| FIELDS.get(index))
+        .ok_or(ValidationError::UnknownLine { number })?;
+    check_line_length(&value.map(str::to_string), field, 38)?;
+    match number {
+        6 => check_town_line(value, destination(line7)),
+        7 => check_country_line(value),
+        _ => Ok(()),
+    }
+}
+
+/// Country an address is sent to, read from line 7.
+enum Destination {
+    France,
+    /// A country of `countries::COUNTRIES` (line7 = "ALLEMAGNE", "ROYAUME-UNI"...),
+    /// whose postcode/town line follows the order of that country.
+    Listed(&'static countries::Country),
+    /// Any other country, given by its ISO code ("BR"), whose town line is
+    /// taken as free text.
+    Other,
+}
+
+fn destination(line7: Option<&str>) -> Destination {
+    match line7.map(str::trim).filter(|l7| !l7.is_empty()) {
+        None => Destination::France,
+        Some(l7) => match countries::find_country(l7) {
+            Some(country) if country.code == "FR" => Destination::France,
+            Some(country) => Destination::Listed(country),
+            None => Destination::Other,
+        },
     }
+}
 
-    if let Some(l7) = &addr.line7 {
-        if l7.trim().is_empty() {
-            return Err(ValidationError::EmptyField { field: "line7" });
+//Check that line6 is present and looks like a 5-digit code plus a city (optional)
+fn check_town_line(line6: Option<&str>, destination: Destination) -> Result<(), ValidationError> {
+    let Some(line6) = line6 else {
+        return Err(ValidationError::EmptyField { field: "line6" });
+    };
+    let valid = match destination {
+        Destination::Listed(country) => country.parse_town_line(line6).is_some(),
+        Destination::Other => !line6.trim().is_empty(),
+        Destination::France => {
+            let re = Regex::new(r"^(\d{5})(?:\s+(.+))?$").unwrap();
+            re.is_match(line6.trim()) && is_french_department_prefix(&line6.trim()[..2])
         }
-    } else {
-        return Err(ValidationError::EmptyField { field: "line7" });
+    };
+    if !valid {
+        return Err(ValidationError::InvalidPostalCode {
+            value: line6.to_string(),
+        });
     }
+    Ok(())
+}
 
+//Check that line7 names a country of the table or gives an ISO code, the only
+//values the conversion can store as the country
+fn check_country_line(line7: Option<&str>) -> Result<(), ValidationError> {
+    let Some(l7) = line7.map(str::trim).filter(|l7| !l7.is_empty()) else {
+        return Err(ValidationError::EmptyField { field: "line7" });
+    };
+    let iso_code = l7.len() == 2 && l7.chars().all(|c| c.is_ascii_alphabetic());
+    if countries::find_country(l7).is_none() && !iso_code {
+        return Err(ValidationError::InvalidCountryCode {
+            value: l7.to_string(),
+        });
+    }
     Ok(())
 }
 
+/// Metropolitan départements (01-95, Corsica included as 20) and overseas (97, 98).
+fn is_french_department_prefix(prefix: &str) -> bool {
+    matches!(prefix.parse::<u8>(), Ok(1..=95 | 97 | 98))
+}
+
+/// Checks the line6 postal code / town pair against the La Poste reference.
+/// CEDEX codes are not part of the public dataset and are accepted as-is, as
+/// are addresses leaving France.
+pub fn validate_postal_consistency(
+    addr: &FrenchAddress,
+    reference: &PostalReference,
+) -> Result<(), ValidationError> {
+    if !matches!(destination(addr.line7.as_deref()), Destination::France) {
+        return Ok(());
+    }
+    let Some(line6) = &addr.line6 else {
+        return Err(ValidationError::EmptyField { field: "line6" });
+    };
+    let line6 = line6.trim();
+    let (post_code, town) = line6.split_at(line6.len().min(5));
+    let town = town.trim();
+
+    if town.is_empty() || town.to_uppercase().contains("CEDEX") {
+        return Ok(());
+    }
+
+    match reference.check(post_code, town) {
+        PostalCheck::Consistent => Ok(()),
+        PostalCheck::UnknownPostalCode => Err(ValidationError::UnknownPostalCode {
+            value: post_code.to_string(),
+        }),
+        PostalCheck::Mismatch {
+            expected_towns,
+            suggested_post_codes,
+        } => Err(ValidationError::PostalCodeTownMismatch {
+            post_code: post_code.to_string(),
+            town: town.to_string(),
+            expected_towns,
+            suggested_post_codes,
+            suggested_town: reference
+                .closest_town(post_code, town)
+                .map(|m| m.official_label),
+        }),
+    }
+}
+
 pub fn validate_iso20022_address(addr: &ISO20022Address) -> Result<(), ValidationError> {
     check_line_length(&addr.street_name, "street_name", 70)?;
     check_line_length(&addr.building_number, "building_number", 16)?;
@@ -83,10 +216,169 @@ pub fn validate_iso20022_address(addr: &ISO20022Address) -> Result<(), Validatio
     check_line_length(&addr.district_name, "district_name", 35)?;
     check_line_length(&addr.country_sub_division, "country_sub_division", 35)?;
 
-    if let Some(ctry) = &addr.country {
-        if ctry.len() != 2 {
-            return Err(ValidationError::InvalidCountryCode {
-                value: ctry.clone(),
+    if let Some(ctry) = &addr.country
+        && ctry.len() != 2
+    {
+        return Err(ValidationError::InvalidCountryCode {
+            value: ctry.clone(),
+        });
+    }
+
+    if let Some(post_code) = &addr.post_code
+        && let Some(country) = addr.country.as_deref().and_then(countries::find_country)
+        && !country.is_valid_post_code(post_code)
+    {
+        return Err(ValidationError::InvalidPostalCode {
+            value: post_code.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+/// How a party address is expressed in an ISO 20022 message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum AddressStructure {
+    /// Structured elements only (`StrtNm`, `TwnNm`...).
+    Structured,
+    /// Structured elements plus `AdrLine`s.
+    Hybrid,
+    /// `AdrLine`s only, possibly with a country.
+    Unstructured,
+    /// No postal data, or only a country.
+    Empty,
+}
+
+impl AddressStructure {
+    /// `iso` holds the structured elements; its country alone does not make
+    /// the address structured.
+    pub fn classify(iso: &ISO20022Address, address_lines: &[String]) -> Self {
+        let structured = [
+            &iso.department,
+            &iso.sub_department,
+            &iso.street_name,
+            &iso.building_number,
+            &iso.building_name,
+            &iso.floor,
+            &iso.post_box,
+            &iso.room,
+            &iso.post_code,
+            &iso.town_name,
+            &iso.town_location_name,
+            &iso.district_name,
+            &iso.country_sub_division,
+        ]
+        .iter()
+        .any(|field| field.is_some());
+
+        match (structured, address_lines.is_empty()) {
+            (true, true) => AddressStructure::Structured,
+            (true, false) => AddressStructure::Hybrid,
+            (false, false) => AddressStructure::Unstructured,
+            (false, true) => AddressStructure::Empty,
+        }
+    }
+}
+
+/// Market rules applied on top of `validate_iso20022_address`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum ValidationProfile {
+    /// ISO 20022 lengths, country code and postcode format only.
+    #[default]
+    Basic,
+    /// CBPR+ from November 2026: town and country mandatory, unstructured
+    /// addresses rejected, at most two `AdrLine`s of 70 characters.
+    Cbpr,
+    /// SEPA rulebooks from November 2026: the CBPR+ rules, restricted to the
+    /// SEPA (SWIFT "X") character set.
+    Sepa,
+}
+
+impl FromStr for ValidationProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "basic" => Ok(ValidationProfile::Basic),
+            "cbpr" | "cbpr+" => Ok(ValidationProfile::Cbpr),
+            "sepa" => Ok(ValidationProfile::Sepa),
+            invalid => Err(format!(
+                "Invalid validation profile: {}, must be 'basic', 'cbpr' or 'sepa'",
+                invalid
+            )),
+        }
+    }
+}
+
+const MAX_HYBRID_ADDRESS_LINES: usize = 2;
+const MAX_ADDRESS_LINE_LENGTH: usize = 70;
+
+/// Checks an address (structured part and `AdrLine`s) against a profile.
+pub fn validate_with_profile(
+    addr: &ISO20022Address,
+    address_lines: &[String],
+    profile: ValidationProfile,
+) -> Result<(), ValidationError> {
+    validate_iso20022_address(addr)?;
+    if profile == ValidationProfile::Basic {
+        return Ok(());
+    }
+
+    if AddressStructure::classify(addr, address_lines) == AddressStructure::Unstructured {
+        return Err(ValidationError::UnstructuredAddress);
+    }
+    if addr
+        .town_name
+        .as_deref()
+        .is_none_or(|t| t.trim().is_empty())
+    {
+        return Err(ValidationError::EmptyField { field: "town_name" });
+    }
+    if addr.country.as_deref().is_none_or(|c| c.trim().is_empty()) {
+        return Err(ValidationError::EmptyField { field: "country" });
+    }
+    if address_lines.len() > MAX_HYBRID_ADDRESS_LINES {
+        return Err(ValidationError::TooLong {
+            field: "address_lines",
+            max_len: MAX_HYBRID_ADDRESS_LINES,
+            actual_len: address_lines.len(),
+        });
+    }
+    for line in address_lines {
+        check_line_length(&Some(line.clone()), "address_line", MAX_ADDRESS_LINE_LENGTH)?;
+    }
+
+    if profile == ValidationProfile::Sepa {
+        let fields = [
+            ("recipient_name", &addr.recipient_name),
+            ("department", &addr.department),
+            ("sub_department", &addr.sub_department),
+            ("street_name", &addr.street_name),
+            ("building_number", &addr.building_number),
+            ("building_name", &addr.building_name),
+            ("floor", &addr.floor),
+            ("post_box", &addr.post_box),
+            ("room", &addr.room),
+            ("post_code", &addr.post_code),
+            ("town_name", &addr.town_name),
+            ("town_location_name", &addr.town_location_name),
+            ("district_name", &addr.district_name),
+            ("country_sub_division", &addr.country_sub_division),
+        ];
+        for (field, value) in fields {
+            if let Some(value) = value
+                && !is_x_charset(value)
+            {
+                return Err(ValidationError::InvalidCharacters {
+                    field,
+                    value: value.clone(),
+                });
+            }
+        }
+        if let Some(line) = address_lines.iter().find(|l| !is_x_charset(l)) {
+            return Err(ValidationError::InvalidCharacters {
+                field: "address_line",
+                value: line.clone(),
             });
         }
     }
@@ -1,10 +1,15 @@
 use crate::application::command::address_command_service::AddressCommandService;
 use crate::application::query::address_query_service::AddressQueryService;
-use std::sync::Mutex;
+use crate::application::query::address_verification_service::AddressVerificationService;
+use crate::domain::postal_reference::PostalReference;
+use std::sync::{Arc, Mutex};
 
 pub struct AppState {
     pub command_service: Mutex<AddressCommandService>,
     pub query_service: Mutex<AddressQueryService>,
+    pub verification_service: Option<AddressVerificationService>,
+    /// Reference French lines are checked against, when loaded.
+    pub postal_reference: Option<Arc<PostalReference>>,
 }
 
 impl AppState {
@@ -12,6 +17,18 @@ impl AppState {
         Self {
             command_service: Mutex::new(command_service),
             query_service: Mutex::new(query_service),
+            verification_service: None,
+            postal_reference: None,
         }
     }
-}
\ No newline at end of file
+
+    pub fn with_verification_service(mut self, service: AddressVerificationService) -> Self {
+        self.verification_service = Some(service);
+        self
+    }
+
+    pub fn with_postal_reference(mut self, reference: Arc<PostalReference>) -> Self {
+        self.postal_reference = Some(reference);
+        self
+    }
+}
@@ -0,0 +1,342 @@
+use crate::domain::models::{AddressKind, FrenchAddress, ISO20022Address};
+use crate::domain::usecases::convert_to_french;
+use serde_json::Value;
+use std::io::{BufWriter, Write};
+use std::str::FromStr;
+
+/// Columns of the ISO layout: the `ISO20022Address` fields, with the
+/// geolocation spread over `latitude`, `longitude` and `ban_id`.
+pub const ISO_COLUMNS: [&str; 21] = [
+    "id",
+    "kind",
+    "recipient_name",
+    "department",
+    "sub_department",
+    "building_name",
+    "floor",
+    "room",
+    "street_name",
+    "building_number",
+    "post_box",
+    "town_location_name",
+    "post_code",
+    "town_name",
+    "district_name",
+    "country_sub_division",
+    "country",
+    "latitude",
+    "longitude",
+    "ban_id",
+    "source_references",
+];
+
+/// Columns of the French layout, readable back by the CSV import.
+pub const FRENCH_COLUMNS: [&str; 9] = [
+    "id", "kind", "line1", "line2", "line3", "line4", "line5", "line6", "line7",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            other => Err(format!(
+                "Invalid export format {}, must be 'csv' or 'ndjson'",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportLayout {
+    /// Structured ISO 20022 fields.
+    Iso,
+    /// The seven NF Z10-011 lines given by `convert_to_french`.
+    French,
+}
+
+impl FromStr for ExportLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "iso" => Ok(Self::Iso),
+            "french" | "fr" => Ok(Self::French),
+            other => Err(format!(
+                "Invalid export layout {}, must be 'iso' or 'french'",
+                other
+            )),
+        }
+    }
+}
+
+impl ExportLayout {
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            Self::Iso => &ISO_COLUMNS,
+            Self::French => &FRENCH_COLUMNS,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub layout: ExportLayout,
+    pub columns: Vec<&'static str>,
+    /// CSV only; `;` like the CSV import.
+    pub delimiter: u8,
+}
+
+impl ExportOptions {
+    /// Takes the comma-separated `columns` in the given order, all the
+    /// layout's columns when `None`.
+    pub fn new(
+        format: ExportFormat,
+        layout: ExportLayout,
+        columns: Option<&str>,
+    ) -> Result<Self, String> {
+        let available = layout.columns();
+        let columns = match columns {
+            None => available.to_vec(),
+            Some(list) => list
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(|column| {
+                    available
+                        .iter()
+                        .find(|a| a.eq_ignore_ascii_case(column))
+                        .copied()
+                        .ok_or_else(|| {
+                            format!(
+                                "Unknown column {}, expected one of {}",
+                                column,
+                                available.join(", ")
+                            )
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        if columns.is_empty() {
+            return Err("No column to export".to_string());
+        }
+        Ok(Self {
+            format,
+            layout,
+            columns,
+            delimiter: b';',
+        })
+    }
+}
+
+/// Writes the addresses one at a time as CSV (with a header) or NDJSON (one
+/// object per line, keys in column order) and returns how many were written.
+pub fn export_addresses<W: Write>(
+    addresses: impl IntoIterator<Item = ISO20022Address>,
+    writer: W,
+    options: &ExportOptions,
+) -> Result<usize, String> {
+    let rows = addresses
+        .into_iter()
+        .map(|iso| row(&iso, options.layout, &options.columns));
+    match options.format {
+        ExportFormat::Csv => {
+            let mut csv = csv::WriterBuilder::new()
+                .delimiter(options.delimiter)
+                .from_writer(writer);
+            csv.write_record(&options.columns)
+                .map_err(|e| e.to_string())?;
+            let mut count = 0;
+            for values in rows {
+                csv.write_record(values.iter().map(csv_cell))
+                    .map_err(|e| e.to_string())?;
+                count += 1;
+            }
+            csv.flush().map_err(|e| e.to_string())?;
+            Ok(count)
+        }
+        ExportFormat::Ndjson => {
+            let mut out = BufWriter::new(writer);
+            let mut count = 0;
+            for values in rows {
+                let fields: Vec<String> = options
+                    .columns
+                    .iter()
+                    .zip(&values)
+                    .map(|(column, value)| format!("{}:{}", Value::from(*column), value))
+                    .collect();
+                writeln!(out, "{{{}}}", fields.join(",")).map_err(|e| e.to_string())?;
+                count += 1;
+            }
+            out.flush().map_err(|e| e.to_string())?;
+            Ok(count)
+        }
+    }
+}
+
+pub(crate) fn row(iso: &ISO20022Address, layout: ExportLayout, columns: &[&str]) -> Vec<Value> {
+    let french = (layout == ExportLayout::French).then(|| convert_to_french(iso));
+    columns
+        .iter()
+        .map(|column| match &french {
+            Some(french) => french_value(iso.kind, french, column),
+            None => iso_value(iso, column),
+        })
+        .collect()
+}
+
+fn iso_value(iso: &ISO20022Address, column: &str) -> Value {
+    let text = |field: &Option<String>| field.clone().map_or(Value::Null, Value::String);
+    match column {
+        "id" => Value::from(iso.id.clone()),
+        "kind" => kind_value(iso.kind),
+        "recipient_name" => text(&iso.recipient_name),
+        "department" => text(&iso.department),
+        "sub_department" => text(&iso.sub_department),
+        "building_name" => text(&iso.building_name),
+        "floor" => text(&iso.floor),
+        "room" => text(&iso.room),
+        "street_name" => text(&iso.street_name),
+        "building_number" => text(&iso.building_number),
+        "post_box" => text(&iso.post_box),
+        "town_location_name" => text(&iso.town_location_name),
+        "post_code" => text(&iso.post_code),
+        "town_name" => text(&iso.town_name),
+        "district_name" => text(&iso.district_name),
+        "country_sub_division" => text(&iso.country_sub_division),
+        "country" => text(&iso.country),
+        "latitude" => iso
+            .geolocation
+            .as_ref()
+            .map_or(Value::Null, |g| Value::from(g.latitude)),
+        "longitude" => iso
+            .geolocation
+            .as_ref()
+            .map_or(Value::Null, |g| Value::from(g.longitude)),
+        "ban_id" => iso
+            .geolocation
+            .as_ref()
+            .map_or(Value::Null, |g| Value::from(g.ban_id.clone())),
+        "source_references" => Value::from(iso.source_references.clone()),
+        _ => Value::Null,
+    }
+}
+
+fn french_value(kind: AddressKind, french: &FrenchAddress, column: &str) -> Value {
+    let line = match column {
+        "id" => return Value::from(french.id.clone()),
+        "kind" => return kind_value(kind),
+        "line1" => &french.line1,
+        "line2" => &french.line2,
+        "line3" => &french.line3,
+        "line4" => &french.line4,
+        "line5" => &french.line5,
+        "line6" => &french.line6,
+        "line7" => &french.line7,
+        _ => return Value::Null,
+    };
+    line.clone().map_or(Value::Null, Value::String)
+}
+
+/// Same spelling as the CSV import and the CLI `--kind`.
+fn kind_value(kind: AddressKind) -> Value {
+    Value::from(match kind {
+        AddressKind::Company => "company",
+        AddressKind::Particular => "particular",
+    })
+}
+
+pub(crate) fn csv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(csv_cell).collect::<Vec<_>>().join("|"),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::GeoLocation;
+
+    fn address() -> ISO20022Address {
+        ISO20022Address {
+            id: "1".to_string(),
+            recipient_name: Some("DURAND SA".to_string()),
+            kind: AddressKind::Company,
+            street_name: Some("RUE DES FLEURS".to_string()),
+            building_number: Some("22BIS".to_string()),
+            post_code: Some("33500".to_string()),
+            town_name: Some("LIBOURNE".to_string()),
+            country: Some("FR".to_string()),
+            geolocation: Some(GeoLocation {
+                latitude: 44.915,
+                longitude: -0.244,
+                ban_id: "33243_1234_00022_bis".to_string(),
+            }),
+            source_references: vec!["STMT-1/E-1".to_string(), "STMT-2/E-4".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_export_csv_selected_columns() {
+        let options = ExportOptions::new(
+            ExportFormat::Csv,
+            ExportLayout::Iso,
+            Some("id, town_name,latitude,source_references,department"),
+        )
+        .unwrap();
+        let mut out = Vec::new();
+
+        let count = export_addresses(vec![address()], &mut out, &options).unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "id;town_name;latitude;source_references;department\n\
+             1;LIBOURNE;44.915;STMT-1/E-1|STMT-2/E-4;\n"
+        );
+    }
+
+    #[test]
+    fn test_export_ndjson_french_layout() {
+        let options = ExportOptions::new(ExportFormat::Ndjson, ExportLayout::French, None).unwrap();
+        let mut out = Vec::new();
+
+        export_addresses(vec![address(), address()], &mut out, &options).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(
+            lines[0].starts_with(r#"{"id":"1","kind":"company","line1":"DURAND SA","line2":null"#)
+        );
+        let object: Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(object["line4"], "22BIS RUE DES FLEURS");
+        assert_eq!(object["line6"], "33500 LIBOURNE");
+    }
+
+    #[test]
+    fn test_export_unknown_column() {
+        assert!(
+            ExportOptions::new(
+                ExportFormat::Csv,
+                ExportLayout::French,
+                Some("id,town_name")
+            )
+            .is_err()
+        );
+    }
+}
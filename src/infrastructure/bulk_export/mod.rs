@@ -0,0 +1 @@
+pub mod address_exporter;
@@ -0,0 +1,235 @@
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::domain::postal_reference::PostalReference;
+use crate::infrastructure::bulk_import::csv_importer::{RejectedRow, convert_lines};
+use serde_json::{Map, Value};
+use std::io::Read;
+use std::sync::Arc;
+
+/// Reads French addresses from a stream of JSON objects (NDJSON, or a single
+/// object over several lines) with `id`, `kind` and `line1` to `line7`, all
+/// optional, and converts each one like a CSV row.
+pub fn read_french_json<R: Read>(
+    input: R,
+    default_kind: Option<AddressKind>,
+    postal_reference: Option<Arc<PostalReference>>,
+) -> impl Iterator<Item = Result<ISO20022Address, RejectedRow>> {
+    json_objects(input).map(move |(row, object)| {
+        let object = object?;
+        let text = |key: &str| {
+            object
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let lines = std::array::from_fn(|n| text(&format!("line{}", n + 1)));
+        convert_lines(
+            text("id"),
+            lines,
+            text("kind").as_deref(),
+            default_kind,
+            postal_reference.as_deref(),
+        )
+        .map_err(|error| RejectedRow {
+            row,
+            fields: vec![Value::Object(object.clone()).to_string()],
+            error,
+        })
+    })
+}
+
+/// Reads ISO 20022 addresses from a stream of JSON objects, as stored or as
+/// written by the NDJSON export (`kind` in either case, geolocation as an
+/// object or as `latitude`, `longitude` and `ban_id`). A missing id is left empty.
+pub fn read_iso_json<R: Read>(
+    input: R,
+) -> impl Iterator<Item = Result<ISO20022Address, RejectedRow>> {
+    json_objects(input).map(|(row, object)| iso_from_object(row, object?))
+}
+
+/// Reads ISO 20022 addresses from CSV with the export's ISO column names; other
+/// columns are ignored and `source_references` are separated by `|`.
+pub fn read_iso_csv<R: Read>(
+    input: R,
+    delimiter: u8,
+) -> Result<impl Iterator<Item = Result<ISO20022Address, RejectedRow>>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(input);
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+
+    Ok(reader
+        .into_records()
+        .enumerate()
+        .map(move |(index, record)| {
+            let row = index + 1;
+            let record = record.map_err(|e| RejectedRow {
+                row,
+                fields: Vec::new(),
+                error: e.to_string(),
+            })?;
+            let object: Map<String, Value> = headers
+                .iter()
+                .zip(record.iter())
+                .filter(|(_, cell)| !cell.trim().is_empty())
+                .map(|(header, cell)| {
+                    let header = header.trim().to_lowercase();
+                    let value = match header.as_str() {
+                        "source_references" => {
+                            Value::from(cell.split('|').map(str::trim).collect::<Vec<_>>())
+                        }
+                        "latitude" | "longitude" => cell
+                            .trim()
+                            .parse::<f64>()
+                            .map_or_else(|_| Value::from(cell), Value::from),
+                        _ => Value::from(cell.trim()),
+                    };
+                    (header, value)
+                })
+                .collect();
+            iso_from_object(row, object).map_err(|rejected| RejectedRow {
+                fields: record.iter().map(str::to_string).collect(),
+                ..rejected
+            })
+        }))
+}
+
+fn iso_from_object(row: usize, object: Map<String, Value>) -> Result<ISO20022Address, RejectedRow> {
+    let original = Value::Object(object.clone()).to_string();
+    iso_from_json(object).map_err(|error| RejectedRow {
+        row,
+        fields: vec![original],
+        error,
+    })
+}
+
+/// Reads one ISO 20022 address from a JSON object, with the leniency of
+/// `read_iso_json`.
+pub fn iso_from_json(mut object: Map<String, Value>) -> Result<ISO20022Address, String> {
+    object.retain(|_, value| !value.is_null());
+    object.entry("id").or_insert_with(|| Value::from(""));
+    if let Some(Value::String(kind)) = object.get_mut("kind") {
+        *kind = match kind.trim().to_lowercase().as_str() {
+            "company" => "Company".to_string(),
+            "particular" => "Particular".to_string(),
+            _ => kind.clone(),
+        };
+    }
+    if !object.contains_key("geolocation") && object.contains_key("latitude") {
+        let geolocation = serde_json::json!({
+            "latitude": object.remove("latitude"),
+            "longitude": object.remove("longitude"),
+            "ban_id": object.remove("ban_id").unwrap_or_else(|| Value::from("")),
+        });
+        object.insert("geolocation".to_string(), geolocation);
+    }
+    serde_json::from_value(Value::Object(object)).map_err(|e| e.to_string())
+}
+
+/// Numbered JSON objects of the input; reading stops at the first syntax error.
+fn json_objects<R: Read>(
+    input: R,
+) -> impl Iterator<Item = (usize, Result<Map<String, Value>, RejectedRow>)> {
+    let mut failed = false;
+    serde_json::Deserializer::from_reader(input)
+        .into_iter::<Value>()
+        .enumerate()
+        .map_while(move |(index, value)| {
+            if failed {
+                return None;
+            }
+            let row = index + 1;
+            let rejected = |error: String| RejectedRow {
+                row,
+                fields: Vec::new(),
+                error,
+            };
+            let object = match value {
+                Ok(Value::Object(object)) => Ok(object),
+                Ok(other) => Err(rejected(format!("Expected a JSON object, got {}", other))),
+                Err(e) => {
+                    failed = true;
+                    Err(rejected(e.to_string()))
+                }
+            };
+            Some((row, object))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::bulk_export::address_exporter::{
+        ExportFormat, ExportLayout, ExportOptions, export_addresses,
+    };
+
+    #[test]
+    fn test_read_french_json() {
+        let input = r#"{"line1": "DURAND SA", "line4": "22BIS RUE DES FLEURS", "line6": "33500 LIBOURNE", "line7": "FRANCE"}
+{"id": "7", "kind": "particular", "line1": "Jean Dupont", "line4": "15 RUE DES LILAS", "line6": "LYON", "line7": "FRANCE"}
+{
+  "id": "8",
+  "line1": "Marie Martin",
+  "line4": "3 AVENUE FOCH",
+  "line6": "75016 PARIS",
+  "line7": "FRANCE"
+}
+[1, 2]
+{"line1": "#;
+
+        let results: Vec<_> = read_french_json(input.as_bytes(), None, None).collect();
+
+        assert_eq!(results.len(), 5);
+        let first = results[0].as_ref().unwrap();
+        assert_eq!(first.kind, AddressKind::Company);
+        assert!(!first.id.is_empty());
+        assert_eq!(results[1].as_ref().unwrap_err().row, 2);
+        let third = results[2].as_ref().unwrap();
+        assert_eq!(
+            (third.id.as_str(), third.kind),
+            ("8", AddressKind::Particular)
+        );
+        assert!(
+            results[3]
+                .as_ref()
+                .unwrap_err()
+                .error
+                .starts_with("Expected a JSON object")
+        );
+        assert_eq!(results[4].as_ref().unwrap_err().row, 5);
+    }
+
+    #[test]
+    fn test_read_iso_back_from_export() {
+        let iso = read_french_json(
+            r#"{"id": "1", "line1": "DURAND SA", "line4": "22BIS RUE DES FLEURS", "line6": "33500 LIBOURNE", "line7": "FRANCE"}"#
+                .as_bytes(),
+            None,
+            None,
+        )
+        .next()
+        .unwrap()
+        .unwrap();
+
+        for format in [ExportFormat::Csv, ExportFormat::Ndjson] {
+            let options = ExportOptions::new(format, ExportLayout::Iso, None).unwrap();
+            let mut out = Vec::new();
+            export_addresses(vec![iso.clone()], &mut out, &options).unwrap();
+
+            let read: Vec<_> = match format {
+                ExportFormat::Csv => read_iso_csv(out.as_slice(), b';').unwrap().collect(),
+                ExportFormat::Ndjson => read_iso_json(out.as_slice()).collect(),
+            };
+
+            let back = read[0].as_ref().unwrap();
+            assert_eq!(back.id, "1");
+            assert_eq!(back.kind, AddressKind::Company);
+            assert_eq!(back.building_number, iso.building_number);
+            assert_eq!(back.street_name, iso.street_name);
+            assert_eq!(back.post_code, iso.post_code);
+            assert_eq!(back.department, None);
+        }
+    }
+}
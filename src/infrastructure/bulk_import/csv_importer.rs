@@ -0,0 +1,372 @@
+use crate::domain::conversion::to_checked_iso;
+use crate::domain::formats::FrenchFormat;
+use crate::domain::models::{AddressKind, FrenchAddressBuilder, ISO20022Address};
+use crate::domain::postal_reference::PostalReference;
+use crate::domain::usecases::detect_kind;
+use encoding_rs::Encoding;
+use encoding_rs_io::{DecodeReaderBytes, DecodeReaderBytesBuilder};
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Valid rows are handed to the store by batches of this size.
+pub const IMPORT_BATCH_SIZE: usize = 5_000;
+
+/// How to read a CSV file of French addresses.
+#[derive(Debug, Clone)]
+pub struct CsvImportOptions {
+    pub delimiter: u8,
+    pub encoding: &'static Encoding,
+    /// Header of the column feeding each of the seven lines.
+    pub line_columns: [String; 7],
+    /// Column holding "company" or "particular"; empty cells fall back to `default_kind`.
+    pub kind_column: Option<String>,
+    /// Kind of the rows without a kind column value; detected from the lines when `None`.
+    pub default_kind: Option<AddressKind>,
+    /// Reference line 6 is checked against, when loaded.
+    pub postal_reference: Option<Arc<PostalReference>>,
+}
+
+impl Default for CsvImportOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b';',
+            encoding: encoding_rs::UTF_8,
+            line_columns: std::array::from_fn(|i| format!("line{}", i + 1)),
+            kind_column: Some("kind".to_string()),
+            default_kind: None,
+            postal_reference: None,
+        }
+    }
+}
+
+impl CsvImportOptions {
+    /// Reads a delimiter given as one character, or "tab".
+    pub fn with_delimiter(mut self, delimiter: &str) -> Result<Self, String> {
+        self.delimiter = match delimiter {
+            "tab" | "\\t" | "\t" => b'\t',
+            d if d.len() == 1 => d.as_bytes()[0],
+            other => {
+                return Err(format!(
+                    "Invalid delimiter {:?}, expected one character",
+                    other
+                ));
+            }
+        };
+        Ok(self)
+    }
+
+    /// Reads a WHATWG encoding label ("utf-8", "windows-1252", "latin1"...).
+    pub fn with_encoding(mut self, label: &str) -> Result<Self, String> {
+        self.encoding = Encoding::for_label(label.trim().as_bytes())
+            .ok_or_else(|| format!("Unknown encoding {}", label))?;
+        Ok(self)
+    }
+
+    /// Maps a line to a column header, from a "line4=Adresse" specification.
+    pub fn with_mapping(mut self, spec: &str) -> Result<Self, String> {
+        let (line, header) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid mapping {}, expected lineN=Header", spec))?;
+        let index = line
+            .trim()
+            .strip_prefix("line")
+            .and_then(|n| n.parse::<usize>().ok())
+            .filter(|n| (1..=7).contains(n))
+            .ok_or_else(|| format!("Invalid line {} in mapping, expected line1 to line7", line))?;
+        self.line_columns[index - 1] = header.trim().to_string();
+        Ok(self)
+    }
+
+    /// "company", "particular", or "auto" for detection.
+    pub fn with_default_kind(mut self, kind: &str) -> Result<Self, String> {
+        self.default_kind = match kind.trim().to_lowercase().as_str() {
+            "auto" => None,
+            other => Some(parse_kind(other)?),
+        };
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CsvImportReport {
+    pub rows: usize,
+    pub imported: usize,
+    pub rejected: usize,
+}
+
+/// A row that could not be turned into an address.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedRow {
+    /// Position of the row after the header, from 1.
+    pub row: usize,
+    /// Original columns; empty when the row itself could not be read.
+    pub fields: Vec<String>,
+    pub error: String,
+}
+
+/// Reads French addresses from CSV one row at a time, validating each with
+/// `FrenchAddressBuilder` and converting it with `convert_to_iso`. The
+/// input is decoded from `options.encoding`, a byte order mark taking
+/// precedence. An `id` column, when present and filled, gives the address id;
+/// a new UUID is used otherwise.
+pub struct CsvAddressReader<R: Read> {
+    records: csv::StringRecordsIntoIter<DecodeReaderBytes<R, Vec<u8>>>,
+    headers: csv::StringRecord,
+    line_indexes: Vec<Option<usize>>,
+    kind_index: Option<usize>,
+    id_index: Option<usize>,
+    default_kind: Option<AddressKind>,
+    postal_reference: Option<Arc<PostalReference>>,
+    row: usize,
+}
+
+impl<R: Read> CsvAddressReader<R> {
+    pub fn new(input: R, options: &CsvImportOptions) -> Result<Self, String> {
+        let decoded = DecodeReaderBytesBuilder::new()
+            .encoding(Some(options.encoding))
+            .build(input);
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(options.delimiter)
+            .flexible(true)
+            .from_reader(decoded);
+        let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+        let column = |name: &str| {
+            headers
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+        };
+
+        let line_indexes: Vec<Option<usize>> = options
+            .line_columns
+            .iter()
+            .map(|name| column(name))
+            .collect();
+        if line_indexes.iter().all(Option::is_none) {
+            return Err(format!(
+                "None of the columns {} found in the header",
+                options.line_columns.join(", ")
+            ));
+        }
+        let kind_index = options.kind_column.as_deref().and_then(column);
+        let id_index = column("id");
+
+        Ok(Self {
+            records: reader.into_records(),
+            headers,
+            line_indexes,
+            kind_index,
+            id_index,
+            default_kind: options.default_kind,
+            postal_reference: options.postal_reference.clone(),
+            row: 0,
+        })
+    }
+
+    pub fn headers(&self) -> &csv::StringRecord {
+        &self.headers
+    }
+
+    fn convert(&self, record: &csv::StringRecord) -> Result<ISO20022Address, String> {
+        let value = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let lines = std::array::from_fn(|n| value(self.line_indexes[n]));
+        convert_lines(
+            value(self.id_index),
+            lines,
+            value(self.kind_index).as_deref(),
+            self.default_kind,
+            self.postal_reference.as_deref(),
+        )
+    }
+}
+
+impl<R: Read> Iterator for CsvAddressReader<R> {
+    type Item = Result<ISO20022Address, RejectedRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.records.next()?;
+        self.row += 1;
+        let row = self.row;
+        Some(match record {
+            Ok(record) => self.convert(&record).map_err(|error| RejectedRow {
+                row,
+                fields: record.iter().map(str::to_string).collect(),
+                error,
+            }),
+            Err(e) => Err(RejectedRow {
+                row,
+                fields: Vec::new(),
+                error: e.to_string(),
+            }),
+        })
+    }
+}
+
+/// Converts the seven lines of a French address, `kind` ("company",
+/// "particular") taking precedence over `default_kind`, itself over detection.
+/// Line 6 is checked against `reference` when given.
+pub(crate) fn convert_lines(
+    id: Option<String>,
+    lines: [Option<String>; 7],
+    kind: Option<&str>,
+    default_kind: Option<AddressKind>,
+    reference: Option<&PostalReference>,
+) -> Result<ISO20022Address, String> {
+    let [line1, line2, line3, line4, line5, line6, line7] = lines;
+    let french = FrenchAddressBuilder::new()
+        .id(id.unwrap_or_else(|| Uuid::new_v4().to_string()))
+        .line1(line1)
+        .line2(line2)
+        .line3(line3)
+        .line4(line4)
+        .line5(line5)
+        .line6(line6)
+        .line7(line7)
+        .build()?;
+
+    let kind = match kind {
+        Some(kind) => parse_kind(kind)?,
+        None => default_kind.unwrap_or_else(|| detect_kind(&french)),
+    };
+    to_checked_iso(french.into(), kind, &FrenchFormat, reference)
+}
+
+/// Reads French addresses from CSV with `CsvAddressReader`. Valid rows are
+/// passed to `store` by batches; the others are written to `rejects` as CSV
+/// (row number after the header, original columns, error).
+pub fn import_csv<R: Read, W: Write>(
+    input: R,
+    rejects: W,
+    options: &CsvImportOptions,
+    mut store: impl FnMut(Vec<ISO20022Address>) -> Result<(), String>,
+) -> Result<CsvImportReport, String> {
+    let reader = CsvAddressReader::new(input, options)?;
+    let columns = reader.headers().len();
+
+    let mut rejects = csv::WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .from_writer(rejects);
+    let mut header_row = vec!["row".to_string()];
+    header_row.extend(reader.headers().iter().map(str::to_string));
+    header_row.push("error".to_string());
+    rejects
+        .write_record(&header_row)
+        .map_err(|e| e.to_string())?;
+
+    let mut report = CsvImportReport::default();
+    let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+    for result in reader {
+        report.rows += 1;
+        match result {
+            Ok(iso) => {
+                batch.push(iso);
+                if batch.len() == IMPORT_BATCH_SIZE {
+                    report.imported += batch.len();
+                    store(std::mem::take(&mut batch))?;
+                }
+            }
+            Err(rejected) => {
+                report.rejected += 1;
+                let mut row = vec![rejected.row.to_string()];
+                row.extend(rejected.fields);
+                row.resize(columns + 1, String::new());
+                row.push(rejected.error);
+                rejects.write_record(&row).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    if !batch.is_empty() {
+        report.imported += batch.len();
+        store(batch)?;
+    }
+    rejects.flush().map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+pub(crate) fn parse_kind(value: &str) -> Result<AddressKind, String> {
+    match value.trim().to_lowercase().as_str() {
+        "company" | "societe" | "société" | "entreprise" => Ok(AddressKind::Company),
+        "particular" | "particulier" => Ok(AddressKind::Particular),
+        other => Err(format!(
+            "Invalid kind {}, must be 'company' or 'particular'",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_csv_windows_1252() {
+        // "Entrée 4" and "Société" in Windows-1252.
+        let mut input = b"Nom;Batiment;Adresse;CP Ville;Pays;kind\r\n".to_vec();
+        input.extend(b"Jean Dupont;Entr\xe9e 4;15 RUE DES LILAS;69001 LYON;FRANCE;\r\n");
+        input.extend(b"Soci\xe9t\xe9 DURAND SA;;22BIS RUE DES FLEURS;33500 LIBOURNE;FRANCE;\r\n");
+        input.extend(b"SANS CODE;;1 RUE X;LYON;FRANCE;\r\n");
+        input.extend(b"MARTIN;;2 RUE Y;75001 PARIS;FRANCE;robot\r\n");
+        let options = CsvImportOptions::default()
+            .with_encoding("windows-1252")
+            .unwrap()
+            .with_mapping("line1=Nom")
+            .unwrap()
+            .with_mapping("line3=Batiment")
+            .unwrap()
+            .with_mapping("line4=Adresse")
+            .unwrap()
+            .with_mapping("line6=CP Ville")
+            .unwrap()
+            .with_mapping("line7=Pays")
+            .unwrap();
+
+        let mut stored = Vec::new();
+        let mut rejects = Vec::new();
+        let report = import_csv(input.as_slice(), &mut rejects, &options, |batch| {
+            stored.extend(batch);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(
+            report,
+            CsvImportReport {
+                rows: 4,
+                imported: 2,
+                rejected: 2
+            }
+        );
+        assert_eq!(stored[0].kind, AddressKind::Particular);
+        assert_eq!(stored[0].floor, Some("Entrée 4".to_string()));
+        assert_eq!(stored[1].kind, AddressKind::Company);
+        assert_eq!(
+            stored[1].recipient_name,
+            Some("Société DURAND SA".to_string())
+        );
+
+        let rejects = String::from_utf8(rejects).unwrap();
+        let lines: Vec<&str> = rejects.lines().collect();
+        assert_eq!(
+            lines[0],
+            "row;Nom;Batiment;Adresse;CP Ville;Pays;kind;error"
+        );
+        assert!(lines[1].starts_with("3;SANS CODE;"));
+        assert!(lines[1].contains("InvalidPostalCode"));
+        assert!(lines[2].starts_with("4;MARTIN;"));
+        assert!(lines[2].ends_with("Invalid kind robot, must be 'company' or 'particular'"));
+    }
+
+    #[test]
+    fn test_import_csv_requires_mapped_columns() {
+        let input = "a,b\n1,2\n";
+        let options = CsvImportOptions::default().with_delimiter(",").unwrap();
+        assert!(import_csv(input.as_bytes(), Vec::new(), &options, |_| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod address_reader;
+pub mod csv_importer;
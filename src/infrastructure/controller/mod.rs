@@ -1 +1 @@
-pub mod rest_controller;
\ No newline at end of file
+pub mod rest_controller;
@@ -1,13 +1,41 @@
 use actix_web::{
-    delete, get, post, put, web::{self, Json, Path}, HttpResponse,
-    Responder,
+    HttpResponse, Responder,
+    body::{BodySize, MessageBody},
+    delete, get, post, put,
+    web::{self, Bytes, Json, Path},
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::{mpsc, oneshot};
 use uuid::Uuid;
 
-use crate::domain::models::{AddressKind, FrenchAddressBuilder};
-use crate::domain::usecases::{convert_to_french, convert_to_iso};
+use crate::domain::conversion::{ConversionOutcome, iso_to_lines, lines_to_iso, to_checked_iso};
+use crate::domain::formats::{
+    AddressLines, FrenchFormat, NationalAddressFormat, SUPPORTED_COUNTRIES, format_for_country,
+};
+use crate::domain::geojson::to_geojson;
+use crate::domain::models::{AddressKind, FrenchAddress};
+use crate::domain::repository::{RepositoryError, until_error};
+use crate::domain::search::{AddressQuery, DEFAULT_PAGE_SIZE, SortField};
+use crate::domain::usecases::convert_to_french;
 use crate::infrastructure::app_state::AppState;
+use crate::infrastructure::bulk_export::address_exporter::{
+    ExportFormat, ExportLayout, ExportOptions, export_addresses,
+};
+use crate::infrastructure::bulk_import::address_reader::iso_from_json;
+use crate::infrastructure::bulk_import::csv_importer::{
+    CsvImportOptions, CsvImportReport, import_csv,
+};
+use crate::infrastructure::iso20022_xml::conversion_result::conversion_result_xml;
+
+/// Size of the chunks an export is streamed by.
+const EXPORT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest CSV body accepted by `POST /addresses/import`.
+pub const IMPORT_PAYLOAD_LIMIT: usize = 64 * 1024 * 1024;
 
 #[derive(Deserialize)]
 pub struct FrenchAddressPayload {
@@ -19,22 +47,291 @@ pub struct FrenchAddressPayload {
     pub line5: Option<String>,
     pub line6: Option<String>,
     pub line7: Option<String>,
+    /// Postal layout of the lines (FR, BE or DE); French when omitted.
+    #[serde(default)]
+    pub country: Option<String>,
+}
+
+impl FrenchAddressPayload {
+    fn address_lines(&self, id: String) -> AddressLines {
+        AddressLines {
+            id,
+            lines: [
+                self.line1.clone(),
+                self.line2.clone(),
+                self.line3.clone(),
+                self.line4.clone(),
+                self.line5.clone(),
+                self.line6.clone(),
+                self.line7.clone(),
+            ],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CountryQuery {
+    pub country: Option<String>,
+}
+
+/// Response format of `POST /convert/to-iso`: json (default) or xml.
+#[derive(Deserialize)]
+pub struct ResponseFormatQuery {
+    pub format: Option<String>,
+}
+
+/// Options of a CSV import; `map` lists `lineN=Header` pairs separated by commas.
+#[derive(Deserialize)]
+pub struct CsvImportQuery {
+    pub delimiter: Option<String>,
+    pub encoding: Option<String>,
+    pub map: Option<String>,
+    pub kind_column: Option<String>,
+    pub kind: Option<String>,
+}
+
+impl CsvImportQuery {
+    fn options(&self) -> Result<CsvImportOptions, String> {
+        let mut options = CsvImportOptions::default();
+        if let Some(delimiter) = &self.delimiter {
+            options = options.with_delimiter(delimiter)?;
+        }
+        if let Some(encoding) = &self.encoding {
+            options = options.with_encoding(encoding)?;
+        }
+        for mapping in self.map.iter().flat_map(|m| m.split(',')) {
+            options = options.with_mapping(mapping)?;
+        }
+        if let Some(kind_column) = &self.kind_column {
+            options.kind_column = Some(kind_column.clone());
+        }
+        if let Some(kind) = &self.kind {
+            options = options.with_default_kind(kind)?;
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<String>,
+    pub layout: Option<String>,
+    pub columns: Option<String>,
+}
+
+impl ExportQuery {
+    fn options(&self) -> Result<ExportOptions, String> {
+        let format = self
+            .format
+            .as_deref()
+            .unwrap_or("csv")
+            .parse::<ExportFormat>()?;
+        let layout = self
+            .layout
+            .as_deref()
+            .unwrap_or("iso")
+            .parse::<ExportLayout>()?;
+        ExportOptions::new(format, layout, self.columns.as_deref())
+    }
+}
+
+/// Criteria of `GET /addresses` (see `AddressQuery`), sorted by `sort` in
+/// `order` (asc or desc). All the matching addresses are returned unless
+/// `page` or `page_size` is given.
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    pub post_code: Option<String>,
+    pub town: Option<String>,
+    pub country: Option<String>,
+    pub kind: Option<String>,
+    pub recipient_name: Option<String>,
+    pub street_name: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    pub page: Option<usize>,
+    pub page_size: Option<usize>,
+}
+
+impl SearchQuery {
+    fn query(&self) -> Result<AddressQuery, HttpResponse> {
+        let kind = self.kind.as_deref().map(parse_kind).transpose()?;
+        let sort = self
+            .sort
+            .as_deref()
+            .unwrap_or("id")
+            .parse::<SortField>()
+            .map_err(|e| HttpResponse::BadRequest().body(e))?;
+        let descending = match self.order.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => {
+                return Err(HttpResponse::BadRequest()
+                    .body(format!("Invalid order {}, must be 'asc' or 'desc'", other)));
+            }
+        };
+        let page_size = match (self.page, self.page_size) {
+            (None, None) => None,
+            (_, size) => Some(size.unwrap_or(DEFAULT_PAGE_SIZE)),
+        };
+        let query = AddressQuery {
+            post_code: self.post_code.clone(),
+            town: self.town.clone(),
+            country: self.country.clone(),
+            kind,
+            recipient_name: self.recipient_name.clone(),
+            street_name: self.street_name.clone(),
+            sort,
+            descending,
+            page: self.page.unwrap_or(1),
+            page_size,
+        };
+        query
+            .validate()
+            .map_err(|e| HttpResponse::BadRequest().body(e))?;
+        Ok(query)
+    }
+}
+
+/// Response body fed by the export task through a channel.
+struct ExportBody(mpsc::Receiver<io::Result<Bytes>>);
+
+impl MessageBody for ExportBody {
+    type Error = io::Error;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        self.get_mut().0.poll_recv(cx)
+    }
+}
+
+/// Sends what is written to the response by chunks; fails once the client is gone.
+struct ChannelWriter {
+    sender: mpsc::Sender<io::Result<Bytes>>,
+    buffer: Vec<u8>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= EXPORT_CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(std::mem::take(&mut self.buffer));
+        self.sender
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+#[derive(Serialize)]
+struct CsvImportResponse {
+    #[serde(flatten)]
+    report: CsvImportReport,
+    /// Rejected rows as CSV, with the row number and the error.
+    rejects: String,
 }
 
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(list_addresses)
+        .service(export_all_addresses)
         .service(get_address)
         .service(add_address)
         .service(update_address)
         .service(delete_address)
-        .service(convert_address);
+        .service(convert_address)
+        .service(verify_address)
+        .service(geocode_address)
+        .service(export_geojson)
+        .service(
+            web::resource("/addresses/import")
+                .app_data(web::PayloadConfig::new(IMPORT_PAYLOAD_LIMIT))
+                .route(web::post().to(import_addresses)),
+        )
+        .service(convert_lines_to_iso)
+        .service(convert_iso_to_french);
 }
 
+/// The addresses matching the criteria of `SearchQuery`, all of them without
+/// any; `X-Total-Count` tells how many match across all pages.
 #[get("/addresses")]
-async fn list_addresses(data: web::Data<AppState>) -> impl Responder {
+async fn list_addresses(
+    data: web::Data<AppState>,
+    search: web::Query<SearchQuery>,
+) -> impl Responder {
+    let query = match search.query() {
+        Ok(query) => query,
+        Err(e) => return e,
+    };
     let query_service = data.query_service.lock().unwrap();
-    let iso_addresses = query_service.get_all_addresses();
-    HttpResponse::Ok().json(iso_addresses)
+    match query_service.search_addresses(&query) {
+        Ok(page) => HttpResponse::Ok()
+            .insert_header(("X-Total-Count", page.total.to_string()))
+            .json(page.addresses),
+        Err(e) => repository_error(e),
+    }
+}
+
+/// Streams all addresses as CSV or NDJSON (see `ExportQuery`). Registered
+/// before `/addresses/{id}`. The query service is only locked while the
+/// stream is opened; a storage failing midway ends the body with an error,
+/// so that the client sees an incomplete response rather than a short export.
+#[get("/addresses/export")]
+async fn export_all_addresses(
+    data: web::Data<AppState>,
+    query: web::Query<ExportQuery>,
+) -> impl Responder {
+    let options = match query.options() {
+        Ok(options) => options,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let content_type = match options.format {
+        ExportFormat::Csv => "text/csv; charset=utf-8",
+        ExportFormat::Ndjson => "application/x-ndjson",
+    };
+
+    let (sender, receiver) = mpsc::channel(4);
+    let (started, start) = oneshot::channel();
+    let data = data.into_inner();
+    tokio::task::spawn_blocking(move || {
+        let stream = data.query_service.lock().unwrap().stream_all_addresses();
+        let mut stream = stream.peekable();
+        // A storage failing from the start gets an error status rather than an empty export.
+        if let Some(Err(e)) = stream.next_if(Result::is_err) {
+            let _ = started.send(Err(e));
+            return;
+        }
+        let _ = started.send(Ok(()));
+        let writer = ChannelWriter {
+            sender: sender.clone(),
+            buffer: Vec::with_capacity(EXPORT_CHUNK_SIZE),
+        };
+        let mut failure = None;
+        let result = export_addresses(until_error(stream, &mut failure), writer, &options);
+        if let Some(e) = failure.map(|e| e.to_string()).or(result.err()) {
+            let message = format!("Export interrupted: {}", e);
+            let _ = sender.blocking_send(Err(io::Error::other(message)));
+        }
+    });
+
+    match start.await {
+        Ok(Err(e)) => repository_error(e),
+        _ => HttpResponse::Ok()
+            .content_type(content_type)
+            .body(ExportBody(receiver)),
+    }
 }
 
 #[get("/addresses/{id}")]
@@ -43,22 +340,179 @@ async fn get_address(data: web::Data<AppState>, path: Path<String>) -> impl Resp
     let query_service = data.query_service.lock().unwrap();
 
     match query_service.get_address(&address_id) {
-        Some(iso_address) => HttpResponse::Ok().json(iso_address),
-        None => HttpResponse::NotFound().body(format!("Address {} not found", address_id)),
+        Ok(Some(iso_address)) => HttpResponse::Ok().json(iso_address),
+        Ok(None) => HttpResponse::NotFound().body(format!("Address {} not found", address_id)),
+        Err(e) => repository_error(e),
     }
 }
 
 #[get("/addresses/{id}/convert")]
-async fn convert_address(data: web::Data<AppState>, path: Path<String>) -> impl Responder {
+async fn convert_address(
+    data: web::Data<AppState>,
+    path: Path<String>,
+    query: web::Query<CountryQuery>,
+) -> impl Responder {
+    let address_id = path.into_inner();
+    let query_service = data.query_service.lock().unwrap();
+
+    let iso_address = match query_service.get_address(&address_id) {
+        Ok(Some(iso_address)) => iso_address,
+        Ok(None) => {
+            return HttpResponse::NotFound().body(format!("Address {} not found", address_id));
+        }
+        Err(e) => return repository_error(e),
+    };
+
+    let format = match &query.country {
+        Some(country) => match parse_country(country) {
+            Ok(format) => format,
+            Err(e) => return e,
+        },
+        None => iso_address
+            .country
+            .as_deref()
+            .and_then(format_for_country)
+            .unwrap_or(&FrenchFormat),
+    };
+
+    if format.country_code() == "FR" {
+        HttpResponse::Ok().json(convert_to_french(&iso_address))
+    } else {
+        HttpResponse::Ok().json(format.to_lines(&iso_address))
+    }
+}
+
+/// Converts postal lines to ISO 20022 without storing anything. Answers `422`
+/// with the errors when the lines are invalid, the report in both cases.
+#[post("/convert/to-iso")]
+async fn convert_lines_to_iso(
+    data: web::Data<AppState>,
+    query: web::Query<ResponseFormatQuery>,
+    payload: Json<FrenchAddressPayload>,
+) -> impl Responder {
+    let xml = match query.format.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("json") => false,
+        Some("xml") => true,
+        Some(other) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Invalid 'format': {}, must be 'json' or 'xml'",
+                other
+            ));
+        }
+    };
+    let kind = match parse_kind(&payload.kind) {
+        Ok(k) => k,
+        Err(e) => return e,
+    };
+    let format = match parse_country(payload.country.as_deref().unwrap_or("FR")) {
+        Ok(format) => format,
+        Err(e) => return e,
+    };
+
+    let outcome = lines_to_iso(
+        payload.address_lines(String::new()),
+        kind,
+        format,
+        data.postal_reference.as_deref(),
+    );
+    let mut response = if outcome.is_valid() {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::UnprocessableEntity()
+    };
+    if xml {
+        response
+            .content_type("application/xml")
+            .body(conversion_result_xml(&outcome))
+    } else {
+        response.json(outcome)
+    }
+}
+
+/// Lays an ISO 20022 address (JSON, id optional) out as French lines without
+/// storing it; `country` picks the layout as for `GET /addresses/{id}/convert`.
+#[post("/convert/to-french")]
+async fn convert_iso_to_french(
+    query: web::Query<CountryQuery>,
+    payload: Json<Map<String, Value>>,
+) -> impl Responder {
+    let iso = match iso_from_json(payload.into_inner()) {
+        Ok(iso) => iso,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let format = match &query.country {
+        Some(country) => match parse_country(country) {
+            Ok(format) => format,
+            Err(e) => return e,
+        },
+        None => iso
+            .country
+            .as_deref()
+            .and_then(format_for_country)
+            .unwrap_or(&FrenchFormat),
+    };
+
+    let outcome = iso_to_lines(&iso, format);
+    let outcome = ConversionOutcome {
+        address: outcome.address.map(FrenchAddress::from),
+        errors: outcome.errors,
+        report: outcome.report,
+    };
+    if outcome.is_valid() {
+        HttpResponse::Ok().json(outcome)
+    } else {
+        HttpResponse::UnprocessableEntity().json(outcome)
+    }
+}
+
+#[get("/addresses/{id}/verify")]
+async fn verify_address(data: web::Data<AppState>, path: Path<String>) -> impl Responder {
     let address_id = path.into_inner();
+    let Some(verification_service) = &data.verification_service else {
+        return HttpResponse::ServiceUnavailable().body("No BAN index configured");
+    };
     let query_service = data.query_service.lock().unwrap();
 
     match query_service.get_address(&address_id) {
-        Some(iso_address) => {
-            let french = convert_to_french(&iso_address);
-            HttpResponse::Ok().json(french)
+        Ok(Some(iso_address)) => {
+            HttpResponse::Ok().json(verification_service.verify_address(&iso_address))
         }
-        None => HttpResponse::NotFound().body(format!("Address {} not found", address_id)),
+        Ok(None) => HttpResponse::NotFound().body(format!("Address {} not found", address_id)),
+        Err(e) => repository_error(e),
+    }
+}
+
+#[post("/addresses/{id}/geocode")]
+async fn geocode_address(data: web::Data<AppState>, path: Path<String>) -> impl Responder {
+    let address_id = path.into_inner();
+    let query_service = data.query_service.lock().unwrap();
+    let mut command_service = data.command_service.lock().unwrap();
+    if !command_service.has_geocoder() {
+        return HttpResponse::ServiceUnavailable().body("No geocoder configured");
+    }
+
+    let existing_iso = match query_service.get_address(&address_id) {
+        Ok(Some(addr)) => addr,
+        Ok(None) => {
+            return HttpResponse::NotFound().body(format!("Address {} not found", address_id));
+        }
+        Err(e) => return repository_error(e),
+    };
+
+    match command_service.geocode_address(existing_iso) {
+        Ok(geocoded) => HttpResponse::Ok().json(geocoded),
+        Err(e) => repository_error(e),
+    }
+}
+
+#[get("/addresses.geojson")]
+async fn export_geojson(data: web::Data<AppState>) -> impl Responder {
+    let query_service = data.query_service.lock().unwrap();
+    match query_service.get_all_addresses() {
+        Ok(addresses) => HttpResponse::Ok()
+            .content_type("application/geo+json")
+            .json(to_geojson(&addresses)),
+        Err(e) => repository_error(e),
     }
 }
 
@@ -76,35 +530,71 @@ async fn add_address(
         Err(e) => return e, // renvoie un HttpResponse d'erreur
     };
 
-    // Construire l'adresse FrenchAddress
-    let french_address = match FrenchAddressBuilder::new()
-        .id(id.clone())
-        .line1(payload.line1.clone())
-        .line2(payload.line2.clone())
-        .line3(payload.line3.clone())
-        .line4(payload.line4.clone())
-        .line5(payload.line5.clone())
-        .line6(payload.line6.clone())
-        .line7(payload.line7.clone())
-        .build()
-    {
-        Ok(addr) => addr,
-        Err(e) => return HttpResponse::BadRequest().body(e),
+    let format = match parse_country(payload.country.as_deref().unwrap_or("FR")) {
+        Ok(format) => format,
+        Err(e) => return e,
     };
 
-    // Convertir en ISO20022Address
-    let iso_address = match convert_to_iso(&french_address, kind) {
-        iso => iso,
-    };
+    // Valider et convertir en ISO20022Address selon le format postal du pays
+    let reference = data.postal_reference.as_deref();
+    let iso_address =
+        match to_checked_iso(payload.address_lines(id.clone()), kind, format, reference) {
+            Ok(iso) => iso,
+            Err(e) => return HttpResponse::BadRequest().body(e),
+        };
 
     // Enregistrer via le CommandService
     if let Err(e) = command_service.add_address(iso_address) {
-        return HttpResponse::InternalServerError().body(e);
+        return repository_error(e);
     }
 
     HttpResponse::Created().body(format!("Address created with ID {}", id))
 }
 
+/// Imports the CSV body (see `CsvImportQuery`) and returns the counts with the
+/// rejected rows. Registered as `POST /addresses/import` with its own body
+/// limit; the import runs on the blocking thread pool.
+async fn import_addresses(
+    data: web::Data<AppState>,
+    query: web::Query<CsvImportQuery>,
+    body: web::Bytes,
+) -> impl Responder {
+    let options = match query.options() {
+        Ok(options) => CsvImportOptions {
+            postal_reference: data.postal_reference.clone(),
+            ..options
+        },
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let data = data.into_inner();
+    let imported = web::block(move || {
+        let mut command_service = data.command_service.lock().unwrap();
+        let mut rejects = Vec::new();
+        let mut store_error = None;
+        let result = import_csv(body.as_ref(), &mut rejects, &options, |batch| {
+            command_service.add_addresses(batch).map_err(|e| {
+                let message = e.to_string();
+                store_error = Some(e);
+                message
+            })
+        });
+        (result, store_error, rejects)
+    })
+    .await;
+    let (result, store_error, rejects) = match imported {
+        Ok(imported) => imported,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    match (result, store_error) {
+        (Ok(report), _) => HttpResponse::Ok().json(CsvImportResponse {
+            report,
+            rejects: String::from_utf8_lossy(&rejects).into_owned(),
+        }),
+        (Err(_), Some(e)) => repository_error(e),
+        (Err(e), None) => HttpResponse::BadRequest().body(e),
+    }
+}
+
 #[put("/addresses/{id}")]
 async fn update_address(
     data: web::Data<AppState>,
@@ -122,31 +612,33 @@ async fn update_address(
     };
 
     let existing_iso = match query_service.get_address(&id) {
-        Some(addr) => addr,
-        None => return HttpResponse::NotFound().body(format!("Address {} not found", id)),
-    };
-
-    let existing_french = convert_to_french(&existing_iso);
-
-    let updated_french = match FrenchAddressBuilder::new()
-        .id(id.clone())
-        .line1(payload.line1.clone().or(existing_french.line1))
-        .line2(payload.line2.clone().or(existing_french.line2))
-        .line3(payload.line3.clone().or(existing_french.line3))
-        .line4(payload.line4.clone().or(existing_french.line4))
-        .line5(payload.line5.clone().or(existing_french.line5))
-        .line6(payload.line6.clone().or(existing_french.line6))
-        .line7(payload.line7.clone().or(existing_french.line7))
-        .build()
-    {
-        Ok(addr) => addr,
-        Err(e) => return HttpResponse::BadRequest().body(e),
+        Ok(Some(addr)) => addr,
+        Ok(None) => return HttpResponse::NotFound().body(format!("Address {} not found", id)),
+        Err(e) => return repository_error(e),
     };
 
-    let updated_iso = convert_to_iso(&updated_french, kind);
+    let format = match parse_country(payload.country.as_deref().unwrap_or("FR")) {
+        Ok(format) => format,
+        Err(e) => return e,
+    };
+
+    let existing_lines = format.to_lines(&existing_iso);
+    let mut updated_lines = payload.address_lines(id.clone());
+    for (line, existing) in updated_lines.lines.iter_mut().zip(existing_lines.lines) {
+        if line.is_none() {
+            *line = existing;
+        }
+    }
+
+    let reference = data.postal_reference.as_deref();
+    let mut updated_iso = match to_checked_iso(updated_lines, kind, format, reference) {
+        Ok(iso) => iso,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    updated_iso.keep_stored_data(&existing_iso);
 
     if let Err(e) = command_service.update_address(updated_iso) {
-        return HttpResponse::InternalServerError().body(e);
+        return repository_error(e);
     }
 
     HttpResponse::Ok().body(format!("Address {} updated", id))
@@ -159,10 +651,35 @@ async fn delete_address(data: web::Data<AppState>, path: Path<String>) -> impl R
 
     match command_service.delete_address(&id) {
         Ok(_) => HttpResponse::Ok().body(format!("Address {} deleted", id)),
-        Err(e) => HttpResponse::NotFound().body(e),
+        Err(e) => repository_error(e),
     }
 }
 
+/// 404 for an unknown id, 409 for a write clashing with the stored
+/// addresses, 503 when the storage is unavailable and 500 when its data
+/// cannot be decoded.
+fn repository_error(error: RepositoryError) -> HttpResponse {
+    let mut response = match error {
+        RepositoryError::NotFound(_) => HttpResponse::NotFound(),
+        RepositoryError::AlreadyExists(_) | RepositoryError::Conflict(_) => {
+            HttpResponse::Conflict()
+        }
+        RepositoryError::Storage(_) => HttpResponse::ServiceUnavailable(),
+        RepositoryError::Serialization(_) => HttpResponse::InternalServerError(),
+    };
+    response.body(error.to_string())
+}
+
+fn parse_country(s: &str) -> Result<&'static dyn NationalAddressFormat, HttpResponse> {
+    format_for_country(s).ok_or_else(|| {
+        HttpResponse::BadRequest().body(format!(
+            "Unsupported 'country': {}, must be one of {}",
+            s,
+            SUPPORTED_COUNTRIES.join(", ")
+        ))
+    })
+}
+
 fn parse_kind(s: &str) -> Result<AddressKind, HttpResponse> {
     match s.to_lowercase().as_str() {
         "company" => Ok(AddressKind::Company),
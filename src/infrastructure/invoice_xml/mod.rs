@@ -0,0 +1 @@
+pub mod postal_address;
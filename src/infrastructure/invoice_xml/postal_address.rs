@@ -0,0 +1,326 @@
+use crate::domain::formats::en16931::InvoiceAddress;
+use crate::infrastructure::iso20022_xml::postal_address::{local_name, read_element_events};
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde::Serialize;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+/// XML syntaxes of EN 16931 invoices.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum InvoiceSyntax {
+    /// OASIS UBL 2.1 (`cac:PostalAddress`), as used by Peppol BIS.
+    Ubl,
+    /// UN/CEFACT Cross Industry Invoice (`ram:PostalTradeAddress`), as embedded in Factur-X.
+    Cii,
+}
+
+impl FromStr for InvoiceSyntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "ubl" => Ok(Self::Ubl),
+            "cii" | "factur-x" => Ok(Self::Cii),
+            other => Err(format!(
+                "Unknown invoice syntax {}, expected ubl or cii",
+                other
+            )),
+        }
+    }
+}
+
+impl InvoiceSyntax {
+    fn element(&self) -> &'static str {
+        match self {
+            Self::Ubl => "PostalAddress",
+            Self::Cii => "PostalTradeAddress",
+        }
+    }
+}
+
+/// A postal address found in an invoice, with the party owning it.
+#[derive(Debug, Clone, Serialize)]
+pub struct InvoiceParty {
+    pub syntax: InvoiceSyntax,
+    pub role: String, // AccountingSupplierParty, BuyerTradeParty...
+    pub name: Option<String>,
+    pub address: InvoiceAddress,
+}
+
+/// Reads every `cac:PostalAddress` and `ram:PostalTradeAddress` of a UBL or CII
+/// invoice (or of a bare address fragment). The party name is the UBL
+/// `PartyName/Name` or `PartyLegalEntity/RegistrationName`, or the CII `Name`.
+pub fn read_invoice_addresses<R: BufRead>(input: R) -> Result<Vec<InvoiceParty>, String> {
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    // Local names of the open elements, with the party name found under each.
+    let mut stack: Vec<(String, Option<String>)> = Vec::new();
+    // Parties read so far, with the depth of the element owning their name.
+    let mut parties: Vec<(usize, InvoiceParty)> = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("XML error at {}: {}", reader.buffer_position(), e))?
+            .into_owned();
+        buf.clear();
+
+        match event {
+            Event::Eof => break,
+            Event::Start(start) => {
+                let name = local_name(&start);
+                let syntax = [InvoiceSyntax::Ubl, InvoiceSyntax::Cii]
+                    .into_iter()
+                    .find(|s| s.element() == name);
+                let Some(syntax) = syntax else {
+                    stack.push((name, None));
+                    continue;
+                };
+                let events = read_element_events(&mut reader, start)?;
+                let role = match stack.as_slice() {
+                    [.., (role, _), (party, _)] if party == "Party" => role.clone(),
+                    [.., (role, _)] => role.clone(),
+                    [] => String::new(),
+                };
+                parties.push((
+                    stack.len().saturating_sub(1),
+                    InvoiceParty {
+                        syntax,
+                        role,
+                        name: None,
+                        address: address_from_events(syntax, &events)?,
+                    },
+                ));
+            }
+            Event::End(_) => {
+                if let Some((_, Some(name))) = stack.pop() {
+                    parties
+                        .iter_mut()
+                        .filter(|(depth, party)| *depth == stack.len() && party.name.is_none())
+                        .for_each(|(_, party)| party.name = Some(name.clone()));
+                }
+            }
+            Event::Text(text) => {
+                let owner = match stack.as_slice() {
+                    [.., _, (wrapper, _), (tag, _)]
+                        if (tag == "Name" && wrapper == "PartyName")
+                            || (tag == "RegistrationName" && wrapper == "PartyLegalEntity") =>
+                    {
+                        stack.len().checked_sub(3)
+                    }
+                    [.., (party, _), (tag, _)]
+                        if tag == "Name" && party.ends_with("TradeParty") =>
+                    {
+                        stack.len().checked_sub(2)
+                    }
+                    _ => None,
+                };
+                if let Some(owner) = owner
+                    && stack[owner].1.is_none()
+                {
+                    let value = text.unescape().map_err(|e| e.to_string())?;
+                    stack[owner].1 = Some(value.trim().to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(parties.into_iter().map(|(_, party)| party).collect())
+}
+
+fn address_from_events(
+    syntax: InvoiceSyntax,
+    events: &[Event<'_>],
+) -> Result<InvoiceAddress, String> {
+    let mut address = InvoiceAddress::default();
+    let mut path: Vec<String> = Vec::new();
+
+    for event in events {
+        match event {
+            Event::Start(e) => path.push(local_name(e)),
+            Event::End(_) => {
+                path.pop();
+            }
+            Event::Text(text) => {
+                let value = text.unescape().map_err(|e| e.to_string())?;
+                let value = value.trim();
+                if value.is_empty() {
+                    continue;
+                }
+                let tags: Vec<&str> = path.iter().skip(1).map(String::as_str).collect();
+                let field = match (syntax, tags.as_slice()) {
+                    (InvoiceSyntax::Ubl, ["StreetName"]) => &mut address.line_one,
+                    (InvoiceSyntax::Ubl, ["AdditionalStreetName"]) => &mut address.line_two,
+                    (InvoiceSyntax::Ubl, ["AddressLine", "Line"]) => &mut address.line_three,
+                    (InvoiceSyntax::Ubl, ["PostalZone"]) => &mut address.postal_zone,
+                    (InvoiceSyntax::Ubl, ["CountrySubentity"]) => &mut address.country_subentity,
+                    (InvoiceSyntax::Ubl, ["Country", "IdentificationCode"]) => {
+                        &mut address.country_code
+                    }
+                    (InvoiceSyntax::Cii, ["LineOne"]) => &mut address.line_one,
+                    (InvoiceSyntax::Cii, ["LineTwo"]) => &mut address.line_two,
+                    (InvoiceSyntax::Cii, ["LineThree"]) => &mut address.line_three,
+                    (InvoiceSyntax::Cii, ["PostcodeCode"]) => &mut address.postal_zone,
+                    (InvoiceSyntax::Cii, ["CountrySubDivisionName"]) => {
+                        &mut address.country_subentity
+                    }
+                    (InvoiceSyntax::Cii, ["CountryID"]) => &mut address.country_code,
+                    (_, ["CityName"]) => &mut address.city_name,
+                    _ => continue,
+                };
+                *field = Some(value.to_string());
+            }
+            _ => {}
+        }
+    }
+    Ok(address)
+}
+
+/// Path of a child element under the address element, and its value.
+type ChildElement<'a> = (&'static [&'static str], &'a Option<String>);
+
+/// Writes the address as a `cac:PostalAddress` (UBL) or `ram:PostalTradeAddress`
+/// (CII) element, children in schema order. The `cac`, `cbc` and `ram` prefixes
+/// are expected to be declared by the enclosing invoice.
+pub fn write_invoice_address<W: Write>(
+    writer: &mut Writer<W>,
+    syntax: InvoiceSyntax,
+    address: &InvoiceAddress,
+) -> std::io::Result<()> {
+    let (element, children): (&str, Vec<ChildElement>) = match syntax {
+        InvoiceSyntax::Ubl => (
+            "cac:PostalAddress",
+            vec![
+                (&["cbc:StreetName"], &address.line_one),
+                (&["cbc:AdditionalStreetName"], &address.line_two),
+                (&["cbc:CityName"], &address.city_name),
+                (&["cbc:PostalZone"], &address.postal_zone),
+                (&["cbc:CountrySubentity"], &address.country_subentity),
+                (&["cac:AddressLine", "cbc:Line"], &address.line_three),
+                (
+                    &["cac:Country", "cbc:IdentificationCode"],
+                    &address.country_code,
+                ),
+            ],
+        ),
+        InvoiceSyntax::Cii => (
+            "ram:PostalTradeAddress",
+            vec![
+                (&["ram:PostcodeCode"], &address.postal_zone),
+                (&["ram:LineOne"], &address.line_one),
+                (&["ram:LineTwo"], &address.line_two),
+                (&["ram:LineThree"], &address.line_three),
+                (&["ram:CityName"], &address.city_name),
+                (&["ram:CountryID"], &address.country_code),
+                (&["ram:CountrySubDivisionName"], &address.country_subentity),
+            ],
+        ),
+    };
+
+    writer.write_event(Event::Start(BytesStart::new(element)))?;
+    for (tags, value) in children {
+        let Some(value) = value else {
+            continue;
+        };
+        for tag in tags {
+            writer.write_event(Event::Start(BytesStart::new(*tag)))?;
+        }
+        writer.write_event(Event::Text(BytesText::new(value)))?;
+        for tag in tags.iter().rev() {
+            writer.write_event(Event::End(BytesEnd::new(*tag)))?;
+        }
+    }
+    writer.write_event(Event::End(BytesEnd::new(element)))
+}
+
+/// Indented XML of the address, as printed by `invoice-export`.
+pub fn invoice_address_xml(syntax: InvoiceSyntax, address: &InvoiceAddress) -> String {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    write_invoice_address(&mut writer, syntax, address).expect("writing to memory");
+    String::from_utf8(writer.into_inner()).expect("XML written from UTF-8 strings")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UBL_INVOICE: &str = r#"<Invoice xmlns="urn:oasis:names:specification:ubl:schema:xsd:Invoice-2"
+    xmlns:cac="urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2"
+    xmlns:cbc="urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2">
+  <cac:AccountingSupplierParty>
+    <cac:Party>
+      <cac:PartyName><cbc:Name>DURAND SA</cbc:Name></cac:PartyName>
+      <cac:PostalAddress>
+        <cbc:StreetName>22BIS RUE DES FLEURS</cbc:StreetName>
+        <cbc:AdditionalStreetName>BP 40122</cbc:AdditionalStreetName>
+        <cbc:CityName>LIBOURNE</cbc:CityName>
+        <cbc:PostalZone>33500</cbc:PostalZone>
+        <cac:Country><cbc:IdentificationCode>FR</cbc:IdentificationCode></cac:Country>
+      </cac:PostalAddress>
+    </cac:Party>
+  </cac:AccountingSupplierParty>
+  <cac:AccountingCustomerParty>
+    <cac:Party>
+      <cac:PostalAddress>
+        <cbc:StreetName>Unter den Linden 77</cbc:StreetName>
+        <cbc:CityName>Berlin</cbc:CityName>
+        <cbc:PostalZone>10117</cbc:PostalZone>
+        <cac:AddressLine><cbc:Line>Haus 2</cbc:Line></cac:AddressLine>
+        <cac:Country><cbc:IdentificationCode>DE</cbc:IdentificationCode></cac:Country>
+      </cac:PostalAddress>
+      <cac:PartyLegalEntity>
+        <cbc:RegistrationName>Muster GmbH</cbc:RegistrationName>
+      </cac:PartyLegalEntity>
+    </cac:Party>
+  </cac:AccountingCustomerParty>
+</Invoice>"#;
+
+    #[test]
+    fn test_read_ubl_invoice() {
+        let parties = read_invoice_addresses(UBL_INVOICE.as_bytes()).unwrap();
+
+        assert_eq!(parties.len(), 2);
+        assert_eq!(parties[0].syntax, InvoiceSyntax::Ubl);
+        assert_eq!(parties[0].role, "AccountingSupplierParty");
+        assert_eq!(parties[0].name, Some("DURAND SA".to_string()));
+        assert_eq!(parties[0].address.line_two, Some("BP 40122".to_string()));
+        assert_eq!(parties[0].address.country_code, Some("FR".to_string()));
+
+        assert_eq!(parties[1].role, "AccountingCustomerParty");
+        assert_eq!(parties[1].name, Some("Muster GmbH".to_string()));
+        assert_eq!(parties[1].address.line_three, Some("Haus 2".to_string()));
+        assert_eq!(parties[1].address.postal_zone, Some("10117".to_string()));
+    }
+
+    #[test]
+    fn test_cii_round_trip() {
+        let address = InvoiceAddress {
+            line_one: Some("22BIS RUE DES FLEURS".to_string()),
+            city_name: Some("LIBOURNE".to_string()),
+            postal_zone: Some("33500".to_string()),
+            country_code: Some("FR".to_string()),
+            ..Default::default()
+        };
+        let xml = invoice_address_xml(InvoiceSyntax::Cii, &address);
+        assert_eq!(
+            xml,
+            "<ram:PostalTradeAddress>\n  <ram:PostcodeCode>33500</ram:PostcodeCode>\n  \
+             <ram:LineOne>22BIS RUE DES FLEURS</ram:LineOne>\n  \
+             <ram:CityName>LIBOURNE</ram:CityName>\n  \
+             <ram:CountryID>FR</ram:CountryID>\n</ram:PostalTradeAddress>"
+        );
+
+        let document = format!(
+            "<rsm:SellerTradeParty><ram:Name>DURAND SA</ram:Name>{}</rsm:SellerTradeParty>",
+            xml
+        );
+        let parties = read_invoice_addresses(document.as_bytes()).unwrap();
+        assert_eq!(parties.len(), 1);
+        assert_eq!(parties[0].syntax, InvoiceSyntax::Cii);
+        assert_eq!(parties[0].role, "SellerTradeParty");
+        assert_eq!(parties[0].name, Some("DURAND SA".to_string()));
+        assert_eq!(parties[0].address, address);
+    }
+}
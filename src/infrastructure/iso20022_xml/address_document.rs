@@ -0,0 +1,124 @@
+use crate::domain::formats::AddressLines;
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::infrastructure::iso20022_xml::postal_address::write_structured_children;
+use quick_xml::Writer;
+use quick_xml::events::BytesText;
+use std::io::Write;
+
+/// Indented XML of a stored address: an `Address` element with the id and
+/// kind as attributes, holding the party name and the structured `PstlAdr`.
+pub fn address_xml(address: &ISO20022Address) -> String {
+    to_xml(|writer| write_address(writer, address))
+}
+
+/// The addresses as `Address` elements of an `Addresses` root.
+pub fn addresses_xml(addresses: &[ISO20022Address]) -> String {
+    to_xml(|writer| {
+        let element = writer.create_element("Addresses");
+        if addresses.is_empty() {
+            element.write_empty()?;
+            return Ok(());
+        }
+        element.write_inner_content(|writer| {
+            addresses
+                .iter()
+                .try_for_each(|address| write_address(writer, address))
+        })?;
+        Ok(())
+    })
+}
+
+/// The lines of a national layout as the `AdrLine`s of an unstructured `PstlAdr`.
+pub fn address_lines_xml(lines: &AddressLines) -> String {
+    to_xml(|writer| {
+        writer
+            .create_element("PstlAdr")
+            .write_inner_content(|writer| {
+                for line in lines.lines.iter().flatten() {
+                    writer
+                        .create_element("AdrLine")
+                        .write_text_content(BytesText::new(line))?;
+                }
+                Ok(())
+            })?;
+        Ok(())
+    })
+}
+
+fn to_xml(write: impl FnOnce(&mut Writer<Vec<u8>>) -> std::io::Result<()>) -> String {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    write(&mut writer).expect("writing to memory");
+    String::from_utf8(writer.into_inner()).expect("XML written from UTF-8 strings")
+}
+
+fn write_address<W: Write>(
+    writer: &mut Writer<W>,
+    address: &ISO20022Address,
+) -> std::io::Result<()> {
+    let kind = match address.kind {
+        AddressKind::Company => "company",
+        AddressKind::Particular => "particular",
+    };
+    writer
+        .create_element("Address")
+        .with_attribute(("id", address.id.as_str()))
+        .with_attribute(("kind", kind))
+        .write_inner_content(|writer| {
+            if let Some(name) = &address.recipient_name {
+                writer
+                    .create_element("Nm")
+                    .write_text_content(BytesText::new(name))?;
+            }
+            writer
+                .create_element("PstlAdr")
+                .write_inner_content(|writer| {
+                    write_structured_children(writer, None, address, "")
+                })?;
+            Ok(())
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addresses_xml() {
+        let address = ISO20022Address {
+            id: "1".to_string(),
+            recipient_name: Some("DURAND & FILS".to_string()),
+            kind: AddressKind::Company,
+            street_name: Some("RUE DES FLEURS".to_string()),
+            building_number: Some("22BIS".to_string()),
+            post_code: Some("33500".to_string()),
+            town_name: Some("LIBOURNE".to_string()),
+            country: Some("FR".to_string()),
+            ..Default::default()
+        };
+
+        let xml = addresses_xml(&[address.clone(), address]);
+
+        assert!(xml.starts_with("<Addresses>\n  <Address id=\"1\" kind=\"company\">"));
+        assert_eq!(xml.matches("<Nm>DURAND &amp; FILS</Nm>").count(), 2);
+        assert!(xml.contains("      <BldgNb>22BIS</BldgNb>\n"));
+        assert_eq!(addresses_xml(&[]), "<Addresses/>");
+
+        let lines = AddressLines {
+            id: "1".to_string(),
+            lines: [
+                Some("DURAND SA".to_string()),
+                None,
+                None,
+                Some("22BIS RUE DES FLEURS".to_string()),
+                None,
+                Some("33500 LIBOURNE".to_string()),
+                None,
+            ],
+        };
+        assert_eq!(
+            address_lines_xml(&lines),
+            "<PstlAdr>\n  <AdrLine>DURAND SA</AdrLine>\n  <AdrLine>22BIS RUE DES FLEURS</AdrLine>\n  <AdrLine>33500 LIBOURNE</AdrLine>\n</PstlAdr>"
+        );
+    }
+}
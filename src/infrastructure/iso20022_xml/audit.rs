@@ -0,0 +1,368 @@
+use crate::domain::validation::{
+    AddressStructure, ValidationProfile, validate_iso20022_address, validate_with_profile,
+};
+use crate::infrastructure::iso20022_xml::postal_address::{
+    PostalAddressXml, local_name, party_of, read_element_events,
+};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Message roots (children of `Document`) that can be audited.
+pub const AUDITED_MESSAGES: [(&str, &str); 3] = [
+    ("CstmrCdtTrfInitn", "pain.001"),
+    ("FIToFICstmrCdtTrf", "pacs.008"),
+    ("BkToCstmrStmt", "camt.053"),
+];
+
+/// One party address found in a file.
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressFinding {
+    pub file: String,
+    pub message: String,
+    pub position: usize, // 1-based rank of the PstlAdr in the file
+    pub party: String,
+    pub name: Option<String>,
+    pub structure: AddressStructure,
+    pub iso_error: Option<String>,
+    pub profile_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AuditCounts {
+    pub addresses: usize,
+    pub structured: usize,
+    pub hybrid: usize,
+    pub unstructured: usize,
+    pub empty: usize,
+    pub iso_failures: usize,
+    pub profile_failures: usize,
+}
+
+impl AuditCounts {
+    fn add(&mut self, finding: &AddressFinding) {
+        self.addresses += 1;
+        match finding.structure {
+            AddressStructure::Structured => self.structured += 1,
+            AddressStructure::Hybrid => self.hybrid += 1,
+            AddressStructure::Unstructured => self.unstructured += 1,
+            AddressStructure::Empty => self.empty += 1,
+        }
+        self.iso_failures += usize::from(finding.iso_error.is_some());
+        self.profile_failures += usize::from(finding.profile_error.is_some());
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedFile {
+    pub file: String,
+    pub reason: String,
+}
+
+/// Read-only audit of the party addresses of a set of payment files.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditReport {
+    pub profile: ValidationProfile,
+    pub findings: Vec<AddressFinding>,
+    pub per_file: BTreeMap<String, AuditCounts>,
+    pub per_counterparty: BTreeMap<String, AuditCounts>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+impl AuditReport {
+    fn new(profile: ValidationProfile) -> Self {
+        Self {
+            profile,
+            findings: Vec::new(),
+            per_file: BTreeMap::new(),
+            per_counterparty: BTreeMap::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, finding: AddressFinding) {
+        self.per_file
+            .entry(finding.file.clone())
+            .or_default()
+            .add(&finding);
+        let counterparty = finding
+            .name
+            .clone()
+            .unwrap_or_else(|| "(unnamed)".to_string());
+        self.per_counterparty
+            .entry(counterparty)
+            .or_default()
+            .add(&finding);
+        self.findings.push(finding);
+    }
+
+    /// Writes `addresses.csv`, `files.csv` and `counterparties.csv` into `dir`.
+    pub fn write_csv(&self, dir: &str) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir, e))?;
+        let path = |name: &str| Path::new(dir).join(name);
+
+        let mut addresses = csv::Writer::from_path(path("addresses.csv"))
+            .map_err(|e| format!("Cannot write {}: {}", dir, e))?;
+        addresses
+            .write_record([
+                "file",
+                "message",
+                "position",
+                "party",
+                "name",
+                "structure",
+                "iso_error",
+                "profile_error",
+            ])
+            .map_err(|e| e.to_string())?;
+        for f in &self.findings {
+            addresses
+                .write_record([
+                    f.file.as_str(),
+                    f.message.as_str(),
+                    &f.position.to_string(),
+                    f.party.as_str(),
+                    f.name.as_deref().unwrap_or(""),
+                    &format!("{:?}", f.structure),
+                    f.iso_error.as_deref().unwrap_or(""),
+                    f.profile_error.as_deref().unwrap_or(""),
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+        addresses.flush().map_err(|e| e.to_string())?;
+
+        write_counts_csv(&path("files.csv"), "file", &self.per_file)?;
+        write_counts_csv(
+            &path("counterparties.csv"),
+            "counterparty",
+            &self.per_counterparty,
+        )
+    }
+}
+
+fn write_counts_csv(
+    path: &Path,
+    key: &str,
+    counts: &BTreeMap<String, AuditCounts>,
+) -> Result<(), String> {
+    let mut writer = csv::Writer::from_path(path)
+        .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    writer
+        .write_record([
+            key,
+            "addresses",
+            "structured",
+            "hybrid",
+            "unstructured",
+            "empty",
+            "iso_failures",
+            "profile_failures",
+        ])
+        .map_err(|e| e.to_string())?;
+    for (key, counts) in counts {
+        writer
+            .write_record([
+                key.clone(),
+                counts.addresses.to_string(),
+                counts.structured.to_string(),
+                counts.hybrid.to_string(),
+                counts.unstructured.to_string(),
+                counts.empty.to_string(),
+                counts.iso_failures.to_string(),
+                counts.profile_failures.to_string(),
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+/// Audits every `.xml` file of `dir`. Files that are not pain.001, pacs.008 or
+/// camt.053 documents, or not well-formed, are listed in `skipped`.
+pub fn audit_directory(dir: &str, profile: ValidationProfile) -> Result<AuditReport, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {}", dir, e))?;
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"))
+        })
+        .collect();
+    paths.sort();
+
+    let mut report = AuditReport::new(profile);
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let result = File::open(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| audit_document(BufReader::new(file), &file_name, profile));
+        match result {
+            Ok(findings) => findings.into_iter().for_each(|f| report.add(f)),
+            Err(reason) => report.skipped.push(SkippedFile {
+                file: file_name,
+                reason,
+            }),
+        }
+    }
+    Ok(report)
+}
+
+/// Lists the party addresses of one document without modifying it.
+pub fn audit_document<R: BufRead>(
+    input: R,
+    file_name: &str,
+    profile: ValidationProfile,
+) -> Result<Vec<AddressFinding>, String> {
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut findings = Vec::new();
+    let mut message: Option<&str> = None;
+    let mut stack: Vec<(String, Option<String>)> = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("XML error at {}: {}", reader.buffer_position(), e))?
+            .into_owned();
+        buf.clear();
+
+        match event {
+            Event::Eof => break,
+            Event::Start(start) if local_name(&start) == "PstlAdr" => {
+                let events = read_element_events(&mut reader, start)?;
+                let postal_address = PostalAddressXml::from_events(&events)?;
+                let (party, name) = party_of(&stack);
+                findings.push(AddressFinding {
+                    file: file_name.to_string(),
+                    message: message.unwrap_or_default().to_string(),
+                    position: findings.len() + 1,
+                    party,
+                    name,
+                    structure: postal_address.structure(),
+                    iso_error: validate_iso20022_address(&postal_address.address)
+                        .err()
+                        .map(|e| format!("{:?}", e)),
+                    profile_error: validate_with_profile(
+                        &postal_address.address,
+                        &postal_address.address_lines,
+                        profile,
+                    )
+                    .err()
+                    .map(|e| format!("{:?}", e)),
+                });
+            }
+            Event::Start(start) => {
+                let name = local_name(&start);
+                if stack.len() == 1 {
+                    message = AUDITED_MESSAGES
+                        .iter()
+                        .find(|(root, _)| *root == name)
+                        .map(|(_, message)| *message);
+                    if message.is_none() {
+                        return Err(format!("Unsupported message {}", name));
+                    }
+                }
+                stack.push((name, None));
+            }
+            Event::End(_) => {
+                stack.pop();
+            }
+            Event::Text(text) => {
+                if let [.., (_, parent_name), (current, _)] = stack.as_mut_slice()
+                    && current == "Nm"
+                {
+                    *parent_name = Some(text.unescape().map_err(|e| e.to_string())?.into_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if message.is_none() {
+        return Err("Not an ISO 20022 document".to_string());
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMT_053: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.08">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Ntry>
+        <NtryDtls>
+          <TxDtls>
+            <RltdPties>
+              <Dbtr>
+                <Pty>
+                  <Nm>DURAND SA</Nm>
+                  <PstlAdr>
+                    <AdrLine>22BIS RUE DES FLEURS</AdrLine>
+                    <AdrLine>33500 LIBOURNE</AdrLine>
+                  </PstlAdr>
+                </Pty>
+              </Dbtr>
+              <Cdtr>
+                <Pty>
+                  <Nm>MARTIN</Nm>
+                  <PstlAdr>
+                    <StrtNm>Rue de l'Opéra</StrtNm>
+                    <PstCd>75001</PstCd>
+                    <TwnNm>PARIS</TwnNm>
+                    <Ctry>FR</Ctry>
+                  </PstlAdr>
+                </Pty>
+              </Cdtr>
+            </RltdPties>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>"#;
+
+    #[test]
+    fn test_audit_document_camt_053() {
+        let findings =
+            audit_document(CAMT_053.as_bytes(), "stmt.xml", ValidationProfile::Cbpr).unwrap();
+
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].message, "camt.053");
+        assert_eq!(findings[0].party, "Dbtr");
+        assert_eq!(findings[0].name, Some("DURAND SA".to_string()));
+        assert_eq!(findings[0].structure, AddressStructure::Unstructured);
+        assert_eq!(findings[0].iso_error, None);
+        assert_eq!(
+            findings[0].profile_error,
+            Some("UnstructuredAddress".to_string())
+        );
+        assert_eq!(findings[1].structure, AddressStructure::Structured);
+        assert_eq!(findings[1].profile_error, None);
+    }
+
+    #[test]
+    fn test_audit_counts() {
+        let mut report = AuditReport::new(ValidationProfile::Sepa);
+        audit_document(CAMT_053.as_bytes(), "stmt.xml", ValidationProfile::Sepa)
+            .unwrap()
+            .into_iter()
+            .for_each(|f| report.add(f));
+
+        let counts = &report.per_file["stmt.xml"];
+        assert_eq!(counts.addresses, 2);
+        assert_eq!(counts.structured, 1);
+        assert_eq!(counts.unstructured, 1);
+        assert_eq!(counts.iso_failures, 0);
+        // Unstructured, and "Opéra" is outside the SEPA character set.
+        assert_eq!(counts.profile_failures, 2);
+        assert_eq!(report.per_counterparty["MARTIN"].profile_failures, 1);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::domain::conversion::ConversionOutcome;
+use crate::domain::models::ISO20022Address;
+use crate::infrastructure::iso20022_xml::postal_address::write_structured_children;
+use quick_xml::Writer;
+use quick_xml::events::BytesText;
+use std::io::Write;
+
+/// Indented XML of a conversion to ISO 20022: the party name and `PstlAdr`
+/// when valid, then the errors and the report.
+pub fn conversion_result_xml(outcome: &ConversionOutcome<ISO20022Address>) -> String {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    write_conversion_result(&mut writer, outcome).expect("writing to memory");
+    String::from_utf8(writer.into_inner()).expect("XML written from UTF-8 strings")
+}
+
+fn write_conversion_result<W: Write>(
+    writer: &mut Writer<W>,
+    outcome: &ConversionOutcome<ISO20022Address>,
+) -> std::io::Result<()> {
+    let valid = if outcome.is_valid() { "true" } else { "false" };
+    writer
+        .create_element("ConversionResult")
+        .with_attribute(("valid", valid))
+        .write_inner_content(|writer| {
+            if let Some(address) = &outcome.address {
+                if let Some(name) = &address.recipient_name {
+                    writer
+                        .create_element("Nm")
+                        .write_text_content(BytesText::new(name))?;
+                }
+                writer
+                    .create_element("PstlAdr")
+                    .write_inner_content(|writer| {
+                        write_structured_children(writer, None, address, "")
+                    })?;
+            }
+            if !outcome.errors.is_empty() {
+                writer
+                    .create_element("Errors")
+                    .write_inner_content(|writer| {
+                        for error in &outcome.errors {
+                            writer
+                                .create_element("Error")
+                                .write_text_content(BytesText::new(error))?;
+                        }
+                        Ok(())
+                    })?;
+            }
+
+            let report = &outcome.report;
+            writer
+                .create_element("Report")
+                .write_inner_content(|writer| {
+                    writer
+                        .create_element("Layout")
+                        .write_text_content(BytesText::new(&report.layout))?;
+                    if let Some(line6) = &report.corrected_line6 {
+                        writer
+                            .create_element("CorrectedLine6")
+                            .write_text_content(BytesText::new(line6))?;
+                    }
+                    for field in &report.filled_fields {
+                        writer
+                            .create_element("FilledField")
+                            .write_text_content(BytesText::new(field))?;
+                    }
+                    for field in &report.lost_fields {
+                        writer
+                            .create_element("LostField")
+                            .write_text_content(BytesText::new(field))?;
+                    }
+                    for check in &report.profiles {
+                        let profile = format!("{:?}", check.profile);
+                        let element = writer
+                            .create_element("Profile")
+                            .with_attribute(("name", profile.as_str()));
+                        match &check.error {
+                            Some(error) => element.write_text_content(BytesText::new(error))?,
+                            None => element.write_empty()?,
+                        };
+                    }
+                    Ok(())
+                })?;
+            Ok(())
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::conversion::lines_to_iso;
+    use crate::domain::formats::{AddressLines, FrenchFormat};
+    use crate::domain::models::AddressKind;
+
+    #[test]
+    fn test_conversion_result_xml() {
+        let lines = AddressLines {
+            id: "1".to_string(),
+            lines: [
+                Some("DURAND & FILS".to_string()),
+                None,
+                None,
+                Some("22BIS RUE DES FLEURS".to_string()),
+                None,
+                Some("33500 LIBOURNE".to_string()),
+                Some("FRANCE".to_string()),
+            ],
+        };
+
+        let xml = conversion_result_xml(&lines_to_iso(
+            lines,
+            AddressKind::Company,
+            &FrenchFormat,
+            None,
+        ));
+
+        assert!(xml.starts_with(r#"<ConversionResult valid="true">"#));
+        assert!(xml.contains("<Nm>DURAND &amp; FILS</Nm>"));
+        assert!(xml.contains("    <BldgNb>22BIS</BldgNb>\n"));
+        assert!(xml.contains(r#"<Profile name="Basic"/>"#));
+        assert!(!xml.contains("<Errors>"));
+    }
+}
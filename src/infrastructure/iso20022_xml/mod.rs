@@ -0,0 +1,6 @@
+pub mod address_document;
+pub mod audit;
+pub mod conversion_result;
+pub mod payment_rewriter;
+pub mod postal_address;
+pub mod statement_parties;
@@ -0,0 +1,244 @@
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::domain::validation::AddressStructure;
+use crate::infrastructure::iso20022_xml::postal_address::{
+    PostalAddressXml, local_name, party_of, read_element_events, write_structured_children,
+};
+use quick_xml::events::{BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde::Serialize;
+use std::io::{BufRead, Write};
+
+/// Message roots (children of `Document`) that can be rewritten.
+pub const SUPPORTED_MESSAGES: [&str; 2] = ["CstmrCdtTrfInitn", "FIToFICstmrCdtTrf"];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum RewriteOutcome {
+    /// French `AdrLine`s replaced by structured elements.
+    Restructured,
+    /// Structured elements only; nothing to do.
+    AlreadyStructured,
+    /// Structured elements and `AdrLine`s; left as is.
+    Hybrid,
+    /// No postal data at all, or only a country.
+    Empty,
+    /// `AdrLine`s that could not be read as a French address; left as is.
+    Untouched { reason: String },
+}
+
+/// What happened to one `PstlAdr` of the file.
+#[derive(Debug, Clone, Serialize)]
+pub struct PartyReport {
+    pub position: usize,      // 1-based rank of the PstlAdr in the document
+    pub party: String,        // Dbtr, Cdtr, UltmtCdtr, CdtrAgt...
+    pub name: Option<String>, // Nm of the party, when given before PstlAdr
+    pub outcome: RewriteOutcome,
+    pub address: Option<ISO20022Address>,
+}
+
+/// Copies a pain.001 or pacs.008 document from `input` to `output`, replacing
+/// every `AdrLine`-only French postal address by its structured form. Everything
+/// else is copied event by event.
+pub fn rewrite_payment_document<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+) -> Result<Vec<PartyReport>, String> {
+    let mut reader = Reader::from_reader(input);
+    let mut writer = Writer::new(output);
+    let mut buf = Vec::new();
+    let mut reports = Vec::new();
+    // Local names of the open elements, with the Nm read directly under each.
+    let mut stack: Vec<(String, Option<String>)> = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("XML error at {}: {}", reader.buffer_position(), e))?
+            .into_owned();
+        buf.clear();
+
+        match event {
+            Event::Eof => break,
+            Event::Start(start) if local_name(&start) == "PstlAdr" => {
+                let events = read_element_events(&mut reader, start)?;
+                let (party, name) = party_of(&stack);
+                let report =
+                    rewrite_postal_address(&mut writer, events, reports.len() + 1, party, name)?;
+                reports.push(report);
+            }
+            Event::Start(start) => {
+                let name = local_name(&start);
+                if stack.len() == 1 && !SUPPORTED_MESSAGES.contains(&name.as_str()) {
+                    return Err(format!(
+                        "Unsupported message {}, expected pain.001 ({}) or pacs.008 ({})",
+                        name, SUPPORTED_MESSAGES[0], SUPPORTED_MESSAGES[1]
+                    ));
+                }
+                stack.push((name, None));
+                write(&mut writer, Event::Start(start))?;
+            }
+            Event::End(end) => {
+                stack.pop();
+                write(&mut writer, Event::End(end))?;
+            }
+            Event::Text(text) => {
+                if let [.., (parent, parent_name), (current, _)] = stack.as_mut_slice()
+                    && current == "Nm"
+                    && parent != "PstlAdr"
+                {
+                    *parent_name = Some(text.unescape().map_err(|e| e.to_string())?.into_owned());
+                }
+                write(&mut writer, Event::Text(text))?;
+            }
+            other => write(&mut writer, other)?,
+        }
+    }
+
+    Ok(reports)
+}
+
+fn rewrite_postal_address<W: Write>(
+    writer: &mut Writer<W>,
+    events: Vec<Event<'static>>,
+    position: usize,
+    party: String,
+    name: Option<String>,
+) -> Result<PartyReport, String> {
+    let postal_address = PostalAddressXml::from_events(&events)?;
+
+    let outcome = match postal_address.structure() {
+        AddressStructure::Structured => RewriteOutcome::AlreadyStructured,
+        AddressStructure::Hybrid => RewriteOutcome::Hybrid,
+        AddressStructure::Empty => RewriteOutcome::Empty,
+        AddressStructure::Unstructured => {
+            match postal_address.to_iso(String::new(), name.clone(), AddressKind::Company) {
+                Ok(iso) => {
+                    write_restructured(writer, &events, &iso)?;
+                    return Ok(PartyReport {
+                        position,
+                        party,
+                        name,
+                        outcome: RewriteOutcome::Restructured,
+                        address: Some(iso),
+                    });
+                }
+                Err(reason) => RewriteOutcome::Untouched { reason },
+            }
+        }
+    };
+
+    for event in events {
+        write(writer, event)?;
+    }
+    Ok(PartyReport {
+        position,
+        party,
+        name,
+        outcome,
+        address: None,
+    })
+}
+
+/// Writes the original `PstlAdr` tags around the structured children, keeping
+/// the namespace prefix and the indentation of the source document.
+fn write_restructured<W: Write>(
+    writer: &mut Writer<W>,
+    events: &[Event<'static>],
+    iso: &ISO20022Address,
+) -> Result<(), String> {
+    let (Some(Event::Start(start)), Some(Event::End(end))) = (events.first(), events.last()) else {
+        return Err("Malformed PstlAdr element".to_string());
+    };
+    let prefix = start
+        .name()
+        .prefix()
+        .map(|p| String::from_utf8_lossy(p.as_ref()).into_owned());
+
+    let whitespace = |event: Option<&Event<'static>>| match event {
+        Some(Event::Text(text)) if text.iter().all(u8::is_ascii_whitespace) => {
+            String::from_utf8_lossy(text).into_owned()
+        }
+        _ => String::new(),
+    };
+    let child_indent = whitespace(events.get(1));
+    let closing_indent = whitespace(events.get(events.len().saturating_sub(2)));
+
+    write(writer, Event::Start(start.clone()))?;
+    write_structured_children(writer, prefix.as_deref(), iso, &child_indent)
+        .map_err(|e| e.to_string())?;
+    if !closing_indent.is_empty() {
+        write(writer, Event::Text(BytesText::from_escaped(closing_indent)))?;
+    }
+    write(writer, Event::End(end.clone()))
+}
+
+fn write<W: Write>(writer: &mut Writer<W>, event: Event<'_>) -> Result<(), String> {
+    writer.write_event(event).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAIN_001: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:pain.001.001.09">
+  <CstmrCdtTrfInitn>
+    <PmtInf>
+      <Dbtr>
+        <Nm>DURAND SA</Nm>
+        <PstlAdr>
+          <Ctry>FR</Ctry>
+          <AdrLine>22BIS RUE DES FLEURS</AdrLine>
+          <AdrLine>33500 LIBOURNE</AdrLine>
+        </PstlAdr>
+      </Dbtr>
+      <CdtTrfTxInf>
+        <Cdtr>
+          <Nm>MARTIN</Nm>
+          <PstlAdr>
+            <StrtNm>Rue de la Paix</StrtNm>
+            <TwnNm>PARIS</TwnNm>
+            <Ctry>FR</Ctry>
+          </PstlAdr>
+        </Cdtr>
+        <UltmtCdtr>
+          <Nm>DUPONT</Nm>
+          <PstlAdr>
+            <AdrLine>QUELQUE PART</AdrLine>
+          </PstlAdr>
+        </UltmtCdtr>
+      </CdtTrfTxInf>
+    </PmtInf>
+  </CstmrCdtTrfInitn>
+</Document>"#;
+
+    #[test]
+    fn test_rewrite_payment_document() {
+        let mut output = Vec::new();
+        let reports = rewrite_payment_document(PAIN_001.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].party, "Dbtr");
+        assert_eq!(reports[0].name, Some("DURAND SA".to_string()));
+        assert_eq!(reports[0].outcome, RewriteOutcome::Restructured);
+        assert_eq!(reports[1].outcome, RewriteOutcome::AlreadyStructured);
+        assert!(matches!(
+            reports[2].outcome,
+            RewriteOutcome::Untouched { .. }
+        ));
+
+        assert!(output.contains(
+            "<PstlAdr>\n          <StrtNm>RUE DES FLEURS</StrtNm>\n          \
+             <BldgNb>22BIS</BldgNb>\n          <PstCd>33500</PstCd>\n          \
+             <TwnNm>LIBOURNE</TwnNm>\n          <Ctry>FR</Ctry>\n        </PstlAdr>"
+        ));
+        assert!(output.contains("<AdrLine>QUELQUE PART</AdrLine>"));
+        assert!(!output.contains("<AdrLine>33500 LIBOURNE</AdrLine>"));
+    }
+
+    #[test]
+    fn test_rewrite_rejects_other_messages() {
+        let camt = r#"<Document><BkToCstmrStmt></BkToCstmrStmt></Document>"#;
+        assert!(rewrite_payment_document(camt.as_bytes(), Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+use crate::domain::formats::french::envelope_from_free_text;
+use crate::domain::formats::{FrenchFormat, NationalAddressFormat};
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::domain::validation::{AddressStructure, validate_iso20022_address};
+use quick_xml::Reader;
+use quick_xml::Writer;
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use std::io::{BufRead, Write};
+
+/// Content of an ISO 20022 `PstlAdr` element: the structured children mapped
+/// onto `ISO20022Address` (id, kind and recipient left empty) and the
+/// unstructured `AdrLine`s.
+#[derive(Debug, Clone, Default)]
+pub struct PostalAddressXml {
+    pub address: ISO20022Address,
+    pub address_lines: Vec<String>,
+}
+
+/// Structured children of `PstlAdr`, in schema order (PostalAddress24).
+pub const STRUCTURED_TAGS: [&str; 14] = [
+    "Dept",
+    "SubDept",
+    "StrtNm",
+    "BldgNb",
+    "BldgNm",
+    "Flr",
+    "PstBx",
+    "Room",
+    "PstCd",
+    "TwnNm",
+    "TwnLctnNm",
+    "DstrctNm",
+    "CtrySubDvsn",
+    "Ctry",
+];
+
+fn field_mut<'a>(iso: &'a mut ISO20022Address, tag: &str) -> Option<&'a mut Option<String>> {
+    Some(match tag {
+        "Dept" => &mut iso.department,
+        "SubDept" => &mut iso.sub_department,
+        "StrtNm" => &mut iso.street_name,
+        "BldgNb" => &mut iso.building_number,
+        "BldgNm" => &mut iso.building_name,
+        "Flr" => &mut iso.floor,
+        "PstBx" => &mut iso.post_box,
+        "Room" => &mut iso.room,
+        "PstCd" => &mut iso.post_code,
+        "TwnNm" => &mut iso.town_name,
+        "TwnLctnNm" => &mut iso.town_location_name,
+        "DstrctNm" => &mut iso.district_name,
+        "CtrySubDvsn" => &mut iso.country_sub_division,
+        "Ctry" => &mut iso.country,
+        _ => return None,
+    })
+}
+
+fn field<'a>(iso: &'a ISO20022Address, tag: &str) -> Option<&'a str> {
+    match tag {
+        "Dept" => iso.department.as_deref(),
+        "SubDept" => iso.sub_department.as_deref(),
+        "StrtNm" => iso.street_name.as_deref(),
+        "BldgNb" => iso.building_number.as_deref(),
+        "BldgNm" => iso.building_name.as_deref(),
+        "Flr" => iso.floor.as_deref(),
+        "PstBx" => iso.post_box.as_deref(),
+        "Room" => iso.room.as_deref(),
+        "PstCd" => iso.post_code.as_deref(),
+        "TwnNm" => iso.town_name.as_deref(),
+        "TwnLctnNm" => iso.town_location_name.as_deref(),
+        "DstrctNm" => iso.district_name.as_deref(),
+        "CtrySubDvsn" => iso.country_sub_division.as_deref(),
+        "Ctry" => iso.country.as_deref(),
+        _ => None,
+    }
+}
+
+impl PostalAddressXml {
+    pub fn structure(&self) -> AddressStructure {
+        AddressStructure::classify(&self.address, &self.address_lines)
+    }
+
+    /// Structured form of the address for the party `name`. `AdrLine`-only
+    /// addresses are read as a French envelope; the `AdrLine`s of a hybrid
+    /// address are dropped.
+    pub fn to_iso(
+        &self,
+        id: String,
+        name: Option<String>,
+        kind: AddressKind,
+    ) -> Result<ISO20022Address, String> {
+        match self.structure() {
+            AddressStructure::Empty => Err("No postal address".to_string()),
+            AddressStructure::Unstructured => {
+                let envelope = envelope_from_free_text(
+                    id,
+                    name,
+                    &self.address_lines,
+                    self.address.country.as_deref(),
+                );
+                FrenchFormat.to_iso(envelope, kind)
+            }
+            AddressStructure::Structured | AddressStructure::Hybrid => {
+                let iso = ISO20022Address {
+                    id,
+                    recipient_name: name,
+                    kind,
+                    ..self.address.clone()
+                };
+                validate_iso20022_address(&iso)
+                    .map_err(|e| format!("Validation error: {:?}", e))?;
+                Ok(iso)
+            }
+        }
+    }
+
+    /// Builds the address from the events of a `PstlAdr` element, as returned
+    /// by `read_element_events`.
+    pub fn from_events(events: &[Event<'_>]) -> Result<Self, String> {
+        let mut postal_address = Self::default();
+        let mut depth = 0;
+        let mut current: Option<String> = None;
+
+        for event in events {
+            match event {
+                Event::Start(e) => {
+                    depth += 1;
+                    if depth == 2 {
+                        current = Some(local_name(e));
+                    }
+                }
+                Event::End(_) => {
+                    if depth == 2 {
+                        current = None;
+                    }
+                    depth -= 1;
+                }
+                Event::Text(text) if depth == 2 => {
+                    let value = text.unescape().map_err(|e| e.to_string())?;
+                    let value = value.trim();
+                    match current.as_deref() {
+                        Some("AdrLine") => postal_address.address_lines.push(value.to_string()),
+                        Some(tag) => {
+                            if let Some(field) = field_mut(&mut postal_address.address, tag) {
+                                *field = Some(value.to_string());
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(postal_address)
+    }
+}
+
+/// Every `PstlAdr` of an XML document whatever its root (a payment message,
+/// the CLI's `Addresses`, a lone `PstlAdr`), with the `Nm` of its party when
+/// read before it.
+pub fn read_postal_addresses<R: BufRead>(
+    input: R,
+) -> Result<Vec<(Option<String>, PostalAddressXml)>, String> {
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut addresses = Vec::new();
+    let mut stack: Vec<(String, Option<String>)> = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("XML error at {}: {}", reader.buffer_position(), e))?
+            .into_owned();
+        buf.clear();
+
+        match event {
+            Event::Eof => break,
+            Event::Start(start) if local_name(&start) == "PstlAdr" => {
+                let events = read_element_events(&mut reader, start)?;
+                let (_, name) = party_of(&stack);
+                addresses.push((name, PostalAddressXml::from_events(&events)?));
+            }
+            Event::Start(start) => stack.push((local_name(&start), None)),
+            Event::End(_) => {
+                stack.pop();
+            }
+            Event::Text(text) => {
+                if let [.., (_, parent_name), (current, _)] = stack.as_mut_slice()
+                    && current == "Nm"
+                {
+                    *parent_name = Some(text.unescape().map_err(|e| e.to_string())?.into_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(addresses)
+}
+
+/// Local name of an element, without its namespace prefix.
+pub fn local_name(start: &BytesStart<'_>) -> String {
+    String::from_utf8_lossy(start.local_name().as_ref()).into_owned()
+}
+
+/// Party owning a `PstlAdr`, from the local names of the open elements (each
+/// with the `Nm` read directly under it): the parent element, the agent for the
+/// `FinInstnId` of an agent, or the related party for the `Pty` wrapper of camt.
+pub fn party_of(stack: &[(String, Option<String>)]) -> (String, Option<String>) {
+    match stack {
+        [.., (owner, _), (wrapper, name)] if wrapper == "FinInstnId" || wrapper == "Pty" => {
+            (owner.clone(), name.clone())
+        }
+        [.., (party, name)] => (party.clone(), name.clone()),
+        [] => (String::new(), None),
+    }
+}
+
+/// Reads the events of the element whose start tag `start` has just been read,
+/// up to and including its end tag.
+pub fn read_element_events<R: BufRead>(
+    reader: &mut Reader<R>,
+    start: BytesStart<'static>,
+) -> Result<Vec<Event<'static>>, String> {
+    let mut events = vec![Event::Start(start)];
+    let mut buf = Vec::new();
+    let mut depth = 1;
+    while depth > 0 {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("XML error at {}: {}", reader.buffer_position(), e))?;
+        match &event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            Event::Eof => return Err("Unexpected end of XML document".to_string()),
+            _ => {}
+        }
+        events.push(event.into_owned());
+        buf.clear();
+    }
+    Ok(events)
+}
+
+/// Writes the structured children of `PstlAdr` for `address`, each preceded
+/// by `indent` and prefixed with the namespace prefix of the parent.
+pub fn write_structured_children<W: Write>(
+    writer: &mut Writer<W>,
+    prefix: Option<&str>,
+    address: &ISO20022Address,
+    indent: &str,
+) -> std::io::Result<()> {
+    for tag in STRUCTURED_TAGS {
+        let Some(value) = field(address, tag) else {
+            continue;
+        };
+        let name = match prefix {
+            Some(prefix) => format!("{}:{}", prefix, tag),
+            None => tag.to_string(),
+        };
+        if !indent.is_empty() {
+            writer.write_event(Event::Text(BytesText::from_escaped(indent)))?;
+        }
+        writer.write_event(Event::Start(BytesStart::new(name.as_str())))?;
+        writer.write_event(Event::Text(BytesText::new(value)))?;
+        writer.write_event(Event::End(BytesEnd::new(name.as_str())))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(xml: &str) -> Vec<Event<'static>> {
+        let mut reader = Reader::from_reader(xml.as_bytes());
+        let mut buf = Vec::new();
+        let Ok(Event::Start(start)) = reader.read_event_into(&mut buf) else {
+            panic!("missing start tag");
+        };
+        let start = start.into_owned();
+        read_element_events(&mut reader, start).unwrap()
+    }
+
+    #[test]
+    fn test_from_events() {
+        let pstl_adr = PostalAddressXml::from_events(&events(
+            "<PstlAdr><StrtNm>Rue des Fleurs</StrtNm><TwnNm>Libourne</TwnNm>\
+             <Ctry>FR</Ctry><AdrLine>BP 40122</AdrLine></PstlAdr>",
+        ))
+        .unwrap();
+
+        assert_eq!(pstl_adr.structure(), AddressStructure::Hybrid);
+        assert_eq!(
+            pstl_adr.address.street_name,
+            Some("Rue des Fleurs".to_string())
+        );
+        assert_eq!(pstl_adr.address.country, Some("FR".to_string()));
+        assert_eq!(pstl_adr.address_lines, vec!["BP 40122".to_string()]);
+    }
+
+    #[test]
+    fn test_read_postal_addresses() {
+        let xml = r#"<Addresses>
+  <Address id="1" kind="company">
+    <Nm>DURAND SA</Nm>
+    <PstlAdr><StrtNm>RUE DES FLEURS</StrtNm><PstCd>33500</PstCd><Ctry>FR</Ctry></PstlAdr>
+  </Address>
+  <PstlAdr><AdrLine>15 RUE DES LILAS</AdrLine><AdrLine>69001 LYON</AdrLine></PstlAdr>
+</Addresses>"#;
+
+        let addresses = read_postal_addresses(xml.as_bytes()).unwrap();
+
+        assert_eq!(addresses.len(), 2);
+        assert_eq!(addresses[0].0.as_deref(), Some("DURAND SA"));
+        assert_eq!(addresses[0].1.address.post_code.as_deref(), Some("33500"));
+        assert_eq!(addresses[1].0, None);
+        assert_eq!(addresses[1].1.address_lines.len(), 2);
+        assert!(read_postal_addresses("<PstlAdr><Ctry>FR</Ctry>".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_write_structured_children() {
+        let iso = ISO20022Address {
+            street_name: Some("Rue d'Alésia & Co".to_string()),
+            post_code: Some("75014".to_string()),
+            country: Some("FR".to_string()),
+            ..Default::default()
+        };
+        let mut writer = Writer::new(Vec::new());
+        write_structured_children(&mut writer, Some("doc"), &iso, "").unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "<doc:StrtNm>Rue d&apos;Alésia &amp; Co</doc:StrtNm>\
+             <doc:PstCd>75014</doc:PstCd><doc:Ctry>FR</doc:Ctry>"
+        );
+    }
+}
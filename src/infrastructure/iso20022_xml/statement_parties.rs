@@ -0,0 +1,285 @@
+use crate::domain::models::{AddressKind, ISO20022Address};
+use crate::infrastructure::iso20022_xml::postal_address::{
+    PostalAddressXml, local_name, read_element_events,
+};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use std::io::BufRead;
+
+/// Statement messages (children of `Document`) whose related parties can be read.
+pub const STATEMENT_MESSAGES: [(&str, &str); 2] = [
+    ("BkToCstmrStmt", "camt.053"),
+    ("BkToCstmrDbtCdtNtfctn", "camt.054"),
+];
+
+const PARTY_ROLES: [&str; 4] = ["Dbtr", "Cdtr", "UltmtDbtr", "UltmtCdtr"];
+
+/// A related party of a statement entry whose address could be read.
+#[derive(Debug, Clone)]
+pub struct StatementParty {
+    pub role: String,            // Dbtr, Cdtr, UltmtDbtr or UltmtCdtr
+    pub entry_reference: String, // "<statement id>/<entry or transaction reference>"
+    pub address: ISO20022Address,
+}
+
+/// A related party that was left out, with the reason.
+#[derive(Debug, Clone)]
+pub struct SkippedParty {
+    pub role: String,
+    pub entry_reference: String,
+    pub name: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+struct PartyState {
+    role: String,
+    name: Option<String>,
+    postal_address: Option<PostalAddressXml>,
+    kind: Option<AddressKind>,
+}
+
+#[derive(Debug, Default)]
+struct References {
+    statement: Option<String>,
+    entry: Option<String>,
+    entry_servicer: Option<String>,
+    transaction_servicer: Option<String>,
+    end_to_end: Option<String>,
+}
+
+impl References {
+    /// Entry reference first, then the servicer and end-to-end references of
+    /// the transaction ("NOTPROVIDED" is ignored).
+    fn source(&self) -> String {
+        let reference = [
+            &self.entry,
+            &self.entry_servicer,
+            &self.transaction_servicer,
+            &self.end_to_end,
+        ]
+        .into_iter()
+        .flatten()
+        .find(|r| !r.eq_ignore_ascii_case("NOTPROVIDED"))
+        .map(String::as_str)
+        .unwrap_or("?");
+        format!("{}/{}", self.statement.as_deref().unwrap_or("?"), reference)
+    }
+}
+
+/// Reads the `RltdPties` (debtor, creditor and ultimate parties) of every
+/// entry of a camt.053 or camt.054 document. The returned addresses have no id;
+/// the kind comes from the party identification (`OrgId` or `PrvtId`) and is
+/// `Company` when the party is not identified.
+pub fn parse_statement_parties<R: BufRead>(
+    input: R,
+) -> Result<(Vec<StatementParty>, Vec<SkippedParty>), String> {
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut references = References::default();
+    let mut party: Option<PartyState> = None;
+    let mut parties = Vec::new();
+    let mut skipped = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("XML error at {}: {}", reader.buffer_position(), e))?
+            .into_owned();
+        buf.clear();
+
+        match event {
+            Event::Eof => break,
+            Event::Start(start) => {
+                let name = local_name(&start);
+                match (stack.last().map(String::as_str), name.as_str()) {
+                    (Some("Document"), message)
+                        if !STATEMENT_MESSAGES.iter().any(|(root, _)| *root == message) =>
+                    {
+                        return Err(format!(
+                            "Unsupported message {}, expected camt.053 or camt.054",
+                            message
+                        ));
+                    }
+                    (_, "Ntry") => {
+                        references = References {
+                            statement: references.statement.take(),
+                            ..Default::default()
+                        };
+                    }
+                    (_, "TxDtls") => {
+                        references.transaction_servicer = None;
+                        references.end_to_end = None;
+                    }
+                    (Some("RltdPties"), role) if PARTY_ROLES.contains(&role) => {
+                        party = Some(PartyState {
+                            role: role.to_string(),
+                            ..Default::default()
+                        });
+                    }
+                    (_, "PstlAdr") if party.is_some() => {
+                        let events = read_element_events(&mut reader, start)?;
+                        if let Some(party) = party.as_mut() {
+                            party.postal_address = Some(PostalAddressXml::from_events(&events)?);
+                        }
+                        continue;
+                    }
+                    (_, "OrgId") => {
+                        if let Some(party) = party.as_mut() {
+                            party.kind = Some(AddressKind::Company);
+                        }
+                    }
+                    (_, "PrvtId") => {
+                        if let Some(party) = party.as_mut() {
+                            party.kind = Some(AddressKind::Particular);
+                        }
+                    }
+                    _ => {}
+                }
+                stack.push(name);
+            }
+            Event::End(_) => {
+                let closed = stack.pop();
+                if let (Some(role), Some(current)) = (closed, party.as_ref())
+                    && role == current.role
+                    && stack.last().is_some_and(|parent| parent == "RltdPties")
+                {
+                    let current = party.take().unwrap_or_default();
+                    let entry_reference = references.source();
+                    let result = match &current.postal_address {
+                        Some(postal_address) => postal_address.to_iso(
+                            String::new(),
+                            current.name.clone(),
+                            current.kind.unwrap_or(AddressKind::Company),
+                        ),
+                        None => Err("No postal address".to_string()),
+                    };
+                    match result {
+                        Ok(mut address) => {
+                            address.source_references = vec![entry_reference.clone()];
+                            parties.push(StatementParty {
+                                role: current.role,
+                                entry_reference,
+                                address,
+                            });
+                        }
+                        Err(reason) => skipped.push(SkippedParty {
+                            role: current.role,
+                            entry_reference,
+                            name: current.name,
+                            reason,
+                        }),
+                    }
+                }
+            }
+            Event::Text(text) => {
+                let value = text
+                    .unescape()
+                    .map_err(|e| e.to_string())?
+                    .trim()
+                    .to_string();
+                let path: Vec<&str> = stack.iter().rev().take(3).map(String::as_str).collect();
+                match path.as_slice() {
+                    ["Id", "Stmt" | "Ntfctn", ..] => references.statement = Some(value),
+                    ["NtryRef", "Ntry", ..] => references.entry = Some(value),
+                    ["AcctSvcrRef", "Ntry", ..] => references.entry_servicer = Some(value),
+                    ["AcctSvcrRef", "Refs", ..] => references.transaction_servicer = Some(value),
+                    ["EndToEndId", "Refs", ..] => references.end_to_end = Some(value),
+                    ["Nm", parent, owner] => {
+                        if let Some(party) = party.as_mut()
+                            && (*parent == party.role || (*parent == "Pty" && *owner == party.role))
+                        {
+                            party.name = Some(value);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if references.statement.is_none() && parties.is_empty() && skipped.is_empty() {
+        return Err("Not a camt.053 or camt.054 document".to_string());
+    }
+    Ok((parties, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMT_054: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.054.001.08">
+  <BkToCstmrDbtCdtNtfctn>
+    <Ntfctn>
+      <Id>NTF-2024-001</Id>
+      <Ntry>
+        <NtryRef>E-42</NtryRef>
+        <NtryDtls>
+          <TxDtls>
+            <Refs><EndToEndId>NOTPROVIDED</EndToEndId></Refs>
+            <RltdPties>
+              <Dbtr>
+                <Pty>
+                  <Nm>DURAND SA</Nm>
+                  <PstlAdr>
+                    <AdrLine>22BIS RUE DES FLEURS</AdrLine>
+                    <AdrLine>33500 LIBOURNE</AdrLine>
+                  </PstlAdr>
+                  <Id><OrgId><AnyBIC>DURAFRPP</AnyBIC></OrgId></Id>
+                </Pty>
+              </Dbtr>
+              <UltmtDbtr>
+                <Pty>
+                  <Nm>Jean Dupont</Nm>
+                  <PstlAdr>
+                    <StrtNm>Rue des Lilas</StrtNm>
+                    <BldgNb>15</BldgNb>
+                    <PstCd>69001</PstCd>
+                    <TwnNm>LYON</TwnNm>
+                    <Ctry>FR</Ctry>
+                  </PstlAdr>
+                  <Id><PrvtId><Othr><Id>123</Id></Othr></PrvtId></Id>
+                </Pty>
+              </UltmtDbtr>
+              <Cdtr>
+                <Pty><Nm>SANS ADRESSE</Nm></Pty>
+              </Cdtr>
+            </RltdPties>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+    </Ntfctn>
+  </BkToCstmrDbtCdtNtfctn>
+</Document>"#;
+
+    #[test]
+    fn test_parse_statement_parties() {
+        let (parties, skipped) = parse_statement_parties(CAMT_054.as_bytes()).unwrap();
+
+        assert_eq!(parties.len(), 2);
+        assert_eq!(parties[0].role, "Dbtr");
+        assert_eq!(parties[0].entry_reference, "NTF-2024-001/E-42");
+        let debtor = &parties[0].address;
+        assert_eq!(debtor.recipient_name, Some("DURAND SA".to_string()));
+        assert_eq!(debtor.kind, AddressKind::Company);
+        assert_eq!(debtor.building_number, Some("22BIS".to_string()));
+        assert_eq!(debtor.post_code, Some("33500".to_string()));
+        assert_eq!(debtor.source_references, vec!["NTF-2024-001/E-42"]);
+
+        let ultimate = &parties[1].address;
+        assert_eq!(ultimate.recipient_name, Some("Jean Dupont".to_string()));
+        assert_eq!(ultimate.kind, AddressKind::Particular);
+        assert_eq!(ultimate.town_name, Some("LYON".to_string()));
+
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].name, Some("SANS ADRESSE".to_string()));
+    }
+
+    #[test]
+    fn test_parse_statement_parties_rejects_payments() {
+        let pain = "<Document><CstmrCdtTrfInitn></CstmrCdtTrfInitn></Document>";
+        assert!(parse_statement_parties(pain.as_bytes()).is_err());
+    }
+}
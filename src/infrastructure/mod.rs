@@ -1,3 +1,8 @@
 pub mod app_state;
+pub mod bulk_export;
+pub mod bulk_import;
 pub mod repository;
-pub mod controller;
\ No newline at end of file
+pub mod controller;
+pub mod invoice_xml;
+pub mod iso20022_xml;
+pub mod reference;
@@ -0,0 +1,149 @@
+use crate::domain::ban::{BanAddress, BanIndex};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Reads the per-département BAN exports (`adresses-33.csv`, `adresses-2A.csv`…)
+/// found in `dir`. When `departements` is empty every export of the directory is read.
+pub fn import_ban_directory(dir: &str, departements: &[String]) -> Result<BanIndex, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {}", dir, e))?;
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            ban_departement(path).is_some_and(|dep| {
+                departements.is_empty() || departements.iter().any(|d| d.eq_ignore_ascii_case(dep))
+            })
+        })
+        .collect();
+    paths.sort();
+
+    if paths.is_empty() {
+        return Err(format!("No BAN export (adresses-XX.csv) found in {}", dir));
+    }
+
+    let mut index = BanIndex::default();
+    for path in paths {
+        let file =
+            File::open(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        index.extend(parse_ban_csv(BufReader::new(file))?);
+    }
+    Ok(index)
+}
+
+fn ban_departement(path: &Path) -> Option<&str> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("adresses-")?
+        .strip_suffix(".csv")
+}
+
+/// Parses a BAN "adresses" CSV export (`;`-separated, with header). Columns are
+/// located by name so that the optional trailing columns may vary between releases.
+pub fn parse_ban_csv(reader: impl BufRead) -> Result<Vec<BanAddress>, String> {
+    let mut lines = reader.lines();
+    let header = match lines.next() {
+        Some(line) => line.map_err(|e| e.to_string())?,
+        None => return Ok(vec![]),
+    };
+    let columns: Vec<&str> = header.split(';').collect();
+    let column = |name: &str| {
+        columns
+            .iter()
+            .position(|c| *c == name)
+            .ok_or_else(|| format!("Missing BAN column '{}'", name))
+    };
+    let id = column("id")?;
+    let numero = column("numero")?;
+    let rep = column("rep")?;
+    let nom_voie = column("nom_voie")?;
+    let code_postal = column("code_postal")?;
+    let code_insee = column("code_insee")?;
+    let nom_commune = column("nom_commune")?;
+    let lon = column("lon")?;
+    let lat = column("lat")?;
+
+    let mut addresses = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(';').collect();
+        let field = |i: usize| fields.get(i).copied().unwrap_or("").trim();
+        let coordinate = |i: usize| {
+            field(i)
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid coordinate on BAN row {}", index + 2))
+        };
+
+        // Street-level entries ("lieux-dits" without number) carry the 99999 placeholder.
+        let house_number = match field(numero) {
+            "" | "99999" => None,
+            n => Some(format!("{}{}", n, field(rep).to_uppercase())),
+        };
+
+        addresses.push(BanAddress {
+            id: field(id).to_string(),
+            house_number,
+            street_name: field(nom_voie).to_string(),
+            post_code: field(code_postal).to_string(),
+            town_name: field(nom_commune).to_string(),
+            insee_code: field(code_insee).to_string(),
+            latitude: coordinate(lat)?,
+            longitude: coordinate(lon)?,
+        });
+    }
+    Ok(addresses)
+}
+
+/// Writes the index as NDJSON, one BAN housenumber per line.
+pub fn save_ban_index(index: &BanIndex, path: &str) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+    let mut writer = BufWriter::new(file);
+    for address in index.addresses() {
+        let line = serde_json::to_string(address).map_err(|e| e.to_string())?;
+        writeln!(writer, "{}", line).map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+pub fn load_ban_index(path: &str) -> Result<BanIndex, String> {
+    let file = File::open(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let mut addresses = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let address: BanAddress = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid BAN index line {}: {}", index + 1, e))?;
+        addresses.push(address);
+    }
+    Ok(BanIndex::new(addresses))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ban_csv() {
+        let data = "id;id_fantoir;numero;rep;nom_voie;code_postal;code_insee;nom_commune;code_insee_ancienne_commune;nom_ancienne_commune;x;y;lon;lat
+33243_1234_00022_bis;33243_1234;22;bis;Rue des Fleurs;33500;33243;Libourne;;;430000;6433000;-0.244;44.915
+33243_1234_99999;33243_1234;99999;;Rue des Fleurs;33500;33243;Libourne;;;430000;6433000;-0.244;44.915
+";
+        let addresses = parse_ban_csv(data.as_bytes()).unwrap();
+
+        assert_eq!(addresses.len(), 2);
+        assert_eq!(addresses[0].house_number, Some("22BIS".to_string()));
+        assert_eq!(addresses[0].street_name, "Rue des Fleurs");
+        assert_eq!(addresses[0].latitude, 44.915);
+        assert_eq!(addresses[1].house_number, None);
+    }
+
+    #[test]
+    fn test_parse_ban_csv_missing_column() {
+        let data = "id;numero;rep\n1;2;";
+        assert!(parse_ban_csv(data.as_bytes()).is_err());
+    }
+}
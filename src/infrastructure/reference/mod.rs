@@ -0,0 +1,2 @@
+pub mod ban_importer;
+pub mod postal_reference_loader;
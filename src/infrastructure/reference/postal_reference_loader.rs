@@ -0,0 +1,13 @@
+use crate::domain::postal_reference::PostalReference;
+use std::fs;
+
+/// Loads the La Poste postal code CSV from disk. Older exports of the dataset are
+/// ISO-8859-1 encoded, so bytes that are not valid UTF-8 are decoded as Latin-1.
+pub fn load_postal_reference(path: &str) -> Result<PostalReference, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let data = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => e.into_bytes().iter().map(|&b| b as char).collect(),
+    };
+    PostalReference::from_csv_str(&data)
+}
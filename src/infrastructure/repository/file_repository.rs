@@ -1,66 +1,682 @@
 use crate::domain::models::ISO20022Address;
-use crate::domain::repository::{AddressRepository, ReadAddressRepository};
+use crate::domain::repository::{
+    AddressRepository, ReadAddressRepository, RepositoryError, check_unique_ids,
+};
+use crate::domain::search::{AddressPage, AddressQuery};
+use serde::{Deserialize, Serialize};
 use serde_json;
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
+type AddressMap = HashMap<String, ISO20022Address>;
+
+/// Entries of the log after which a write folds it into the snapshot.
+pub const DEFAULT_COMPACT_AFTER: usize = 1000;
+
+/// Stores the addresses in a JSON snapshot, a JSON object by id as written by
+/// earlier versions, and a write-ahead log of the changes made since,
+/// `<file>.wal`, one JSON entry per line. The addresses are kept in memory,
+/// shared by the clones of the repository, so that a write appends and syncs
+/// one line instead of rewriting the whole file.
+///
+/// Several processes can share the files (the CLI and the REST server both
+/// default to `addresses.json`):
+/// - reads hold a shared lock on `<file>.lock`, writes an exclusive one over
+///   their whole read-modify-write cycle. Only writes create the lock file:
+///   before the first one there is nothing to wait for, and a read-only
+///   directory can still be read;
+/// - every operation first checks the snapshot and the log on disk: a log grown
+///   by another process is replayed from where this one stopped, a replaced
+///   snapshot or a shrunk log is loaded again;
+/// - once the log holds `compact_after` entries, the snapshot is replaced
+///   atomically (written to `<file>.tmp`, synced, renamed over it, its previous
+///   version kept as `<file>.bak` and the log folded into it as
+///   `<file>.bak.wal`) and the log emptied. Replaying a log again over the
+///   snapshot it was folded into changes nothing, so a crash in between loses
+///   nothing;
+/// - a truncated or damaged snapshot is read from `<file>.bak` and
+///   `<file>.bak.wal` instead, which gives the same addresses, and copied to
+///   `<file>.corrupt` by the next compaction.
+///
+/// Every write acknowledged by `Ok` survives a crash or a damaged snapshot.
+/// A crash in the middle of a write can only leave its log entry cut short:
+/// that write never returned, so the entry is ignored, then overwritten by
+/// the next one.
+///
+/// Damage found on the way, a snapshot read from the backup or a log entry cut
+/// short, is not an error; it is kept for `take_warnings`.
 #[derive(Clone)]
 pub struct FileBasedAddressRepository {
     file_path: String,
+    compact_after: usize,
+    index: Arc<Mutex<Index>>,
+}
+
+/// The addresses in memory, and what they were read from.
+#[derive(Default)]
+struct Index {
+    addresses: AddressMap,
+    loaded: bool,
+    snapshot: Option<FileStamp>,
+    /// The snapshot is damaged and the addresses come from the backup.
+    recovered: bool,
+    /// Bytes of the log replayed, up to the end of its last complete entry.
+    log_len: u64,
+    log_entries: usize,
+    /// Where the log was last found to end with an entry cut short.
+    cut_short_at: Option<u64>,
+    warnings: Vec<String>,
+}
+
+/// Identifies a version of a file, to notice it was replaced or rewritten.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    inode: u64,
+}
+
+impl FileStamp {
+    fn of(metadata: &Metadata) -> Self {
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+        Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            inode,
+        }
+    }
+}
+
+/// A change, as a line of the log.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum LogEntry {
+    Put { address: Box<ISO20022Address> },
+    Delete { id: String },
+}
+
+impl LogEntry {
+    fn apply(self, addresses: &mut AddressMap) {
+        match self {
+            Self::Put { address } => {
+                addresses.insert(address.id.clone(), *address);
+            }
+            Self::Delete { id } => {
+                addresses.remove(&id);
+            }
+        }
+    }
+}
+
+/// Complete entries of a log, and the bytes they take.
+struct LogTail {
+    entries: Vec<LogEntry>,
+    len: u64,
+    /// The log goes on with an entry cut short by a crash.
+    cut_short: bool,
+}
+
+/// Addresses of the snapshot, and why they come from the backup if they do.
+struct Loaded {
+    data: AddressMap,
+    recovered: Option<String>,
 }
 
 impl FileBasedAddressRepository {
     pub fn new(file_path: String) -> Self {
-        Self { file_path }
+        Self {
+            file_path,
+            compact_after: DEFAULT_COMPACT_AFTER,
+            index: Arc::default(),
+        }
     }
 
-    fn load_data(&self) -> HashMap<String, ISO20022Address> {
-        let data = fs::read_to_string(&self.file_path).unwrap_or("{}".to_string());
-        serde_json::from_str(&data).unwrap_or_else(|_| HashMap::new())
+    /// Folds the log into the snapshot once it holds `entries` changes.
+    pub fn with_compact_after(mut self, entries: usize) -> Self {
+        self.compact_after = entries.max(1);
+        self
     }
 
-    fn save_data(&self, data: &HashMap<String, ISO20022Address>) {
-        let json = serde_json::to_string_pretty(data).unwrap();
-        fs::write(&self.file_path, json).expect("File write failed");
+    /// What was found damaged and worked around since the last call, such as
+    /// a snapshot read from the backup, for the caller to report.
+    pub fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut self.index.lock().unwrap().warnings)
     }
-}
 
-impl AddressRepository for FileBasedAddressRepository {
-    fn save(&mut self, address: ISO20022Address) -> Result<(), String> {
-        let mut data = self.load_data();
-        data.insert(address.id.clone(), address);
-        self.save_data(&data);
+    /// Runs `read` on the up to date addresses under a shared lock.
+    fn read<T>(&self, read: impl FnOnce(&AddressMap) -> T) -> Result<T, RepositoryError> {
+        let _lock = self.lock(false)?;
+        let mut index = self.index.lock().unwrap();
+        self.refresh(&mut index)?;
+        Ok(read(&index.addresses))
+    }
+
+    /// Logs the changes `change` makes of the up to date addresses under an
+    /// exclusive lock, unless it fails, and compacts when the log is full or
+    /// the snapshot does not exist yet.
+    fn modify(
+        &self,
+        change: impl FnOnce(&AddressMap) -> Result<Vec<LogEntry>, RepositoryError>,
+    ) -> Result<(), RepositoryError> {
+        let _lock = self.lock(true)?;
+        let mut index = self.index.lock().unwrap();
+        self.refresh(&mut index)?;
+        let entries = change(&index.addresses)?;
+        self.append(&mut index, entries)?;
+        if index.log_entries >= self.compact_after || index.snapshot.is_none() {
+            self.compact(&mut index)?;
+        }
         Ok(())
     }
 
-    fn update(&mut self, address: ISO20022Address) -> Result<(), String> {
-        let mut data = self.load_data();
-        if data.contains_key(&address.id) {
-            data.insert(address.id.clone(), address);
-            self.save_data(&data);
-            Ok(())
+    /// Locks `<file>.lock`, which outlives the renames of the snapshot; the
+    /// lock is released when the returned file is dropped. A shared lock
+    /// opens the file read-only and is skipped when no write created it yet.
+    fn lock(&self, exclusive: bool) -> Result<Option<File>, RepositoryError> {
+        let path = self.companion("lock");
+        let opened = if exclusive {
+            OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)
         } else {
-            Err("Address not found".to_string())
+            File::open(&path)
+        };
+        let lock = match opened {
+            Ok(lock) => lock,
+            Err(e) if !exclusive && e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(self.storage_error(e)),
+        };
+        let locked = if exclusive {
+            lock.lock()
+        } else {
+            lock.lock_shared()
+        };
+        locked.map_err(|e| self.storage_error(e))?;
+        Ok(Some(lock))
+    }
+
+    /// Brings the index up to date with the files, which other processes may
+    /// have changed since it was last read.
+    fn refresh(&self, index: &mut Index) -> Result<(), RepositoryError> {
+        let snapshot = self.stamp(Path::new(&self.file_path))?;
+        let log_len = self.stamp(&self.companion("wal"))?.map_or(0, |s| s.len);
+        if !index.loaded || index.snapshot != snapshot || log_len < index.log_len {
+            let loaded = self.load_data()?;
+            *index = Index {
+                addresses: loaded.data,
+                loaded: true,
+                snapshot,
+                recovered: loaded.recovered.is_some(),
+                log_len: 0,
+                log_entries: 0,
+                cut_short_at: None,
+                warnings: std::mem::take(&mut index.warnings),
+            };
+            index.warnings.extend(loaded.recovered);
         }
+        if log_len > index.log_len {
+            self.replay_log(index)?;
+        }
+        Ok(())
     }
 
-    fn delete(&mut self, address_id: &str) -> Result<(), String> {
-        let mut data = self.load_data();
-        if data.remove(address_id).is_some() {
-            self.save_data(&data);
-            Ok(())
-        } else {
-            Err("Address not found".to_string())
+    fn stamp(&self, path: &Path) -> Result<Option<FileStamp>, RepositoryError> {
+        match fs::metadata(path) {
+            Ok(metadata) => Ok(Some(FileStamp::of(&metadata))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(self.storage_error(e)),
+        }
+    }
+
+    /// Applies the complete entries of the log past `index.log_len`, and
+    /// warns once of an entry cut short after them.
+    fn replay_log(&self, index: &mut Index) -> Result<(), RepositoryError> {
+        let path = self.companion("wal");
+        let tail = self.read_log(&path, index.log_len)?;
+        index.log_len += tail.len;
+        index.log_entries += tail.entries.len();
+        for entry in tail.entries {
+            entry.apply(&mut index.addresses);
+        }
+        if tail.cut_short && index.cut_short_at != Some(index.log_len) {
+            index.cut_short_at = Some(index.log_len);
+            index.warnings.push(format!(
+                "{}: ignored an entry cut short by a crash at byte {}",
+                path.display(),
+                index.log_len
+            ));
+        }
+        Ok(())
+    }
+
+    /// The complete entries of the log at `path` from byte `start`; none when
+    /// it does not exist.
+    fn read_log(&self, path: &Path, start: u64) -> Result<LogTail, RepositoryError> {
+        let mut tail = LogTail {
+            entries: Vec::new(),
+            len: 0,
+            cut_short: false,
+        };
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(tail),
+            Err(e) => return Err(self.storage_error(e)),
+        };
+        file.seek(SeekFrom::Start(start))
+            .map_err(|e| self.storage_error(e))?;
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .map_err(|e| self.storage_error(e))?;
+            if read == 0 || !line.ends_with('\n') {
+                tail.cut_short = read > 0;
+                return Ok(tail);
+            }
+            let entry: LogEntry = serde_json::from_str(&line).map_err(|e| {
+                RepositoryError::Serialization(format!("{}: {}", path.display(), e))
+            })?;
+            tail.entries.push(entry);
+            tail.len += read as u64;
+        }
+    }
+
+    /// Appends the entries to the log, synced, then applies them.
+    fn append(&self, index: &mut Index, entries: Vec<LogEntry>) -> Result<(), RepositoryError> {
+        let mut buffer = Vec::new();
+        for entry in &entries {
+            serde_json::to_writer(&mut buffer, entry)
+                .map_err(|e| RepositoryError::Serialization(e.to_string()))?;
+            buffer.push(b'\n');
         }
+        let created = index.log_len == 0;
+        OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.companion("wal"))
+            .and_then(|mut log| {
+                // Drops an entry cut short by a crash.
+                log.set_len(index.log_len)?;
+                log.seek(SeekFrom::End(0))?;
+                log.write_all(&buffer)?;
+                log.sync_data()
+            })
+            .map_err(|e| self.storage_error(e))?;
+        if created {
+            self.sync_directory(Path::new(&self.file_path))?;
+        }
+        index.log_len += buffer.len() as u64;
+        index.log_entries += entries.len();
+        for entry in entries {
+            entry.apply(&mut index.addresses);
+        }
+        Ok(())
+    }
+
+    /// Replaces the snapshot by the addresses in memory and empties the log.
+    fn compact(&self, index: &mut Index) -> Result<(), RepositoryError> {
+        let mut folded = self.read_bytes(&self.companion("wal"))?;
+        folded.truncate(index.log_len as usize);
+        self.save_data(&index.addresses, index.recovered, &folded)?;
+        OpenOptions::new()
+            .write(true)
+            .open(self.companion("wal"))
+            .and_then(|log| {
+                log.set_len(0)?;
+                log.sync_all()
+            })
+            .map_err(|e| self.storage_error(e))?;
+        index.snapshot = self.stamp(Path::new(&self.file_path))?;
+        index.recovered = false;
+        index.log_len = 0;
+        index.log_entries = 0;
+        index.cut_short_at = None;
+        Ok(())
+    }
+
+    /// The addresses of the snapshot, none when it does not exist yet. A
+    /// damaged one is replaced by the backup brought up to date with its log;
+    /// without a readable backup it is an error, so that a compaction never
+    /// replaces it.
+    fn load_data(&self) -> Result<Loaded, RepositoryError> {
+        match self.read_file(Path::new(&self.file_path)) {
+            Ok(data) => Ok(Loaded {
+                data: data.unwrap_or_default(),
+                recovered: None,
+            }),
+            Err(RepositoryError::Serialization(error)) => {
+                let backup = self.companion("bak");
+                match self.read_file(&backup) {
+                    Ok(Some(mut data)) => {
+                        let log = self.read_log(&self.companion("bak.wal"), 0)?;
+                        for entry in log.entries {
+                            entry.apply(&mut data);
+                        }
+                        Ok(Loaded {
+                            data,
+                            recovered: Some(format!(
+                                "{}, read {} instead",
+                                error,
+                                backup.display()
+                            )),
+                        })
+                    }
+                    _ => Err(RepositoryError::Serialization(error)),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_bytes(&self, path: &Path) -> Result<Vec<u8>, RepositoryError> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(bytes),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(self.storage_error(e)),
+        }
+    }
+
+    fn read_file(&self, path: &Path) -> Result<Option<AddressMap>, RepositoryError> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(self.storage_error(e)),
+        };
+        serde_json::from_str(&data)
+            .map(Some)
+            .map_err(|e| RepositoryError::Serialization(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Replaces the snapshot by `data`, the version it replaces plus the
+    /// `folded` log. That version becomes the backup and `folded` its log;
+    /// when it was read from the backup, it is copied aside as
+    /// `<file>.corrupt` and `folded` added to the backup's log instead. At
+    /// every step the backup and its log give the addresses of the snapshot,
+    /// or of an older one with the log still to be replayed.
+    fn save_data(
+        &self,
+        data: &AddressMap,
+        recovered: bool,
+        folded: &[u8],
+    ) -> Result<(), RepositoryError> {
+        let json = serde_json::to_string_pretty(data)
+            .map_err(|e| RepositoryError::Serialization(e.to_string()))?;
+        let path = Path::new(&self.file_path);
+        let temporary = self.companion("tmp");
+        self.write_synced(&temporary, json.as_bytes())?;
+
+        let backup_log = self.companion("bak.wal");
+        if recovered {
+            let mut log = self.read_bytes(&backup_log)?;
+            log.extend_from_slice(folded);
+            self.replace_synced(&backup_log, &log)?;
+            fs::copy(path, self.companion("corrupt")).map_err(|e| self.storage_error(e))?;
+        } else if path.exists() {
+            let backup = self.companion("bak.tmp");
+            fs::copy(path, &backup)
+                .and_then(|_| File::open(&backup)?.sync_all())
+                .and_then(|_| fs::rename(&backup, self.companion("bak")))
+                .map_err(|e| self.storage_error(e))?;
+            self.replace_synced(&backup_log, folded)?;
+        }
+        fs::rename(&temporary, path).map_err(|e| self.storage_error(e))?;
+        self.sync_directory(path)
+    }
+
+    /// Writes `contents` to `<path>.tmp`, synced, then renames it over `path`.
+    fn replace_synced(&self, path: &Path, contents: &[u8]) -> Result<(), RepositoryError> {
+        let temporary = PathBuf::from(format!("{}.tmp", path.display()));
+        self.write_synced(&temporary, contents)?;
+        fs::rename(&temporary, path).map_err(|e| self.storage_error(e))
+    }
+
+    fn write_synced(&self, path: &Path, contents: &[u8]) -> Result<(), RepositoryError> {
+        File::create(path)
+            .and_then(|mut file| {
+                file.write_all(contents)?;
+                file.sync_all()
+            })
+            .map_err(|e| self.storage_error(e))
+    }
+
+    /// Makes the renames durable; directories cannot be opened for that outside Unix.
+    #[cfg(unix)]
+    fn sync_directory(&self, path: &Path) -> Result<(), RepositoryError> {
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        File::open(directory)
+            .and_then(|directory| directory.sync_all())
+            .map_err(|e| self.storage_error(e))
+    }
+
+    #[cfg(not(unix))]
+    fn sync_directory(&self, _path: &Path) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+
+    fn companion(&self, extension: &str) -> PathBuf {
+        PathBuf::from(format!("{}.{}", self.file_path, extension))
+    }
+
+    fn storage_error(&self, error: std::io::Error) -> RepositoryError {
+        RepositoryError::Storage(format!("{}: {}", self.file_path, error))
+    }
+}
+
+impl AddressRepository for FileBasedAddressRepository {
+    fn save(&mut self, address: ISO20022Address) -> Result<(), RepositoryError> {
+        self.save_all(vec![address])
+    }
+
+    fn save_all(&mut self, addresses: Vec<ISO20022Address>) -> Result<(), RepositoryError> {
+        check_unique_ids(&addresses)?;
+        self.modify(|stored| {
+            if let Some(address) = addresses.iter().find(|a| stored.contains_key(&a.id)) {
+                return Err(RepositoryError::AlreadyExists(address.id.clone()));
+            }
+            Ok(addresses
+                .into_iter()
+                .map(|address| LogEntry::Put {
+                    address: Box::new(address),
+                })
+                .collect())
+        })
+    }
+
+    fn update(&mut self, address: ISO20022Address) -> Result<(), RepositoryError> {
+        self.modify(|stored| {
+            if !stored.contains_key(&address.id) {
+                return Err(RepositoryError::NotFound(address.id));
+            }
+            Ok(vec![LogEntry::Put {
+                address: Box::new(address),
+            }])
+        })
+    }
+
+    fn delete(&mut self, address_id: &str) -> Result<(), RepositoryError> {
+        self.modify(|stored| {
+            if !stored.contains_key(address_id) {
+                return Err(RepositoryError::NotFound(address_id.to_string()));
+            }
+            Ok(vec![LogEntry::Delete {
+                id: address_id.to_string(),
+            }])
+        })
     }
 }
 
 impl ReadAddressRepository for FileBasedAddressRepository {
-    fn find_by_id(&self, address_id: &str) -> Option<ISO20022Address> {
-        self.load_data().get(address_id).cloned()
+    fn find_by_id(&self, address_id: &str) -> Result<Option<ISO20022Address>, RepositoryError> {
+        self.read(|addresses| addresses.get(address_id).cloned())
+    }
+
+    fn find_all(&self) -> Result<Vec<ISO20022Address>, RepositoryError> {
+        self.read(|addresses| addresses.values().cloned().collect())
+    }
+
+    fn search(&self, query: &AddressQuery) -> Result<AddressPage, RepositoryError> {
+        self.read(|addresses| query.apply(addresses.values()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use uuid::Uuid;
+
+    fn address(id: &str) -> ISO20022Address {
+        ISO20022Address {
+            id: id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn repository(dir: &Path) -> FileBasedAddressRepository {
+        fs::create_dir_all(dir).unwrap();
+        FileBasedAddressRepository::new(dir.join("addresses.json").to_string_lossy().into_owned())
+    }
+
+    fn sorted_ids(repo: &FileBasedAddressRepository) -> Vec<String> {
+        let mut ids: Vec<String> = repo.find_all().unwrap().into_iter().map(|a| a.id).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_log_is_compacted_into_the_snapshot() {
+        let dir = std::env::temp_dir().join(format!("file-repository-{}", Uuid::new_v4()));
+        let mut repo = repository(&dir).with_compact_after(2);
+
+        // The first write creates the snapshot, the next ones fill the log
+        repo.save(address("1")).unwrap();
+        repo.save_all(vec![address("2"), address("3")]).unwrap();
+        let snapshot = fs::read_to_string(&repo.file_path).unwrap();
+        assert!(snapshot.contains("\"3\""));
+        assert_eq!(fs::metadata(repo.companion("wal")).unwrap().len(), 0);
+
+        repo.delete("2").unwrap();
+        let log = fs::read_to_string(repo.companion("wal")).unwrap();
+        assert_eq!(log, "{\"op\":\"delete\",\"id\":\"2\"}\n");
+        // The snapshot alone still holds the address until the next compaction
+        let reader = repository(&dir);
+        assert_eq!(sorted_ids(&reader), ["1", "3"]);
+        assert!(
+            fs::read_to_string(&repo.file_path)
+                .unwrap()
+                .contains("\"2\"")
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_reads_do_not_create_the_lock() {
+        let dir = std::env::temp_dir().join(format!("file-repository-{}", Uuid::new_v4()));
+        let repo = repository(&dir);
+        let snapshot = AddressMap::from([("1".to_string(), address("1"))]);
+        fs::write(&repo.file_path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+
+        assert_eq!(sorted_ids(&repo), ["1"]);
+        assert!(!repo.companion("lock").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_external_changes_are_noticed() {
+        let dir = std::env::temp_dir().join(format!("file-repository-{}", Uuid::new_v4()));
+        let mut writer = repository(&dir).with_compact_after(2);
+        let reader = repository(&dir);
+
+        writer.save(address("1")).unwrap();
+        assert_eq!(sorted_ids(&reader), ["1"]);
+        writer.save(address("2")).unwrap(); // logged
+        assert_eq!(sorted_ids(&reader), ["1", "2"]);
+        writer.save(address("3")).unwrap(); // compacts
+        assert_eq!(sorted_ids(&reader), ["1", "2", "3"]);
+
+        // A snapshot written by another tool
+        let external = AddressMap::from([("9".to_string(), address("9"))]);
+        fs::write(&writer.file_path, serde_json::to_string(&external).unwrap()).unwrap();
+        assert_eq!(sorted_ids(&reader), ["9"]);
+        assert_eq!(writer.find_by_id("9").unwrap().unwrap().id, "9");
+        let _ = fs::remove_dir_all(dir);
     }
 
-    fn find_all(&self) -> Vec<ISO20022Address> {
-        self.load_data().values().cloned().collect()
+    #[test]
+    fn test_recovery_after_crashes() {
+        let dir = std::env::temp_dir().join(format!("file-repository-{}", Uuid::new_v4()));
+        let mut repo = repository(&dir).with_compact_after(1);
+        repo.save(address("1")).unwrap();
+        repo.save(address("2")).unwrap();
+        let backup = fs::read_to_string(repo.companion("bak")).unwrap();
+        assert!(backup.contains("\"1\"") && !backup.contains("\"2\""));
+
+        // A log entry cut short, then a truncated snapshot
+        let mut repo = repository(&dir);
+        repo.save(address("3")).unwrap();
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(repo.companion("wal"))
+            .unwrap();
+        log.write_all(b"{\"op\":\"put\",\"addr").unwrap();
+        let reader = repository(&dir);
+        assert_eq!(sorted_ids(&reader), ["1", "2", "3"]);
+        assert_eq!(sorted_ids(&reader), ["1", "2", "3"]);
+        let warnings = reader.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("cut short by a crash"));
+        repo.save(address("4")).unwrap();
+        assert_eq!(sorted_ids(&repository(&dir)), ["1", "2", "3", "4"]);
+
+        let json = fs::read_to_string(&repo.file_path).unwrap();
+        fs::write(&repo.file_path, &json[..json.len() / 2]).unwrap();
+        // Every acknowledged write survives: "2" is in the backup's log
+        let mut repo = repository(&dir).with_compact_after(1);
+        assert_eq!(sorted_ids(&repo), ["1", "2", "3", "4"]);
+        let warnings = repo.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].ends_with("addresses.json.bak instead"));
+        repo.save(address("5")).unwrap();
+        assert_eq!(sorted_ids(&repository(&dir)), ["1", "2", "3", "4", "5"]);
+        assert_eq!(
+            fs::read_to_string(repo.companion("corrupt")).unwrap(),
+            json[..json.len() / 2]
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_concurrent_writers_lose_nothing() {
+        let dir = std::env::temp_dir().join(format!("file-repository-{}", Uuid::new_v4()));
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                // One repository per thread, as separate processes would have
+                let mut repo = repository(&dir).with_compact_after(7);
+                thread::spawn(move || {
+                    for n in 0..10 {
+                        repo.save(address(&format!("{writer}-{n}"))).unwrap();
+                    }
+                })
+            })
+            .collect();
+        writers.into_iter().for_each(|w| w.join().unwrap());
+
+        assert_eq!(repository(&dir).find_all().unwrap().len(), 40);
+        let _ = fs::remove_dir_all(dir);
     }
 }
@@ -1,50 +1,72 @@
 use crate::domain::models::ISO20022Address;
-use crate::domain::repository::{AddressRepository, ReadAddressRepository};
+use crate::domain::repository::{
+    AddressRepository, ReadAddressRepository, RepositoryError, check_unique_ids,
+};
+use crate::domain::search::{AddressPage, AddressQuery};
 use std::collections::HashMap;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct InMemoryAddressRepository {
     addresses: HashMap<String, ISO20022Address>,
 }
 
 impl InMemoryAddressRepository {
     pub fn new() -> Self {
-        Self {
-            addresses: HashMap::new(),
-        }
+        Self::default()
     }
 }
 
 impl AddressRepository for InMemoryAddressRepository {
-    fn save(&mut self, address: ISO20022Address) -> Result<(), String> {
+    fn save(&mut self, address: ISO20022Address) -> Result<(), RepositoryError> {
+        if self.addresses.contains_key(&address.id) {
+            return Err(RepositoryError::AlreadyExists(address.id));
+        }
         self.addresses.insert(address.id.clone(), address);
         Ok(())
     }
 
-    fn update(&mut self, address: ISO20022Address) -> Result<(), String> {
+    fn save_all(&mut self, addresses: Vec<ISO20022Address>) -> Result<(), RepositoryError> {
+        check_unique_ids(&addresses)?;
+        if let Some(stored) = addresses
+            .iter()
+            .find(|address| self.addresses.contains_key(&address.id))
+        {
+            return Err(RepositoryError::AlreadyExists(stored.id.clone()));
+        }
+        for address in addresses {
+            self.addresses.insert(address.id.clone(), address);
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, address: ISO20022Address) -> Result<(), RepositoryError> {
         if self.addresses.contains_key(&address.id) {
             self.addresses.insert(address.id.clone(), address);
             Ok(())
         } else {
-            Err("Adresse non trouvée".to_string())
+            Err(RepositoryError::NotFound(address.id))
         }
     }
 
-    fn delete(&mut self, address_id: &str) -> Result<(), String> {
+    fn delete(&mut self, address_id: &str) -> Result<(), RepositoryError> {
         if self.addresses.remove(address_id).is_some() {
             Ok(())
         } else {
-            Err("Adresse non trouvée".to_string())
+            Err(RepositoryError::NotFound(address_id.to_string()))
         }
     }
 }
 
 impl ReadAddressRepository for InMemoryAddressRepository {
-    fn find_by_id(&self, address_id: &str) -> Option<ISO20022Address> {
-        self.addresses.get(address_id).cloned()
+    fn find_by_id(&self, address_id: &str) -> Result<Option<ISO20022Address>, RepositoryError> {
+        Ok(self.addresses.get(address_id).cloned())
+    }
+
+    fn find_all(&self) -> Result<Vec<ISO20022Address>, RepositoryError> {
+        Ok(self.addresses.values().cloned().collect())
     }
 
-    fn find_all(&self) -> Vec<ISO20022Address> {
-        self.addresses.values().cloned().collect()
+    fn search(&self, query: &AddressQuery) -> Result<AddressPage, RepositoryError> {
+        Ok(query.apply(self.addresses.values()))
     }
 }
@@ -1,3 +1,3 @@
 pub mod file_repository;
 pub mod in_memory_repository;
-pub mod mongo_repository;
\ No newline at end of file
+pub mod mongo_repository;
@@ -1,10 +1,19 @@
 use crate::domain::models::ISO20022Address;
-use crate::domain::repository::{AddressRepository, ReadAddressRepository};
+use crate::domain::repository::{
+    AddressRepository, AddressStream, ReadAddressRepository, RepositoryError, check_unique_ids,
+};
+use crate::domain::search::{AddressPage, AddressQuery};
 use mongodb::{
-    bson::doc,
+    IndexModel,
+    bson::{Document, doc},
+    error::{Error, ErrorKind, WriteFailure},
+    options::IndexOptions,
     sync::{Client, Collection},
 };
 
+/// Server error code of a write breaking a unique index.
+const DUPLICATE_KEY: i32 = 11000;
+
 #[derive(Clone)]
 pub struct MongoAddressRepository {
     collection: Collection<ISO20022Address>,
@@ -20,6 +29,16 @@ impl MongoAddressRepository {
         let client = Client::with_uri_str(&sanitized).map_err(|e| e.to_string())?;
         let db = client.database(db_name);
         let collection = db.collection::<ISO20022Address>(coll_name);
+
+        // Ids are unique across processes; fails if the collection already holds duplicates.
+        let unique_id = IndexModel::builder()
+            .keys(doc! { "id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        collection
+            .create_index(unique_id)
+            .run()
+            .map_err(|e| format!("Cannot create the unique index on id: {}", e))?;
         Ok(Self { collection })
     }
 }
@@ -37,7 +56,9 @@ fn sanitize_tls_insecure_false(uri: &str) -> String {
     let mut params: Vec<(String, String)> = vec![];
     if let Some(q) = query {
         for pair in q.split('&') {
-            if pair.is_empty() { continue; }
+            if pair.is_empty() {
+                continue;
+            }
             let mut kv = pair.splitn(2, '=');
             let k = kv.next().unwrap_or("");
             let v = kv.next().unwrap_or("");
@@ -75,54 +96,205 @@ fn sanitize_tls_insecure_false(uri: &str) -> String {
 }
 
 impl AddressRepository for MongoAddressRepository {
-    fn save(&mut self, address: ISO20022Address) -> Result<(), String> {
-        self.collection
-            .insert_one(address)
+    fn save(&mut self, address: ISO20022Address) -> Result<(), RepositoryError> {
+        self.save_all(vec![address])
+    }
+
+    /// All or nothing without needing a replica set for transactions: when
+    /// the insert fails part way, the addresses it already wrote are deleted
+    /// again. Readers may see them in between, and should that delete fail
+    /// too the error says so and they stay stored.
+    fn save_all(&mut self, addresses: Vec<ISO20022Address>) -> Result<(), RepositoryError> {
+        if addresses.is_empty() {
+            return Ok(());
+        }
+        check_unique_ids(&addresses)?;
+        let ids: Vec<String> = addresses.iter().map(|a| a.id.clone()).collect();
+        let stored = self
+            .collection
+            .find_one(doc! { "id": { "$in": &ids } })
             .run()
-            .map_err(|e| e.to_string())?;
-        Ok(())
+            .map_err(mongo_error)?;
+        if let Some(stored) = stored {
+            return Err(RepositoryError::AlreadyExists(stored.id));
+        }
+        // The unique index on `id` still catches a concurrent insert.
+        let Err(error) = self.collection.insert_many(addresses).run() else {
+            return Ok(());
+        };
+        // The insert is ordered: it stops at the refused address, and none of
+        // the ids were stored before it started.
+        let refused = duplicate_key_index(&error);
+        let written = &ids[..refused.unwrap_or(ids.len())];
+        let error = match refused {
+            Some(index) => RepositoryError::AlreadyExists(ids[index].clone()),
+            None => mongo_error(error),
+        };
+        if !written.is_empty()
+            && let Err(rollback) = self
+                .collection
+                .delete_many(doc! { "id": { "$in": written } })
+                .run()
+        {
+            return Err(RepositoryError::Storage(format!(
+                "{error}; the {} addresses written before it could not be removed: {rollback}",
+                written.len()
+            )));
+        }
+        Err(error)
     }
 
-    fn update(&mut self, address: ISO20022Address) -> Result<(), String> {
+    fn update(&mut self, address: ISO20022Address) -> Result<(), RepositoryError> {
         let filter = doc! { "id": &address.id };
-        self.collection
+        let id = address.id.clone();
+        let result = self
+            .collection
             .replace_one(filter, address)
             .run()
-            .map_err(|e| e.to_string())?;
+            .map_err(mongo_error)?;
+        if result.matched_count == 0 {
+            return Err(RepositoryError::NotFound(id));
+        }
         Ok(())
     }
 
-    fn delete(&mut self, address_id: &str) -> Result<(), String> {
+    fn delete(&mut self, address_id: &str) -> Result<(), RepositoryError> {
         let filter = doc! { "id": address_id };
-        self.collection
+        let result = self
+            .collection
             .delete_one(filter)
             .run()
-            .map_err(|e| e.to_string())?;
+            .map_err(mongo_error)?;
+        if result.deleted_count == 0 {
+            return Err(RepositoryError::NotFound(address_id.to_string()));
+        }
         Ok(())
     }
 }
 
+fn mongo_error(error: Error) -> RepositoryError {
+    match error.kind.as_ref() {
+        ErrorKind::BsonSerialization(_) | ErrorKind::BsonDeserialization(_) => {
+            RepositoryError::Serialization(error.to_string())
+        }
+        _ => RepositoryError::Storage(error.to_string()),
+    }
+}
+
+/// Position in the batch of the first address refused by a unique index.
+fn duplicate_key_index(error: &Error) -> Option<usize> {
+    match error.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == DUPLICATE_KEY => Some(0),
+        ErrorKind::InsertMany(e) => e
+            .write_errors
+            .iter()
+            .flatten()
+            .find(|e| e.code == DUPLICATE_KEY)
+            .map(|e| e.index),
+        _ => None,
+    }
+}
+
 impl ReadAddressRepository for MongoAddressRepository {
-    fn find_by_id(&self, address_id: &str) -> Option<ISO20022Address> {
+    fn find_by_id(&self, address_id: &str) -> Result<Option<ISO20022Address>, RepositoryError> {
         let filter = doc! { "id": address_id };
-        self.collection.find_one(filter).run().unwrap_or_else(|_| None)
+        self.collection.find_one(filter).run().map_err(mongo_error)
     }
 
-    fn find_all(&self) -> Vec<ISO20022Address> {
-        let cursor = match self.collection.find(doc! {}).run() {
-            Ok(cursor) => cursor,
-            Err(_) => return vec![],
-        };
+    fn find_all(&self) -> Result<Vec<ISO20022Address>, RepositoryError> {
+        self.stream_all().collect()
+    }
+
+    fn stream_all(&self) -> AddressStream {
+        match self.collection.find(doc! {}).run() {
+            Ok(cursor) => Box::new(cursor.map(|doc_result| doc_result.map_err(mongo_error))),
+            Err(e) => Box::new(std::iter::once(Err(mongo_error(e)))),
+        }
+    }
+
+    fn search(&self, query: &AddressQuery) -> Result<AddressPage, RepositoryError> {
+        let filter = search_filter(query);
+        let direction = if query.descending { -1 } else { 1 };
+        let mut sort = doc! { query.sort.field_name(): direction };
+        sort.insert("id", direction);
+
+        let total = self
+            .collection
+            .count_documents(filter.clone())
+            .run()
+            .map_err(mongo_error)?;
+        let mut find = self
+            .collection
+            .find(filter)
+            .sort(sort)
+            .skip(query.skip() as u64);
+        if let Some(size) = query.page_size {
+            find = find.limit(size as i64);
+        }
+        let addresses = find
+            .run()
+            .map_err(mongo_error)?
+            .map(|doc_result| doc_result.map_err(mongo_error))
+            .collect::<Result<_, _>>()?;
+        Ok(AddressPage {
+            addresses,
+            total: total as usize,
+            page: query.page,
+            page_size: query.page_size,
+        })
+    }
+}
 
-        cursor
-            .map(|doc_result| doc_result.unwrap_or_default())
-            .collect()
+/// The criteria of `query` as a filter document, matching like
+/// `AddressQuery::matches`: case-insensitive regular expressions on the text
+/// fields, with the criteria escaped.
+fn search_filter(query: &AddressQuery) -> Document {
+    let mut filter = doc! {};
+    let mut text = |field: &str, criterion: &Option<String>, pattern: fn(String) -> String| {
+        if let Some(criterion) = criterion {
+            let pattern = pattern(regex::escape(criterion.trim()));
+            filter.insert(field, doc! { "$regex": pattern, "$options": "i" });
+        }
+    };
+    text("post_code", &query.post_code, |c| format!("^{c}"));
+    text("town_name", &query.town, |c| format!("^{c}$"));
+    text("country", &query.country, |c| format!("^{c}$"));
+    text("recipient_name", &query.recipient_name, |c| c);
+    text("street_name", &query.street_name, |c| c);
+    if let Some(kind) = query.kind {
+        let kind = mongodb::bson::to_bson(&kind).expect("AddressKind serializes to a string");
+        filter.insert("kind", kind);
     }
+    filter
 }
 
 #[cfg(test)]
 mod tests {
-    use super::sanitize_tls_insecure_false;
+    use super::{sanitize_tls_insecure_false, search_filter};
+    use crate::domain::models::AddressKind;
+    use crate::domain::search::AddressQuery;
+    use mongodb::bson::doc;
+
+    #[test]
+    fn search_filter_escapes_criteria() {
+        let query = AddressQuery {
+            post_code: Some("75008".to_string()),
+            town: Some("Saint-Denis".to_string()),
+            recipient_name: Some("A.B (SA)".to_string()),
+            kind: Some(AddressKind::Company),
+            ..Default::default()
+        };
+        assert_eq!(
+            search_filter(&query),
+            doc! {
+                "post_code": { "$regex": "^75008", "$options": "i" },
+                "town_name": { "$regex": "^Saint\\-Denis$", "$options": "i" },
+                "recipient_name": { "$regex": "A\\.B \\(SA\\)", "$options": "i" },
+                "kind": "Company",
+            }
+        );
+        assert_eq!(search_filter(&AddressQuery::default()), doc! {});
+    }
 
     #[test]
     fn sanitize_removes_tls_insecure_false_and_adds_strict_flags() {
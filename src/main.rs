@@ -1,18 +1,41 @@
 use clap::Parser;
 use std::env;
+use std::sync::Arc;
 
 use fraddriso20022::application::command::address_command_service::AddressCommandService;
 use fraddriso20022::application::query::address_query_service::AddressQueryService;
-use fraddriso20022::cli::commands::{run_cqrs, CliCqrs};
+use fraddriso20022::application::query::address_verification_service::AddressVerificationService;
+use fraddriso20022::cli::commands::{run_cqrs, run_stateless, CliCqrs};
+use fraddriso20022::domain::postal_reference::TownCorrectionMode;
 use fraddriso20022::domain::repository::{AddressRepository, ReadAddressRepository};
+use fraddriso20022::infrastructure::reference::ban_importer::load_ban_index;
+use fraddriso20022::infrastructure::reference::postal_reference_loader::load_postal_reference;
 use fraddriso20022::infrastructure::repository::file_repository::FileBasedAddressRepository;
 use fraddriso20022::infrastructure::repository::in_memory_repository::InMemoryAddressRepository;
 use fraddriso20022::infrastructure::repository::mongo_repository::MongoAddressRepository;
 
 fn main() {
     let cli = CliCqrs::parse();
+    let output = cli.output;
+
+    let postal_reference = env::var("POSTAL_REFERENCE_CSV").ok().map(|path| {
+        let mode: TownCorrectionMode = env::var("TOWN_CORRECTION")
+            .unwrap_or_else(|_| "suggest".to_string())
+            .parse()
+            .expect("TOWN_CORRECTION must be 'suggest' or 'auto'!");
+        let reference = load_postal_reference(&path).expect("Cannot load postal reference data!");
+        Arc::new(reference.with_correction_mode(mode))
+    });
+
+    // Conversions without storage don't open the repository.
+    let cli = match run_stateless(cli, postal_reference.as_ref()) {
+        Ok(Some(cli)) => cli,
+        Ok(None) => return,
+        Err(e) => e.exit(output),
+    };
 
     let selected_repo = env::var("SELECT_REPO").unwrap_or_else(|_| "file".to_string());
+    let mut file_repo = None;
 
     let (write_repo, read_repo): (
         Box<dyn AddressRepository + Send>,
@@ -33,12 +56,35 @@ fn main() {
         }
         _ => {
             let repo_file = FileBasedAddressRepository::new("addresses.json".to_string());
+            file_repo = Some(repo_file.clone());
             (Box::new(repo_file.clone()), Box::new(repo_file))
         }
     };
 
+    let ban_index = env::var("BAN_INDEX_PATH")
+        .ok()
+        .map(|path| Arc::new(load_ban_index(&path).expect("Cannot load BAN index!")));
+    let verification_service = ban_index.clone().map(AddressVerificationService::new);
+
     let mut command_service = AddressCommandService::new(write_repo);
+    if let Some(index) = ban_index {
+        command_service = command_service.with_geocoder(index);
+    }
     let query_service = AddressQueryService::new(read_repo);
 
-    run_cqrs(cli, &mut command_service, &query_service);
+    let result = run_cqrs(
+        cli,
+        &mut command_service,
+        &query_service,
+        verification_service.as_ref(),
+        postal_reference.as_ref(),
+    );
+
+    // Damage the file repository worked around, such as a snapshot read from its backup
+    for warning in file_repo.iter().flat_map(|repo| repo.take_warnings()) {
+        output.print_warning(&warning, serde_json::json!({ "warning": warning }));
+    }
+    if let Err(e) = result {
+        e.exit(output);
+    }
 }
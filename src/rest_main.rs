@@ -1,17 +1,30 @@
 use actix_web::{web, App, HttpServer};
 use fraddriso20022::application::command::address_command_service::AddressCommandService;
 use fraddriso20022::application::query::address_query_service::AddressQueryService;
+use fraddriso20022::application::query::address_verification_service::AddressVerificationService;
+use fraddriso20022::domain::postal_reference::TownCorrectionMode;
 use fraddriso20022::domain::repository::{AddressRepository, ReadAddressRepository};
 use fraddriso20022::infrastructure::app_state::AppState;
 use fraddriso20022::infrastructure::controller::rest_controller::configure_routes;
+use fraddriso20022::infrastructure::reference::ban_importer::load_ban_index;
+use fraddriso20022::infrastructure::reference::postal_reference_loader::load_postal_reference;
 use fraddriso20022::infrastructure::repository::file_repository::FileBasedAddressRepository;
 use fraddriso20022::infrastructure::repository::in_memory_repository::InMemoryAddressRepository;
 use fraddriso20022::infrastructure::repository::mongo_repository::MongoAddressRepository;
 use std::env;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+
+    let postal_reference = env::var("POSTAL_REFERENCE_CSV").ok().map(|path| {
+        let mode: TownCorrectionMode = env::var("TOWN_CORRECTION")
+            .unwrap_or_else(|_| "suggest".to_string())
+            .parse()
+            .expect("TOWN_CORRECTION must be 'suggest' or 'auto'!");
+        let reference = load_postal_reference(&path).expect("Cannot load postal reference data!");
+        Arc::new(reference.with_correction_mode(mode))
+    });
     let selected_repo = env::var("SELECT_REPO").unwrap_or_else(|_| "file".to_string());
 
     let (write_repo, read_repo): (
@@ -33,16 +46,33 @@ async fn main() -> std::io::Result<()> {
         }
         _ => {
             let repo_file = FileBasedAddressRepository::new("addresses.json".to_string());
+            // Loaded now so that damage worked around is reported at startup
+            if let Err(e) = repo_file.find_all() {
+                eprintln!("Warning: {}", e);
+            }
+            for warning in repo_file.take_warnings() {
+                eprintln!("Warning: {}", warning);
+            }
             (Box::new(repo_file.clone()), Box::new(repo_file))
         }
     };
 
-    let command_service = AddressCommandService::new(write_repo);
+    let ban_index = env::var("BAN_INDEX_PATH")
+        .ok()
+        .map(|path| Arc::new(load_ban_index(&path).expect("Cannot load BAN index!")));
+    let verification_service = ban_index.clone().map(AddressVerificationService::new);
+
+    let mut command_service = AddressCommandService::new(write_repo);
+    if let Some(index) = ban_index {
+        command_service = command_service.with_geocoder(index);
+    }
     let query_service = AddressQueryService::new(read_repo);
 
     let shared_state = web::Data::new(AppState {
         command_service: Mutex::new(command_service),
         query_service: Mutex::new(query_service),
+        verification_service,
+        postal_reference,
     });
 
     println!("Server running at http://127.0.0.1:8080 ...");
@@ -1,4 +1,5 @@
 use assert_cmd::Command;
+use predicates::prelude::PredicateBooleanExt;
 use predicates::str::{contains, is_match};
 use regex::Regex;
 use std::path::Path;
@@ -144,12 +145,306 @@ fn test_cli_end_to_end() {
         .arg("--id")
         .arg(&id)
         .assert()
-        .success()
+        .code(3)
         .stderr(is_match(r"Address with ID .* not found").unwrap());
 
     println!("✅ Adresse supprimée avec succès !");
 }
 
+#[test]
+fn test_cli_stateless_conversion() {
+    let input = r#"{"line1":"DURAND SA","line4":"22BIS RUE DES FLEURS","line6":"33500 LIBOURNE","line7":"FRANCE"}
+{"line1":"Jean Dupont","line4":"15 RUE DES LILAS","line6":"LYON","line7":"FRANCE"}"#;
+
+    // to-iso n'écrit rien dans le dépôt, même si MongoDB est sélectionné
+    let output = Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .env("SELECT_REPO", "mongo")
+        .env_remove("MONGO_URI")
+        .arg("to-iso")
+        .write_stdin(input)
+        .assert()
+        .code(4)
+        .stderr(contains("Row 2: Validation error"))
+        .get_output()
+        .stdout
+        .clone();
+    let iso = str::from_utf8(&output).unwrap();
+    assert_eq!(iso.lines().count(), 1);
+    assert!(iso.contains(r#""building_number":"22BIS""#));
+
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .args(["to-french", "--format", "csv", "--columns", "line4,line6"])
+        .write_stdin(iso)
+        .assert()
+        .success()
+        .stdout("line4;line6\n22BIS RUE DES FLEURS;33500 LIBOURNE\n");
+}
+
+#[test]
+fn test_cli_json_output_and_exit_codes() {
+    // Dépôt en mémoire : rien n'est écrit dans addresses.json
+    let output = Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .env("SELECT_REPO", "inmemory")
+        .args(["add", "--output", "json", "--kind", "company"])
+        .args(["-a", "DURAND SA", "-d", "22BIS RUE DES FLEURS"])
+        .args(["-f", "33500 LIBOURNE", "-g", "FRANCE"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let added: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(added["building_number"], "22BIS");
+    assert_eq!(added["town_name"], "LIBOURNE");
+
+    let output = Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .env("SELECT_REPO", "inmemory")
+        .args(["get", "--id", "inconnu", "--output", "ndjson"])
+        .assert()
+        .code(3)
+        .get_output()
+        .stderr
+        .clone();
+    let error: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(error["error"], "not_found");
+
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .env("SELECT_REPO", "inmemory")
+        .args([
+            "--output",
+            "json",
+            "add",
+            "--kind",
+            "company",
+            "-a",
+            "DURAND SA",
+        ])
+        .assert()
+        .code(4)
+        .stderr(contains(r#""error":"validation""#));
+
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .env("SELECT_REPO", "inmemory")
+        .args(["add", "--kind", "association", "-a", "DURAND SA"])
+        .assert()
+        .code(2)
+        .stderr(contains("Invalid address kind"));
+}
+
+#[test]
+fn test_cli_interactive_add() {
+    // Le code postal invalide est redemandé avant l'aperçu et la confirmation
+    let answers = "DURAND SA\n\n\n22BIS RUE DES FLEURS\n\n\nLIBOURNE\n33500 LIBOURNE\ny\n";
+
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .env("SELECT_REPO", "inmemory")
+        .args(["add", "--interactive", "--kind", "company"])
+        .write_stdin(answers)
+        .assert()
+        .success()
+        .stdout(contains("Address added successfully with ID:"))
+        .stderr(contains("Line 6 - Postal code and town"))
+        .stderr(contains("InvalidPostalCode"))
+        .stderr(contains("building_number  22BIS"));
+
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .env("SELECT_REPO", "inmemory")
+        .args([
+            "add",
+            "--interactive",
+            "--kind",
+            "company",
+            "-a",
+            "DURAND SA",
+        ])
+        .write_stdin("\n\n\n22BIS RUE DES FLEURS\n\n\n33500 LIBOURNE\nn\n")
+        .assert()
+        .success()
+        .stdout(contains("Address not saved."));
+}
+
+#[test]
+fn test_cli_validate() {
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .args(["validate", "-a", "DURAND SA", "-d", "22BIS RUE DES FLEURS"])
+        .args(["-f", "33500 LIBOURNE", "-g", "FRANCE"])
+        .assert()
+        .success()
+        .stdout(contains("1 valid, 0 invalid"));
+
+    // Un enregistrement invalide fait échouer la commande (code 4)
+    let input = r#"{"line1":"DURAND SA","line4":"22BIS RUE DES FLEURS","line6":"33500 LIBOURNE","line7":"FRANCE"}
+{"line1":"Jean Dupont","line4":"15 RUE DES LILAS","line6":"LYON","line7":"FRANCE"}"#;
+    let output = Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .args(["validate", "--profile", "sepa", "--output", "json"])
+        .write_stdin(input)
+        .assert()
+        .code(4)
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["invalid"], 1);
+    assert_eq!(report["records"][0]["valid"], true);
+    assert_eq!(report["records"][1]["record"], 2);
+
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .args(["validate", "--input-format", "xml", "--profile", "cbpr"])
+        .write_stdin("<PstlAdr><AdrLine>15 RUE DES LILAS</AdrLine><Ctry>FR</Ctry></PstlAdr>")
+        .assert()
+        .code(4)
+        .stdout(contains("Record 1: Validation error: UnstructuredAddress"));
+}
+
+#[test]
+fn test_cli_search() {
+    // Dossier à part : le dépôt fichier écrit addresses.json dans le dossier courant
+    let dir = std::env::temp_dir().join(format!("cli-search-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for (kind, name, line6) in [
+        ("company", "DURAND SA", "75008 PARIS"),
+        ("particular", "Jean Martin", "69001 LYON"),
+        ("company", "MARTIN CONSEIL", "75016 PARIS"),
+    ] {
+        Command::cargo_bin("fraddriso20022-cli")
+            .unwrap()
+            .current_dir(&dir)
+            .args(["add", "-k", kind, "-a", name, "-d", "3 AVENUE FOCH"])
+            .args(["-f", line6, "-g", "FRANCE"])
+            .assert()
+            .success();
+    }
+
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["search", "--post-code", "75", "--recipient-name", "martin"])
+        .assert()
+        .success()
+        .stdout(contains("MARTIN CONSEIL"))
+        .stdout(contains("DURAND SA").not())
+        .stdout(contains("Matching addresses: 1."));
+
+    let output = Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["search", "-k", "company", "--sort", "name", "--desc"])
+        .args(["--page-size", "1", "--output", "json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let page: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(page["total"], 2);
+    assert_eq!(page["addresses"][0]["recipient_name"], "MARTIN CONSEIL");
+
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["search", "--page", "0"])
+        .assert()
+        .code(2);
+
+    // Un fichier corrompu est relu depuis la sauvegarde, complétée par le journal...
+    fs::copy(dir.join("addresses.json"), dir.join("addresses.json.bak")).unwrap();
+    fs::write(dir.join("addresses.json"), "{not json").unwrap();
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .current_dir(&dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(contains("DURAND SA"))
+        .stderr(contains("read addresses.json.bak instead"));
+
+    // ... et sans sauvegarde, c'est une erreur de stockage, pas un dépôt vide
+    fs::remove_file(dir.join("addresses.json.bak")).unwrap();
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .current_dir(&dir)
+        .arg("list")
+        .assert()
+        .code(5)
+        .stderr(contains("Failed to read addresses: Serialization error"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_cli_update_keeps_stored_data() {
+    let dir = std::env::temp_dir().join(format!("cli-update-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let stored = serde_json::json!({
+        "geo-1": {
+            "id": "geo-1",
+            "recipient_name": "DURAND SA",
+            "kind": "Company",
+            "street_name": "RUE DES FLEURS",
+            "building_number": "22BIS",
+            "post_code": "33500",
+            "town_name": "LIBOURNE",
+            "country": "FR",
+            "source_references": ["STMT-2024-001"],
+            "geolocation": {
+                "latitude": 44.915,
+                "longitude": -0.244,
+                "ban_id": "33243_1234_00022_bis"
+            }
+        }
+    });
+    fs::write(dir.join("addresses.json"), stored.to_string()).unwrap();
+    let get = || {
+        let output = Command::cargo_bin("fraddriso20022-cli")
+            .unwrap()
+            .current_dir(&dir)
+            .args(["get", "--id", "geo-1", "--output", "json"])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        serde_json::from_slice::<serde_json::Value>(&output).unwrap()
+    };
+
+    // Seul le destinataire change : les coordonnées restent valables
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["update", "--id", "geo-1", "--kind", "company"])
+        .args(["-a", "DURAND ET FILS"])
+        .assert()
+        .success();
+    let updated = get();
+    assert_eq!(updated["recipient_name"], "DURAND ET FILS");
+    assert_eq!(updated["geolocation"]["ban_id"], "33243_1234_00022_bis");
+    assert_eq!(updated["source_references"][0], "STMT-2024-001");
+
+    // Nouvelle rue : les anciennes coordonnées ne sont pas conservées
+    Command::cargo_bin("fraddriso20022-cli")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["update", "--id", "geo-1", "--kind", "company"])
+        .args(["-d", "3 AVENUE FOCH"])
+        .assert()
+        .success();
+    let moved = get();
+    assert_eq!(moved["street_name"], "AVENUE FOCH");
+    assert!(moved.get("geolocation").is_none());
+    assert_eq!(moved["source_references"][0], "STMT-2024-001");
+    let _ = fs::remove_dir_all(&dir);
+}
+
 /// Gestionnaire de fichier temporaire pour les tests
 struct TestFileManager {
     file_path: String,
@@ -164,13 +459,22 @@ impl TestFileManager {
 
         // Supprime le fichier s'il existe avant le test
         let _ = fs::remove_file(&manager.file_path);
+        manager.remove_companions();
 
         manager
     }
+
+    /// Supprime le verrou, la sauvegarde et le journal du dépôt fichier
+    fn remove_companions(&self) {
+        for extension in ["lock", "bak", "bak.wal", "wal"] {
+            let _ = fs::remove_file(format!("{}.{}", self.file_path, extension));
+        }
+    }
 }
 
 impl Drop for TestFileManager {
     fn drop(&mut self) {
+        self.remove_companions();
         if Path::new(&self.file_path).exists() {
             if fs::remove_file(&self.file_path).is_err() {
                 eprintln!("⚠️ Impossible de supprimer {}", self.file_path);
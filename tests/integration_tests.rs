@@ -1,8 +1,14 @@
 use fraddriso20022::application::command::address_command_service::AddressCommandService;
 use fraddriso20022::application::query::address_query_service::AddressQueryService;
+use fraddriso20022::domain::ban::{BanAddress, BanIndex};
 use fraddriso20022::domain::models::{AddressKind, FrenchAddress, ISO20022Address};
-use fraddriso20022::domain::repository::{AddressRepository, ReadAddressRepository};
+use fraddriso20022::domain::repository::{
+    AddressRepository, ReadAddressRepository, RepositoryError,
+};
+use fraddriso20022::domain::search::{AddressQuery, SortField};
 use fraddriso20022::domain::usecases::convert_to_iso;
+use fraddriso20022::infrastructure::bulk_import::csv_importer::{CsvImportOptions, import_csv};
+use fraddriso20022::infrastructure::repository::file_repository::FileBasedAddressRepository;
 use fraddriso20022::infrastructure::repository::in_memory_repository::InMemoryAddressRepository;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
@@ -21,25 +27,25 @@ impl ArcInMemoryRepository {
 }
 
 impl AddressRepository for ArcInMemoryRepository {
-    fn save(&mut self, address: ISO20022Address) -> Result<(), String> {
+    fn save(&mut self, address: ISO20022Address) -> Result<(), RepositoryError> {
         self.inner.lock().unwrap().save(address)
     }
 
-    fn update(&mut self, address: ISO20022Address) -> Result<(), String> {
+    fn update(&mut self, address: ISO20022Address) -> Result<(), RepositoryError> {
         self.inner.lock().unwrap().update(address)
     }
 
-    fn delete(&mut self, address_id: &str) -> Result<(), String> {
+    fn delete(&mut self, address_id: &str) -> Result<(), RepositoryError> {
         self.inner.lock().unwrap().delete(address_id)
     }
 }
 
 impl ReadAddressRepository for ArcInMemoryRepository {
-    fn find_by_id(&self, address_id: &str) -> Option<ISO20022Address> {
+    fn find_by_id(&self, address_id: &str) -> Result<Option<ISO20022Address>, RepositoryError> {
         self.inner.lock().unwrap().find_by_id(address_id)
     }
 
-    fn find_all(&self) -> Vec<ISO20022Address> {
+    fn find_all(&self) -> Result<Vec<ISO20022Address>, RepositoryError> {
         self.inner.lock().unwrap().find_all()
     }
 }
@@ -62,12 +68,12 @@ fn particular_with_all_data() {
         line6: Some("88000 EPINAL".to_string()),
         line7: Some("France".to_string()),
     };
-    let converted_address = convert_to_iso(&address, AddressKind::Particular);
+    let converted_address = convert_to_iso(&address, AddressKind::Particular).unwrap();
     assert_eq!(converted_address.id, id);
     command_service
         .add_address(converted_address.clone())
         .expect("Failed to add address");
-    let stored_address = read_service.get_address(&id);
+    let stored_address = read_service.get_address(&id).unwrap();
     assert!(stored_address.is_some(), "Address not found in repository");
     let stored_address = stored_address.unwrap();
     assert_eq!(stored_address.id, id);
@@ -103,11 +109,13 @@ fn particular_with_all_data() {
         country: Some("FR".to_string()),
         district_name: None,
         country_sub_division: None,
+        geolocation: None,
+        source_references: vec![],
     };
     command_service
         .update_address(address_update)
         .expect("Failed to update address");
-    let stored_address = read_service.get_address(&id);
+    let stored_address = read_service.get_address(&id).unwrap();
     assert!(stored_address.is_some(), "Address not found in repository");
     let stored_address = stored_address.unwrap();
     assert_eq!(stored_address.id, id);
@@ -116,7 +124,7 @@ fn particular_with_all_data() {
     command_service
         .delete_address(&id)
         .expect("Failed to delete address");
-    let stored_address = read_service.get_address(&id);
+    let stored_address = read_service.get_address(&id).unwrap();
     assert!(stored_address.is_none(), "Address was not deleted!");
 }
 
@@ -138,12 +146,12 @@ fn company_with_all_data() {
         line6: Some("33506 LIBOURNE CEDEX".to_string()),
         line7: Some("France".to_string()),
     };
-    let converted_address = convert_to_iso(&address, AddressKind::Company);
+    let converted_address = convert_to_iso(&address, AddressKind::Company).unwrap();
     assert_eq!(converted_address.id, id);
     command_service
         .add_address(converted_address.clone())
         .expect("Failed to add address");
-    let stored_address = read_service.get_address(&id);
+    let stored_address = read_service.get_address(&id).unwrap();
     assert!(stored_address.is_some(), "Address not found in repository");
     let stored_address = stored_address.unwrap();
     assert_eq!(stored_address.id, id);
@@ -179,11 +187,13 @@ fn company_with_all_data() {
         country: Some("FR".to_string()),
         district_name: None,
         country_sub_division: None,
+        geolocation: None,
+        source_references: vec![],
     };
     command_service
         .update_address(address_update)
         .expect("Failed to update address");
-    let stored_address = read_service.get_address(&id);
+    let stored_address = read_service.get_address(&id).unwrap();
     assert!(stored_address.is_some(), "Address not found in repository");
     let stored_address = stored_address.unwrap();
     assert_eq!(stored_address.id, id);
@@ -204,7 +214,7 @@ fn company_with_all_data() {
     command_service
         .delete_address(&id)
         .expect("Failed to delete address");
-    let stored_address = read_service.get_address(&id);
+    let stored_address = read_service.get_address(&id).unwrap();
     assert!(
         stored_address.is_none(),
         "Address was not deleted from repository!"
@@ -224,7 +234,7 @@ fn private_individual_with_apartment() {
         line6: Some("75010 PARIS".to_string()),
         line7: Some("France".to_string()),
     };
-    let converted_address = convert_to_iso(&address, AddressKind::Particular);
+    let converted_address = convert_to_iso(&address, AddressKind::Particular).unwrap();
     assert_eq!(converted_address.id, id);
     assert_eq!(converted_address.room, Some("Apt. 12B".to_string()));
     assert_eq!(converted_address.floor, Some("3rd Floor".to_string()));
@@ -251,7 +261,7 @@ fn company_without_department() {
         line6: Some("64000 PAU".to_string()),
         line7: Some("France".to_string()),
     };
-    let converted_address = convert_to_iso(&address, AddressKind::Company);
+    let converted_address = convert_to_iso(&address, AddressKind::Company).unwrap();
     assert_eq!(converted_address.id, id);
     assert_eq!(
         converted_address.street_name,
@@ -276,7 +286,7 @@ fn private_individual_with_po_box() {
         line6: Some("31000 TOULOUSE".to_string()),
         line7: Some("France".to_string()),
     };
-    let converted_address = convert_to_iso(&address, AddressKind::Particular);
+    let converted_address = convert_to_iso(&address, AddressKind::Particular).unwrap();
     assert_eq!(converted_address.id, id);
     assert_eq!(converted_address.post_box, Some("BP 1234".to_string()));
     assert_eq!(converted_address.post_code, Some("31000".to_string()));
@@ -297,7 +307,7 @@ fn company_with_multiple_floors() {
         line6: Some("92800 PUTEAUX".to_string()),
         line7: Some("France".to_string()),
     };
-    let converted_address = convert_to_iso(&address, AddressKind::Company);
+    let converted_address = convert_to_iso(&address, AddressKind::Company).unwrap();
     assert_eq!(converted_address.id, id);
     assert_eq!(
         converted_address.department,
@@ -317,3 +327,277 @@ fn company_with_multiple_floors() {
     assert_eq!(converted_address.town_name, Some("PUTEAUX".to_string()));
     assert_eq!(converted_address.country, Some("FR".to_string()));
 }
+
+#[test]
+fn company_geocoded_on_add() {
+    let arc_repo = ArcInMemoryRepository::new();
+    let command_repo: Box<dyn AddressRepository + Send> = Box::new(arc_repo.clone());
+    let read_repo: Box<dyn ReadAddressRepository + Send> = Box::new(arc_repo.clone());
+    let read_service = AddressQueryService::new(read_repo);
+    let ban_index = BanIndex::new(vec![BanAddress {
+        id: "33243_1234_00022_bis".to_string(),
+        house_number: Some("22BIS".to_string()),
+        street_name: "Rue des Fleurs".to_string(),
+        post_code: "33500".to_string(),
+        town_name: "Libourne".to_string(),
+        insee_code: "33243".to_string(),
+        latitude: 44.915,
+        longitude: -0.244,
+    }]);
+    let mut command_service =
+        AddressCommandService::new(command_repo).with_geocoder(Arc::new(ban_index));
+    let id = Uuid::new_v4().to_string();
+    let address = FrenchAddress {
+        id: id.clone(),
+        line1: Some("DURAND SA".to_string()),
+        line2: None,
+        line3: None,
+        line4: Some("22BIS RUE DES FLEURS".to_string()),
+        line5: None,
+        line6: Some("33500 LIBOURNE".to_string()),
+        line7: Some("France".to_string()),
+    };
+    command_service
+        .add_address(convert_to_iso(&address, AddressKind::Company).unwrap())
+        .expect("Failed to add address");
+    let stored_address = read_service.get_address(&id).unwrap().unwrap();
+    let location = stored_address
+        .geolocation
+        .clone()
+        .expect("Address was not geocoded");
+    assert_eq!(location.ban_id, "33243_1234_00022_bis");
+    assert_eq!(location.latitude, 44.915);
+    assert_eq!(location.longitude, -0.244);
+
+    let moved = ISO20022Address {
+        building_number: Some("99".to_string()),
+        ..stored_address
+    };
+    let moved = command_service
+        .geocode_address(moved)
+        .expect("Failed to geocode address");
+    assert!(moved.geolocation.is_none());
+}
+
+#[test]
+fn statement_parties_deduplicated_on_import() {
+    let arc_repo = ArcInMemoryRepository::new();
+    let command_repo: Box<dyn AddressRepository + Send> = Box::new(arc_repo.clone());
+    let read_repo: Box<dyn ReadAddressRepository + Send> = Box::new(arc_repo.clone());
+    let read_service = AddressQueryService::new(read_repo);
+    let mut command_service = AddressCommandService::new(command_repo);
+    let party = |reference: &str, street: &str| ISO20022Address {
+        recipient_name: Some("DURAND SA".to_string()),
+        kind: AddressKind::Company,
+        street_name: Some(street.to_string()),
+        building_number: Some("22BIS".to_string()),
+        post_code: Some("33500".to_string()),
+        town_name: Some("LIBOURNE".to_string()),
+        country: Some("FR".to_string()),
+        source_references: vec![reference.to_string()],
+        ..Default::default()
+    };
+
+    let summary = command_service
+        .import_addresses(
+            vec![
+                party("STMT-1/E-1", "RUE DES FLEURS"),
+                party("STMT-1/E-2", "Rue des Fleurs"),
+            ],
+            read_service.get_all_addresses().unwrap(),
+        )
+        .expect("Failed to import addresses");
+    assert_eq!(
+        (summary.added, summary.merged, summary.unchanged),
+        (1, 1, 0)
+    );
+
+    let summary = command_service
+        .import_addresses(
+            vec![
+                party("STMT-1/E-2", "RUE DES FLEURS"),
+                party("STMT-2/E-1", "RUE DES FLEURS"),
+            ],
+            read_service.get_all_addresses().unwrap(),
+        )
+        .expect("Failed to import addresses");
+    assert_eq!(
+        (summary.added, summary.merged, summary.unchanged),
+        (0, 1, 1)
+    );
+
+    let stored = read_service.get_all_addresses().unwrap();
+    assert_eq!(stored.len(), 1);
+    assert!(!stored[0].id.is_empty());
+    assert_eq!(
+        stored[0].source_references,
+        vec!["STMT-1/E-1", "STMT-1/E-2", "STMT-2/E-1"]
+    );
+}
+
+#[test]
+fn csv_import_stores_valid_rows() {
+    let arc_repo = ArcInMemoryRepository::new();
+    let command_repo: Box<dyn AddressRepository + Send> = Box::new(arc_repo.clone());
+    let read_repo: Box<dyn ReadAddressRepository + Send> = Box::new(arc_repo.clone());
+    let read_service = AddressQueryService::new(read_repo);
+    let mut command_service = AddressCommandService::new(command_repo);
+    let input = "line1,line4,line6,line7,kind\n\
+                 DURAND SA,22BIS RUE DES FLEURS,33500 LIBOURNE,FRANCE,company\n\
+                 Jean Dupont,15 RUE DES LILAS,LYON,FRANCE,particular\n\
+                 Marie Martin,3 AVENUE FOCH,75016 PARIS,FRANCE,\n";
+    let options = CsvImportOptions::default().with_delimiter(",").unwrap();
+
+    let mut rejects = Vec::new();
+    let report = import_csv(input.as_bytes(), &mut rejects, &options, |batch| {
+        command_service
+            .add_addresses(batch)
+            .map_err(|e| e.to_string())
+    })
+    .expect("Failed to import CSV");
+
+    assert_eq!((report.rows, report.imported, report.rejected), (3, 2, 1));
+    let stored = read_service.get_all_addresses().unwrap();
+    assert_eq!(stored.len(), 2);
+    assert!(
+        stored
+            .iter()
+            .any(|a| a.recipient_name.as_deref() == Some("Marie Martin")
+                && a.kind == AddressKind::Particular)
+    );
+    assert!(
+        String::from_utf8(rejects)
+            .unwrap()
+            .contains("2,Jean Dupont,")
+    );
+}
+
+#[test]
+fn search_by_post_code_prefix_and_kind() {
+    let file = std::env::temp_dir().join(format!("search-{}.json", Uuid::new_v4()));
+    let mut file_repo = FileBasedAddressRepository::new(file.to_string_lossy().into_owned());
+    let mut arc_repo = ArcInMemoryRepository::new();
+    let addresses = [
+        ("DURAND SA", AddressKind::Company, "75008", "PARIS"),
+        ("Jean Martin", AddressKind::Particular, "75008", "PARIS"),
+        ("MARTIN CONSEIL", AddressKind::Company, "75016", "PARIS"),
+        ("ACME", AddressKind::Company, "69002", "LYON"),
+    ]
+    .map(|(name, kind, post_code, town)| ISO20022Address {
+        id: Uuid::new_v4().to_string(),
+        recipient_name: Some(name.to_string()),
+        kind,
+        post_code: Some(post_code.to_string()),
+        town_name: Some(town.to_string()),
+        country: Some("FR".to_string()),
+        ..Default::default()
+    });
+    file_repo.save_all(addresses.to_vec()).unwrap();
+    arc_repo.save_all(addresses.to_vec()).unwrap();
+
+    let query = AddressQuery {
+        post_code: Some("75".to_string()),
+        kind: Some(AddressKind::Company),
+        sort: SortField::RecipientName,
+        descending: true,
+        page_size: Some(1),
+        ..Default::default()
+    };
+    // Le dépôt fichier filtre lui-même, l'autre passe par `stream_all`
+    let repositories: [Box<dyn ReadAddressRepository>; 2] =
+        [Box::new(file_repo), Box::new(arc_repo)];
+    for repository in repositories {
+        let service = AddressQueryService::new(repository);
+        let page = service.search_addresses(&query).unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.addresses.len(), 1);
+        assert_eq!(
+            page.addresses[0].recipient_name.as_deref(),
+            Some("MARTIN CONSEIL")
+        );
+
+        let lyon = AddressQuery {
+            town: Some("Lyon".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(service.search_addresses(&lyon).unwrap().total, 1);
+    }
+    let page_zero = AddressQuery {
+        page: 0,
+        ..Default::default()
+    };
+    assert!(page_zero.validate().is_err());
+    for extension in ["", ".lock", ".bak", ".wal"] {
+        let _ = std::fs::remove_file(format!("{}{extension}", file.display()));
+    }
+}
+
+#[test]
+fn repository_errors_are_typed() {
+    let file = std::env::temp_dir().join(format!("errors-{}.json", Uuid::new_v4()));
+    let path = file.to_string_lossy().into_owned();
+    let repositories: [Box<dyn AddressRepository>; 2] = [
+        Box::new(InMemoryAddressRepository::new()),
+        Box::new(FileBasedAddressRepository::new(path.clone())),
+    ];
+    for repository in repositories {
+        let mut command_service = AddressCommandService::new(repository);
+        let address = ISO20022Address {
+            id: "1".to_string(),
+            ..Default::default()
+        };
+
+        command_service.add_address(address.clone()).unwrap();
+        assert_eq!(
+            command_service.add_address(address.clone()),
+            Err(RepositoryError::AlreadyExists("1".to_string()))
+        );
+        let other = ISO20022Address {
+            id: "2".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            command_service.add_addresses(vec![other.clone(), other]),
+            Err(RepositoryError::Conflict(_))
+        ));
+        command_service.delete_address("1").unwrap();
+        assert_eq!(
+            command_service.delete_address("1"),
+            Err(RepositoryError::NotFound("1".to_string()))
+        );
+        assert!(matches!(
+            command_service.update_address(address),
+            Err(RepositoryError::NotFound(_))
+        ));
+    }
+
+    // Un fichier illisible sans sauvegarde n'est jamais remplacé
+    let _ = std::fs::remove_file(format!("{path}.bak"));
+    std::fs::write(&file, "{not json").unwrap();
+    let mut file_repo = FileBasedAddressRepository::new(path.clone());
+    assert!(matches!(
+        file_repo.save(ISO20022Address::default()),
+        Err(RepositoryError::Serialization(_))
+    ));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "{not json");
+    // ... et n'est pas lu comme un dépôt vide
+    let read_service = AddressQueryService::new(Box::new(file_repo));
+    assert!(matches!(
+        read_service.get_all_addresses(),
+        Err(RepositoryError::Serialization(_))
+    ));
+    assert!(read_service.get_address("1").is_err());
+    assert!(
+        read_service
+            .search_addresses(&AddressQuery::default())
+            .is_err()
+    );
+    let stream: Vec<_> = read_service.stream_all_addresses().collect();
+    assert!(matches!(
+        stream[..],
+        [Err(RepositoryError::Serialization(_))]
+    ));
+    for extension in ["", ".lock", ".wal"] {
+        let _ = std::fs::remove_file(format!("{path}{extension}"));
+    }
+}
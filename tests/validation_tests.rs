@@ -1,6 +1,8 @@
 use fraddriso20022::domain::models::{FrenchAddress, ISO20022Address};
+use fraddriso20022::domain::postal_reference::PostalReference;
 use fraddriso20022::domain::validation::{
-    ValidationError, validate_french_address, validate_iso20022_address,
+    ValidationError, ValidationProfile, validate_french_address, validate_french_line,
+    validate_iso20022_address, validate_postal_consistency, validate_with_profile,
 };
 
 #[test]
@@ -40,6 +42,7 @@ fn test_french_address_validation_invalid_postal_code() {
 }
 
 #[test]
+#[allow(clippy::field_reassign_with_default)]
 fn test_iso20022_address_validation_invalid_country() {
     let mut iso = ISO20022Address::default();
     iso.id = "3".to_string();
@@ -52,3 +55,213 @@ fn test_iso20022_address_validation_invalid_country() {
     let res = validate_iso20022_address(&iso);
     assert!(matches!(res, Err(ValidationError::InvalidCountryCode { value }) if value == "FRANCE"));
 }
+
+#[test]
+fn test_french_address_validation_unknown_department() {
+    let addr = FrenchAddress {
+        id: "4".to_string(),
+        line1: Some("Exemple".to_string()),
+        line4: Some("10 rue de la Paix".to_string()),
+        line6: Some("00000 X".to_string()),
+        line7: Some("France".to_string()),
+        ..Default::default()
+    };
+
+    let res = validate_french_address(&addr);
+    assert!(matches!(res, Err(ValidationError::InvalidPostalCode { value }) if value == "00000 X"));
+}
+
+#[test]
+fn test_french_address_postal_code_town_mismatch() {
+    let reference = PostalReference::from_csv_str(
+        "#Code_commune_INSEE;Nom_de_la_commune;Code_postal;Libellé_d_acheminement;Ligne_5
+75101;PARIS 01;75001;PARIS;
+69381;LYON 01;69001;LYON;",
+    )
+    .unwrap();
+    let addr = FrenchAddress {
+        id: "5".to_string(),
+        line1: Some("Exemple".to_string()),
+        line4: Some("10 rue de la Paix".to_string()),
+        line6: Some("75001 LYON".to_string()),
+        line7: Some("France".to_string()),
+        ..Default::default()
+    };
+
+    let res = validate_postal_consistency(&addr, &reference);
+    assert!(matches!(
+        res,
+        Err(ValidationError::PostalCodeTownMismatch { post_code, town, expected_towns, suggested_post_codes, .. })
+            if post_code == "75001"
+                && town == "LYON"
+                && expected_towns == vec!["PARIS".to_string()]
+                && suggested_post_codes == vec!["69001".to_string()]
+    ));
+
+    let cedex = FrenchAddress {
+        line6: Some("75001 PARIS CEDEX 01".to_string()),
+        ..addr
+    };
+    assert!(validate_postal_consistency(&cedex, &reference).is_ok());
+}
+
+#[test]
+fn test_french_address_mismatch_suggests_closest_town() {
+    let reference = PostalReference::from_csv_str(
+        "#Code_commune_INSEE;Nom_de_la_commune;Code_postal;Libellé_d_acheminement;Ligne_5
+33243;LIBOURNE;33500;LIBOURNE;",
+    )
+    .unwrap();
+    let addr = FrenchAddress {
+        id: "6".to_string(),
+        line1: Some("Exemple".to_string()),
+        line6: Some("33500 LIBOUNRE".to_string()),
+        line7: Some("France".to_string()),
+        ..Default::default()
+    };
+
+    let res = validate_postal_consistency(&addr, &reference);
+    assert!(matches!(
+        res,
+        Err(ValidationError::PostalCodeTownMismatch { suggested_town: Some(town), .. })
+            if town == "LIBOURNE"
+    ));
+}
+
+#[test]
+fn test_french_address_validation_foreign_destination() {
+    let addr = FrenchAddress {
+        id: "10".to_string(),
+        line1: Some("Hans Müller".to_string()),
+        line2: None,
+        line3: None,
+        line4: Some("Unter den Linden 77".to_string()),
+        line5: None,
+        line6: Some("10117 BERLIN".to_string()),
+        line7: Some("ALLEMAGNE".to_string()),
+    };
+    assert!(validate_french_address(&addr).is_ok());
+
+    let uk = FrenchAddress {
+        line6: Some("LONDON 12345".to_string()),
+        line7: Some("ROYAUME-UNI".to_string()),
+        ..addr
+    };
+    assert!(
+        matches!(validate_french_address(&uk), Err(ValidationError::InvalidPostalCode { value }) if value == "LONDON 12345")
+    );
+
+    // Pays absent de la table : donné par son code ISO, la ligne 6 n'est pas contrôlée
+    let br = FrenchAddress {
+        line6: Some("01310-100 SAO PAULO".to_string()),
+        line7: Some("BR".to_string()),
+        ..uk
+    };
+    assert!(validate_french_address(&br).is_ok());
+
+    // ... mais son nom, que la conversion ne saurait pas coder, est refusé
+    let bresil = FrenchAddress {
+        line7: Some("BRESIL".to_string()),
+        ..br
+    };
+    assert!(
+        matches!(validate_french_address(&bresil), Err(ValidationError::InvalidCountryCode { value }) if value == "BRESIL")
+    );
+}
+
+#[test]
+fn test_french_line_validation() {
+    assert!(validate_french_line(4, Some("22BIS RUE DES FLEURS"), None).is_ok());
+    assert!(matches!(
+        validate_french_line(1, Some("SOCIETE DES ETABLISSEMENTS DURAND ET FILS"), None),
+        Err(ValidationError::TooLong { field: "line1", .. })
+    ));
+    assert!(validate_french_line(6, Some("33500 LIBOURNE"), Some("FRANCE")).is_ok());
+    assert!(matches!(
+        validate_french_line(6, Some("1000 BRUXELLES"), None),
+        Err(ValidationError::InvalidPostalCode { .. })
+    ));
+    assert!(validate_french_line(6, Some("1000 BRUXELLES"), Some("BELGIQUE")).is_ok());
+    assert!(matches!(
+        validate_french_line(6, None, None),
+        Err(ValidationError::EmptyField { field: "line6" })
+    ));
+    assert!(validate_french_line(7, Some("BR"), None).is_ok());
+    assert!(matches!(
+        validate_french_line(7, Some("BRESIL"), None),
+        Err(ValidationError::InvalidCountryCode { .. })
+    ));
+    for number in [0, 8] {
+        assert!(matches!(
+            validate_french_line(number, Some("FRANCE"), None),
+            Err(ValidationError::UnknownLine { number: n }) if n == number
+        ));
+    }
+    assert!(validate_french_line(6, Some("01310-100 SAO PAULO"), Some("BR")).is_ok());
+}
+
+#[test]
+fn test_iso20022_address_validation_post_code_per_country() {
+    let nl = ISO20022Address {
+        id: "11".to_string(),
+        post_code: Some("1012 AB".to_string()),
+        town_name: Some("AMSTERDAM".to_string()),
+        country: Some("NL".to_string()),
+        ..Default::default()
+    };
+    assert!(validate_iso20022_address(&nl).is_ok());
+
+    let us = ISO20022Address {
+        post_code: Some("1012 AB".to_string()),
+        country: Some("US".to_string()),
+        ..nl
+    };
+    assert!(
+        matches!(validate_iso20022_address(&us), Err(ValidationError::InvalidPostalCode { value }) if value == "1012 AB")
+    );
+}
+
+#[test]
+fn test_validation_profiles() {
+    let hybrid = ISO20022Address {
+        id: "12".to_string(),
+        town_name: Some("LIBOURNE".to_string()),
+        country: Some("FR".to_string()),
+        ..Default::default()
+    };
+    let lines = vec!["22BIS RUE DES FLEURS".to_string()];
+    assert!(validate_with_profile(&hybrid, &lines, ValidationProfile::Cbpr).is_ok());
+
+    let too_many_lines = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+    assert!(matches!(
+        validate_with_profile(&hybrid, &too_many_lines, ValidationProfile::Cbpr),
+        Err(ValidationError::TooLong {
+            field: "address_lines",
+            max_len: 2,
+            actual_len: 3
+        })
+    ));
+    assert!(validate_with_profile(&hybrid, &too_many_lines, ValidationProfile::Basic).is_ok());
+
+    let unstructured = ISO20022Address {
+        town_name: None,
+        ..hybrid.clone()
+    };
+    assert!(matches!(
+        validate_with_profile(&unstructured, &lines, ValidationProfile::Sepa),
+        Err(ValidationError::UnstructuredAddress)
+    ));
+
+    let accented = ISO20022Address {
+        town_name: Some("SAINT-ÉTIENNE".to_string()),
+        ..hybrid
+    };
+    assert!(validate_with_profile(&accented, &[], ValidationProfile::Cbpr).is_ok());
+    assert!(matches!(
+        validate_with_profile(&accented, &[], ValidationProfile::Sepa),
+        Err(ValidationError::InvalidCharacters {
+            field: "town_name",
+            ..
+        })
+    ));
+}